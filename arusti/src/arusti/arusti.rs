@@ -6,9 +6,62 @@ extern crate nalgebra;
 pub use nalgebra::{Vector3, UnitQuaternion};
 
 mod types;
-pub use types::{ElementType,Element,Figure,Sequence};
+pub use types::{ElementType,Element,FlatElement,RollDirection,TurnRollDirection,RadiusSize,CombiningPoint,Figure,LayoutCommand,Sequence};
 
 pub mod olan;
 
+pub mod geometry;
+pub use geometry::{FigureGeometry,FlightState,LineDirection};
+
+pub mod diagram;
+pub use diagram::{DiagramOptions,Form};
+
+pub mod pilot_errors;
+pub use pilot_errors::{PilotError,ErrorModel,InjectedError};
+
 pub mod data_generation;
-pub use data_generation::{DataPointGenerator,DataPoint,PerformanceOptions};
\ No newline at end of file
+pub use data_generation::{DataPointGenerator,DataPointGeneratorBuilder,DataPointIter,DataPoint,PerformanceOptions,SensorNoise};
+
+pub mod geo;
+pub use geo::{GeoOrigin,LocalFrame};
+
+pub mod box_frame;
+pub use box_frame::{BoxFrame,Category};
+
+pub mod export;
+pub use export::ExportFormat;
+
+pub mod import;
+pub use import::{ImportFormat,ImportOptions};
+
+pub mod profiles;
+pub use profiles::AircraftProfile;
+
+pub mod segmentation;
+pub use segmentation::{Segment,FigureSegment,SegmentationOptions};
+
+pub mod fitting;
+pub use fitting::ElementFit;
+pub mod recognition;
+pub use recognition::Candidate;
+
+pub mod judging;
+pub use judging::{JudgingOptions,FigureMark};
+
+pub mod seq_file;
+pub use seq_file::SeqFile;
+
+pub mod plot;
+pub use plot::{PlotFormat,PlotOptions,Projection};
+
+pub mod scoring;
+pub use scoring::{Coefficients,ScoringOptions,SequenceScore};
+
+pub mod rules;
+pub use rules::{RuleSet,ProgramRules,Violation};
+
+pub mod diff;
+pub use diff::{SequenceDiff,FigureDiff,FigureChange};
+
+pub mod editing;
+pub use editing::EditReport;
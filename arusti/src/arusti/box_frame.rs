@@ -0,0 +1,250 @@
+extern crate nalgebra as na;
+use na::Vector3;
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::data_generation::DataPoint;
+
+/// Samples outside the box closer together than this are one event (s)
+const EVENT_GAP: f32 = 1.0;
+
+/// Competition category, which sets the height of the box
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum Category {
+    Unlimited,
+    Advanced,
+    Intermediate,
+    Sports,
+    Primary,
+    }
+
+impl FromStr for Category {
+    type Err = String;
+
+    fn from_str(category: &str) -> Result<Category, String> {
+        match category.to_lowercase().as_str() {
+            "unlimited" => Ok(Category::Unlimited),
+            "advanced" => Ok(Category::Advanced),
+            "intermediate" => Ok(Category::Intermediate),
+            "sports" | "sportsman" => Ok(Category::Sports),
+            "primary" => Ok(Category::Primary),
+            _ => Err(format!("Unknown category: {}", category)),
+            }
+        }
+    }
+
+impl Category {
+    /// Lowest and highest altitudes above the ground (m)
+    pub fn altitudes(&self) -> (f32, f32) {
+        match self {
+            Category::Unlimited => (100.0, 1000.0),
+            Category::Advanced => (200.0, 1100.0),
+            Category::Intermediate => (300.0, 1100.0),
+            Category::Sports => (400.0, 1200.0),
+            Category::Primary => (450.0, 1200.0),
+            }
+        }
+    }
+
+/// Direction of flight relative to the box
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum BoxDirection {
+    /// Along the X axis towards the A end
+    TowardsA,
+    /// Along the X axis towards the B end
+    TowardsB,
+    /// Along the Y axis towards the judges
+    TowardsJudges,
+    /// Along the Y axis away from the judges
+    AwayFromJudges,
+    }
+
+/// Edge of the box crossed by a box out
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum BoxEdge {
+    /// The A end of the X axis
+    A,
+    /// The B end of the X axis
+    B,
+    /// The side nearest the judges
+    Near,
+    /// The side furthest from the judges
+    Far,
+    Top,
+    Bottom,
+    }
+
+impl fmt::Display for BoxEdge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BoxEdge::A => write!(f, "A end"),
+            BoxEdge::B => write!(f, "B end"),
+            BoxEdge::Near => write!(f, "near side"),
+            BoxEdge::Far => write!(f, "far side"),
+            BoxEdge::Top => write!(f, "top"),
+            BoxEdge::Bottom => write!(f, "bottom"),
+            }
+        }
+    }
+
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum BoxEventKind {
+    /// Outside the sides, ends or top of the box
+    BoxOut,
+    /// Below the bottom of the box
+    LowAltitude,
+    }
+
+/// A period spent outside one edge of the box
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub struct BoxEvent {
+    pub edge: BoxEdge,
+    /// Index of the first sample outside the box
+    pub start: usize,
+    /// Index one past the last sample outside the box
+    pub end: usize,
+    /// Furthest distance outside the box (m)
+    pub distance: f32,
+    }
+
+impl BoxEvent {
+    pub fn kind(&self) -> BoxEventKind {
+        match self.edge {
+            BoxEdge::Bottom => BoxEventKind::LowAltitude,
+            _ => BoxEventKind::BoxOut,
+            }
+        }
+    }
+
+/// The aerobatic box and the frame fixed to it.
+///
+/// Box coordinates have x along the X axis from the A end to the B end, y along the Y axis away from the judges
+/// and z up, from the centre of the box at ground level. The judges sit on the -y side with the B end on their right.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub struct BoxFrame {
+    /// Centre of the box at ground level, in the earth frame
+    pub centre: Vector3<f32>,
+    /// Heading of the X axis from A to B (degrees clockwise from north)
+    pub heading: f32,
+    /// Length along the X axis (m)
+    pub length: f32,
+    /// Width along the Y axis (m)
+    pub width: f32,
+    /// Lowest altitude above the ground (m)
+    pub bottom: f32,
+    /// Highest altitude above the ground (m)
+    pub top: f32,
+    /// Distance of the judge line from the near side of the box (m)
+    pub judge_distance: f32,
+    }
+
+impl Default for BoxFrame {
+    fn default() -> BoxFrame {
+        BoxFrame::new(Category::Unlimited)
+        }
+    }
+
+impl BoxFrame {
+    /// A 1000 m box for the category, centred on the earth frame origin with the X axis north
+    pub fn new(category: Category) -> BoxFrame {
+        let (bottom, top) = category.altitudes();
+        BoxFrame {
+            centre: Vector3::zeros(),
+            heading: 0.0,
+            length: 1000.0,
+            width: 1000.0,
+            bottom,
+            top,
+            judge_distance: 150.0,
+            }
+        }
+
+    /// Convert an earth frame position to box coordinates
+    pub fn to_box(&self, position: &Vector3<f32>) -> Vector3<f32> {
+        let (sh, ch) = self.heading.to_radians().sin_cos();
+        let offset = position - self.centre;
+        Vector3::new(ch * offset[0] + sh * offset[1], sh * offset[0] - ch * offset[1], -offset[2])
+        }
+
+    /// Convert box coordinates to an earth frame position
+    pub fn to_earth(&self, position: &Vector3<f32>) -> Vector3<f32> {
+        let (sh, ch) = self.heading.to_radians().sin_cos();
+        self.centre + Vector3::new(ch * position[0] + sh * position[1], sh * position[0] - ch * position[1], -position[2])
+        }
+
+    /// Earth heading for flying in a direction (degrees clockwise from north)
+    pub fn earth_heading(&self, direction: BoxDirection) -> f32 {
+        let offset = match direction {
+            BoxDirection::TowardsB => 0.0,
+            BoxDirection::TowardsJudges => 90.0,
+            BoxDirection::TowardsA => 180.0,
+            BoxDirection::AwayFromJudges => 270.0,
+            };
+        (self.heading + offset).rem_euclid(360.0)
+        }
+
+    /// Position of the judges at ground level on the centre of the judge line, in the earth frame.
+    ///  The judge line runs parallel to the X axis
+    pub fn judge_position(&self) -> Vector3<f32> {
+        self.to_earth(&Vector3::new(0.0, -(self.width / 2.0 + self.judge_distance), 0.0))
+        }
+
+    /// Distance outside each edge of the box, positive when outside
+    fn edge_distances(&self, position: &Vector3<f32>) -> [(BoxEdge, f32); 6] {
+        let position = self.to_box(position);
+        [
+            (BoxEdge::A, -position[0] - self.length / 2.0),
+            (BoxEdge::B, position[0] - self.length / 2.0),
+            (BoxEdge::Near, -position[1] - self.width / 2.0),
+            (BoxEdge::Far, position[1] - self.width / 2.0),
+            (BoxEdge::Top, position[2] - self.top),
+            (BoxEdge::Bottom, self.bottom - position[2]),
+            ]
+        }
+
+    /// Whether an earth frame position is inside the box
+    pub fn contains(&self, position: &Vector3<f32>) -> bool {
+        self.edge_distances(position).iter().all(|(_, distance)| *distance <= 0.0)
+        }
+
+    /// Find the periods spent outside each edge of the box
+    pub fn box_events(&self, points: &[DataPoint]) -> Vec<BoxEvent> {
+        let mut events: Vec<BoxEvent> = Vec::new();
+        for (i, point) in points.iter().enumerate() {
+            for (edge, distance) in self.edge_distances(&point.position).iter().cloned() {
+                if distance <= 0.0 {
+                    continue;
+                    }
+                let recent = events.iter_mut().rev()
+                    .find(|event| event.edge == edge && point.time - points[event.end - 1].time <= EVENT_GAP);
+                match recent {
+                    Some(event) => {
+                        event.end = i + 1;
+                        event.distance = event.distance.max(distance);
+                        }
+                    None => events.push(BoxEvent { edge, start: i, end: i + 1, distance }),
+                    }
+                }
+            }
+        events
+        }
+    }
@@ -1,337 +1,539 @@
 use std::mem;
+use std::collections::VecDeque;
+use std::iter::Enumerate;
+use std::slice;
 
 extern crate nalgebra as na;
-use na::{Vector3, UnitQuaternion, Matrix3};
+use na::{Unit, Vector3, UnitQuaternion};
 
-use crate::types::{ElementType, Element, Figure, Sequence};
+use crate::types::{Element, Figure, Sequence, TurnRollDirection};
+use crate::pilot_errors::{PilotError, ErrorModel, InjectedError};
+use crate::box_frame::{BoxFrame, BoxDirection};
 
+/// Steps in which the rates build up as an autorotation is entered
+const AUTOROTATION_ENTRY_STEPS: usize = 10;
+
+/// Sample of the aircraft state. Position is in the earth frame (x north, y east, z down)
 #[derive(Debug)]
 pub struct DataPoint {
+    /// Time of the sample (s)
+    pub time: f32,
     pub position: Vector3<f32>,
     pub attitude: UnitQuaternion<f32>
     }
 
+/// Gaussian sensor noise applied to generated DataPoints, reproducible from its seed
+pub struct SensorNoise {
+    state: u64,
+    /// Standard deviation of the position error on each axis (m)
+    pub position_std_dev: f32,
+    /// Standard deviation of the attitude error about each axis (degrees)
+    pub attitude_std_dev: f32,
+    }
+
+impl SensorNoise {
+    pub fn new(seed: u64, position_std_dev: f32, attitude_std_dev: f32) -> SensorNoise {
+        SensorNoise {
+            state: seed,
+            position_std_dev,
+            attitude_std_dev,
+            }
+        }
+
+    /// Next uniform sample in (0,1] from a SplitMix64 sequence
+    fn next_uniform(&mut self) -> f32 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        ((z >> 40) as f32 + 1.0) / (1u64 << 24) as f32
+        }
+
+    /// Next standard normal sample by the Box-Muller transform
+    fn next_gaussian(&mut self) -> f32 {
+        let radius = (-2.0 * self.next_uniform().ln()).sqrt();
+        let angle = 2.0 * std::f32::consts::PI * self.next_uniform();
+        radius * angle.cos()
+        }
+
+    fn next_vector(&mut self, std_dev: f32) -> Vector3<f32> {
+        Vector3::<f32>::new(self.next_gaussian(), self.next_gaussian(), self.next_gaussian()) * std_dev
+        }
+
+    pub fn apply(&mut self, point: &mut DataPoint) {
+        point.position += self.next_vector(self.position_std_dev);
+        let attitude_error = self.next_vector(self.attitude_std_dev.to_radians());
+        point.attitude *= UnitQuaternion::from_scaled_axis(attitude_error);
+        }
+    }
+
 pub struct PerformanceOptions {
     pub roll_rate: f32,
     pub snap_rate: f32,
     pub spin_rate: f32,
+    pub pitch_rate: f32,
     pub turn_rate: f32,
     pub hesitation_time: f32,
+    /// Pitch of the nose off the roll axis once a snap has broken (degrees)
+    pub snap_entry_pitch: f32,
+    /// Time for a snap to build up to the snap rate (s)
+    pub snap_entry_time: f32,
     }
 
 pub struct DataPointGenerator {
+    time: f64,
+    start_time: f64,
+    sample_count: u64,
     position: Vector3<f32>,
     body_velocity: Vector3<f32>,
-    attitude: Vector3<f32>,
+    attitude: UnitQuaternion<f32>,
+    /// Rates about the body axes (deg/s)
     omega_b: Vector3<f32>,
+    /// Rates about the earth axes (deg/s)
+    omega_e: Vector3<f32>,
     wind: Vector3<f32>,
     sample_rate: f32,
     performance: PerformanceOptions,
-    points: Vec<DataPoint>,
+    /// Points generated but not yet taken, kept when an iterator is dropped part way through a figure
+    points: VecDeque<DataPoint>,
+    error_model: ErrorModel,
+    active_errors: Vec<PilotError>,
+    injected_errors: Vec<InjectedError>,
     }
 
-impl DataPointGenerator {
+/// Builder for a DataPointGenerator with a configured initial state
+pub struct DataPointGeneratorBuilder {
+    performance: PerformanceOptions,
+    velocity: Vector3<f32>,
+    wind: Vector3<f32>,
+    sample_rate: f32,
+    position: Vector3<f32>,
+    attitude: UnitQuaternion<f32>,
+    heading: Option<f32>,
+    start_time: f32,
+    }
 
-    pub fn new(initial_velocity: Vector3<f32>, wind: Vector3<f32>, performance: PerformanceOptions) -> DataPointGenerator {
-        DataPointGenerator {
-            position: Vector3::<f32>::new(0.0,0.0,0.0),
-            body_velocity: initial_velocity,
-            attitude: Vector3::<f32>::new(0.0,0.0,0.0),
-            omega_b: Vector3::<f32>::new(0.0,0.0,0.0),
-            wind: wind,
+impl DataPointGeneratorBuilder {
+    pub fn new(performance: PerformanceOptions) -> DataPointGeneratorBuilder {
+        DataPointGeneratorBuilder {
+            performance,
+            velocity: Vector3::<f32>::zeros(),
+            wind: Vector3::<f32>::zeros(),
             sample_rate: 50.0,
-            performance: performance,
-            points: Vec::<DataPoint>::new(),
+            position: Vector3::<f32>::zeros(),
+            attitude: UnitQuaternion::<f32>::identity(),
+            heading: None,
+            start_time: 0.0,
             }
         }
-    
-    pub fn generate_points(&mut self, sequence: &Sequence) -> Vec<DataPoint> {
-        for figure in sequence {
-            self.generate_figure(figure);
-            self.normalise_attitude();
-            }
-        return mem::replace(&mut self.points,Vec::<DataPoint>::new());
+
+    /// Initial velocity in the body frame (m/s)
+    pub fn velocity(mut self, velocity: Vector3<f32>) -> DataPointGeneratorBuilder {
+        self.velocity = velocity;
+        self
         }
-    
-    fn normalise_attitude(&mut self) {
-        self.attitude = self.attitude.map( |e| (e + 360.0) % 360.0 );
-        if 90.0 < self.attitude[1] && self.attitude[1] < 270.0 {
-            // Pitch inverted. Normalise such that roll causes inversion
-            self.attitude[0] = (self.attitude[0] + 180.0) % 360.0;
-            if self.attitude[1] <= 180.0 {
-                self.attitude[1] = 180.0 - self.attitude[1];
-                }
-            else {
-                self.attitude[1] = 540.0 - self.attitude[1];
-                }
-            self.attitude[2] = (self.attitude[2] + 180.0) % 360.0;
-            }
+
+    /// Wind velocity in the earth frame (m/s)
+    pub fn wind(mut self, wind: Vector3<f32>) -> DataPointGeneratorBuilder {
+        self.wind = wind;
+        self
         }
 
-    fn get_dcm_body2earth(&self) -> Matrix3<f32> {
-        let roll_rad  = self.attitude[0].to_radians();
-        let theta_rad = self.attitude[1].to_radians();
-        let yaw_rad   = self.attitude[2].to_radians();
+    /// Number of DataPoints generated per second (Hz)
+    pub fn sample_rate(mut self, sample_rate: f32) -> DataPointGeneratorBuilder {
+        self.sample_rate = sample_rate;
+        self
+        }
 
-        let cr = roll_rad.cos();
-        let sr = roll_rad.sin();
+    /// Starting position in the earth frame, e.g. box entry at -height (m)
+    pub fn position(mut self, position: Vector3<f32>) -> DataPointGeneratorBuilder {
+        self.position = position;
+        self
+        }
 
-        let ct = theta_rad.cos();
-        let st = theta_rad.sin();
+    /// Initial attitude of the body frame relative to the earth frame
+    pub fn attitude(mut self, attitude: UnitQuaternion<f32>) -> DataPointGeneratorBuilder {
+        self.attitude = attitude;
+        self
+        }
 
-        let cy = yaw_rad.cos();
-        let sy = yaw_rad.sin();
+    /// Initial heading (degrees from north). Overrides the yaw of the initial attitude
+    pub fn heading(mut self, heading: f32) -> DataPointGeneratorBuilder {
+        self.heading = Some(heading);
+        self
+        }
 
-        // Build the rotation matrix
-        let rot_x = Matrix3::<f32>::new(
-             1.0, 0.0, 0.0,
-             0.0,  cr, -sr,
-             0.0,  sr,  cr,
-            );
-        let rot_y = Matrix3::<f32>::new(
-             ct, 0.0,  st,
-            0.0, 1.0, 0.0,
-            -st, 0.0,  ct,
-            );
-        let rot_z = Matrix3::<f32>::new(
-             cy, -sy, 0.0,
-             sy,  cy, 0.0,
-            0.0, 0.0, 1.0
-            );
+    /// Starting position in box coordinates, heading along one of the box axes
+    pub fn box_entry(self, box_frame: &BoxFrame, position: Vector3<f32>, direction: BoxDirection) -> DataPointGeneratorBuilder {
+        self.position(box_frame.to_earth(&position))
+            .heading(box_frame.earth_heading(direction))
+        }
 
-        // Multiply the matrices together to get the combined matrix
-        (rot_z * rot_y) * rot_x
+    /// Time of the first generated DataPoint (s)
+    pub fn start_time(mut self, start_time: f32) -> DataPointGeneratorBuilder {
+        self.start_time = start_time;
+        self
         }
 
-    fn get_jacobian_body2earth(&self) -> Matrix3<f32> {
-        let phi = self.attitude[0].to_radians();
-        let theta = self.attitude[1].to_radians();
+    pub fn build(self) -> DataPointGenerator {
+        let attitude = match self.heading {
+            Some(heading) => {
+                let (roll, pitch, _) = self.attitude.euler_angles();
+                UnitQuaternion::from_euler_angles(roll, pitch, heading.to_radians())
+                }
+            None => self.attitude,
+            };
 
-        let sp = phi.sin();
-        let cp = phi.cos();
+        DataPointGenerator {
+            time: self.start_time as f64,
+            start_time: self.start_time as f64,
+            sample_count: 0,
+            position: self.position,
+            body_velocity: self.velocity,
+            attitude,
+            omega_b: Vector3::<f32>::zeros(),
+            omega_e: Vector3::<f32>::zeros(),
+            wind: self.wind,
+            sample_rate: self.sample_rate,
+            performance: self.performance,
+            points: VecDeque::<DataPoint>::new(),
+            error_model: ErrorModel::new(),
+            active_errors: Vec::<PilotError>::new(),
+            injected_errors: Vec::<InjectedError>::new(),
+            }
+        }
+    }
 
-        let st = theta.sin();
-        let ct = theta.cos();
-        let tt = theta.tan();
+impl DataPointGenerator {
 
-        Matrix3::<f32>::new(
-            1.0, sp*tt, cp*tt,
-            0.0,   cp ,  -sp ,
-            0.0, sp/ct, cp/ct
-            )
+    pub fn new(initial_velocity: Vector3<f32>, wind: Vector3<f32>, performance: PerformanceOptions) -> DataPointGenerator {
+        DataPointGeneratorBuilder::new(performance)
+            .velocity(initial_velocity)
+            .wind(wind)
+            .build()
         }
-    
-    fn get_invjacobian_earth2body(&self) -> Matrix3<f32> {
-        let phi = self.attitude[0].to_radians();
-        let theta = self.attitude[1].to_radians();
 
-        let sp = phi.sin();
-        let cp = phi.cos();
+    pub fn builder(performance: PerformanceOptions) -> DataPointGeneratorBuilder {
+        DataPointGeneratorBuilder::new(performance)
+        }
 
-        let st = theta.sin();
-        let ct = theta.cos();
+    /// Set the pilot errors to inject into subsequently generated flights
+    pub fn set_error_model(&mut self, error_model: ErrorModel) {
+        self.error_model = error_model;
+        }
 
-        Matrix3::<f32>::new(
-            1.0, 0.0,   -st,
-            0.0,  cp, ct*sp,
-            0.0, -sp, ct*cp
-            )
+    /// Take the record of errors injected into the flights generated so far
+    pub fn take_injected_errors(&mut self) -> Vec<InjectedError> {
+        mem::take(&mut self.injected_errors)
+        }
+    
+    pub fn generate_points(&mut self, sequence: &Sequence) -> Vec<DataPoint> {
+        self.iter_points(sequence).collect()
         }
 
-    /// Generate the next DataPoint based on the current velocity and attitude rate
-    fn generate_next_point(&mut self) {
-        self.position += self.get_dcm_body2earth() * self.body_velocity * self.sample_rate.recip();
-        self.attitude += self.get_jacobian_body2earth() * self.omega_b * self.sample_rate.recip();
+    /// Lazily generate the DataPoints for sequence. Points are generated a figure at a time as the
+    ///  iterator is advanced, so the whole flight is never held in memory. Points left from a figure
+    ///  started by an earlier iterator come first
+    pub fn iter_points<'a>(&'a mut self, sequence: &'a Sequence) -> DataPointIter<'a> {
+        DataPointIter {
+            generator: self,
+            figures: sequence.figures.iter().enumerate(),
+            }
+        }
+    
+    /// Set the angular rates held for the following propagation, about the body axes and the earth axes (deg/s)
+    fn set_rates(&mut self, omega_b: Vector3<f32>, omega_e: Vector3<f32>) {
+        self.omega_b = omega_b;
+        self.omega_e = omega_e;
+        }
 
-        self.points.push(DataPoint {
-            position: self.position,
-            attitude: UnitQuaternion::from_euler_angles(
-                self.attitude[0].to_radians(),
-                self.attitude[1].to_radians(),
-                self.attitude[2].to_radians()
-                ),
-            });
+    /// Attitude after holding the current rates for dt from the current state
+    fn get_attitude_after(&self, dt: f32) -> UnitQuaternion<f32> {
+        let earth_rotation = UnitQuaternion::from_scaled_axis(self.omega_e.map(|e| e.to_radians()) * dt);
+        let body_rotation = UnitQuaternion::from_scaled_axis(self.omega_b.map(|e| e.to_radians()) * dt);
+        earth_rotation * self.attitude * body_rotation
         }
 
-    fn generate_figure(&mut self, figure: &Figure) {
+    /// Advance the state by dt. Attitude is propagated exactly, position by RK4.
+    ///  The aircraft flies through the air, so it drifts with the wind
+    fn step(&mut self, dt: f32) {
+        let k1 = self.attitude * self.body_velocity;
+        let k23 = self.get_attitude_after(0.5 * dt) * self.body_velocity;
+        let k4 = self.get_attitude_after(dt) * self.body_velocity;
+
+        self.position += (k1 + k23 * 4.0 + k4) * (dt / 6.0) + self.wind * dt;
+        self.attitude = self.get_attitude_after(dt);
+        self.attitude.renormalize_fast();
+        self.time += dt as f64;
+        }
+
+    /// Hold the current rates for duration, generating DataPoints on the sample clock.
+    ///  The state finishes exactly at the end of the duration, between samples if necessary
+    fn propagate(&mut self, duration: f32) {
+        let end_time = self.time + duration as f64;
+        loop {
+            let next_sample_time = self.start_time + (self.sample_count + 1) as f64 / self.sample_rate as f64;
+            if next_sample_time > end_time {
+                break;
+                }
+            self.step((next_sample_time - self.time) as f32);
+            self.sample_count += 1;
+            self.points.push_back(DataPoint {
+                time: self.time as f32,
+                position: self.position,
+                attitude: self.attitude,
+                });
+            }
+        self.step((end_time - self.time) as f32);
+        }
+
+    fn generate_figure(&mut self, figure_idx: usize, figure: &Figure) {
         // Need to do some tailoring for some elements
         // Includes radius matching and line length matching
-        for element in figure {
-            eprintln!("Elem: {:?}",element);
-            self.generate_element(&element);
-            eprintln!("Att: {:?}",self.attitude.data);
-            eprintln!("Vel: {:?}",self.body_velocity.data);
+        for (element_idx,element) in figure.elements.iter().enumerate() {
+            log::debug!("Elem: {:?}",element);
+            self.active_errors = self.error_model.errors_for(figure_idx, element_idx);
+            let start_index = self.sample_count as usize;
+            let (flown, mut applied) = self.apply_element_errors(element);
+            applied.extend(self.generate_element(&flown));
+            self.active_errors.clear();
+            for error in applied {
+                self.injected_errors.push(InjectedError {
+                    figure: figure_idx,
+                    element: element_idx,
+                    error,
+                    downgrade: error.downgrade(),
+                    start_index,
+                    end_index: self.sample_count as usize,
+                    });
+                }
+            log::debug!("Att: {:?}",self.attitude.euler_angles());
+            log::debug!("Vel: {:?}",self.body_velocity.data);
             }
         }
 
-    fn generate_element(&mut self, element: &Element) {
-        match element.elem_type {
-            ElementType::Line => self.generate_line(element),
-            ElementType::Radius => self.generate_radius(element),
-            ElementType::Turn => self.generate_turn(element),
-            ElementType::Roll => self.generate_roll(element),
-            ElementType::Flick => self.generate_flick(element),
-            ElementType::Spin => self.generate_spin(element),
-            _ => unreachable!()
+    /// Modify the element to be flown for errors that change its definition.
+    ///  Returns the element to fly and the errors that changed it
+    fn apply_element_errors(&self, element: &Element) -> (Element, Vec<PilotError>) {
+        let mut flown = *element;
+        let mut applied = Vec::new();
+        if let Element::Roll { angle, divisions, .. } = &mut flown {
+            let mut parts = element.roll_parts();
+            for error in &self.active_errors {
+                match *error {
+                    PilotError::RollRotation(delta) => {
+                        *angle += delta;
+                        applied.push(*error);
+                        }
+                    // A continuous roll has no points to miss
+                    PilotError::MissingPoints(count) if count > 0 && parts > 1 => {
+                        parts = parts.saturating_sub(count).max(1);
+                        applied.push(*error);
+                        }
+                    _ => {}
+                    }
+                }
+            // Keep the number of parts when the roll is over or under rotated
+            *divisions = Element::hesitation_divisions(*angle, parts);
             }
+        (flown, applied)
         }
-    
-    fn generate_line(&mut self, element: &Element) {
-        // Angle defines angle between forward direction and ground
-        // Argument is length (m)
-        self.omega_b = Vector3::<f32>::new(0.0,0.0,0.0);
 
-        let mut current_time = self.sample_rate.recip();
+    /// Angle each hesitation stops short by
+    fn hesitation_shortfall(&self) -> f32 {
+        self.active_errors.iter()
+            .map(|error| match error { PilotError::HesitationShort(angle) => *angle, _ => 0.0 })
+            .sum()
+        }
 
-        while current_time < 1.0 {
-            current_time += self.sample_rate.recip();
-            self.generate_next_point();
-            }
-        return;
-        /*
-        if element.angle == 0.0 {
-            // CGT line, Set velocity, compute attitude with wind
-            self.velocity = Vector3::<f32>::new(0.0,0.0,0.0);
+    /// Body rates to drift a line off its intended pitch and heading over the given time
+    fn line_drift_rates(&self, line_time: f32) -> Vector3<f32> {
+        let mut omega_b = Vector3::<f32>::zeros();
+        for error in &self.active_errors {
+            match error {
+                PilotError::OffPitch(angle) => omega_b[1] += angle / line_time,
+                PilotError::OffHeading(angle) => omega_b[2] += angle / line_time,
+                _ => {}
+                }
             }
-        else {
-            // ZLA line. Set attitude, compute velocity with wind
-            if element.angle.abs() > 50.0 {
-                // Vertical line
+        omega_b
+        }
 
-                }
-            else {
-                self.attitude[0] = if element.inverted { 180.0 } else { 0.0 };
-                }
+    /// Generate points for an element, returning the active errors it applied
+    fn generate_element(&mut self, element: &Element) -> Vec<PilotError> {
+        match *element {
+            Element::Line { .. } => self.generate_line(),
+            Element::Radius { angle, .. } => self.generate_radius(angle),
+            Element::Turn { angle, rolls, roll_direction, .. } => self.generate_turn(angle, rolls, roll_direction),
+            Element::Roll { angle, direction, .. } => self.generate_roll(direction.sign() * angle, element.roll_parts()),
+            Element::Flick { angle, direction, negative, .. } => self.generate_flick(direction.sign() * angle, negative),
+            Element::Spin { angle, direction, negative, .. } => self.generate_spin(direction.sign() * angle, negative),
+            Element::Stall { .. } | Element::Combining(_) => unreachable!()
             }
-            */
-        // Attitude and velocity set, propogate for appropriate length
-        // TODO: Speed model?
-        
         }
     
-    fn generate_radius(&mut self, element: &Element) {
-        assert_eq!(element.elem_type, ElementType::Radius);
-
-        // Use turn rate * 2 for now...
-        let radius_time = element.angle.abs() * (self.performance.turn_rate * 2.0).recip();
-        self.omega_b = Vector3::<f32>::new(0.0,(self.performance.turn_rate * 2.0).copysign(element.angle),0.0);
-
-        let mut current_time = self.sample_rate.recip();
-
-        while current_time < radius_time {
-            current_time += self.sample_rate.recip();
-            self.generate_next_point();
+    /// Generate points for a line, flown for a second at the entry attitude. Returns the drift errors applied
+    fn generate_line(&mut self) -> Vec<PilotError> {
+        let line_time = 1.0;
+        self.set_rates(self.line_drift_rates(line_time), Vector3::<f32>::zeros());
+        self.propagate(line_time);
+        self.active_errors.iter()
+            .filter(|error| matches!(error, PilotError::OffPitch(_) | PilotError::OffHeading(_)))
+            .copied()
+            .collect()
+        }
+    
+    /// Generate points for a pull (+ve) or push (-ve) through an angle. Returns the uneven radius errors applied
+    fn generate_radius(&mut self, angle: f32) -> Vec<PilotError> {
+        let pitch_rate = self.performance.pitch_rate.copysign(angle);
+
+        // An uneven radius flies the second half at a different size for the same speed
+        let second_half_scale = self.active_errors.iter()
+            .map(|error| match error { PilotError::UnevenRadius(scale) => *scale, _ => 1.0 })
+            .product::<f32>();
+
+        for rate in [pitch_rate, pitch_rate / second_half_scale].iter() {
+            let half_time = 0.5 * angle.abs() / rate.abs();
+            self.set_rates(Vector3::<f32>::new(0.0,*rate,0.0), Vector3::<f32>::zeros());
+            self.propagate(half_time);
             }
+        self.active_errors.iter().filter(|error| matches!(error, PilotError::UnevenRadius(_))).copied().collect()
         }
 
-    /// Generate points for the turn defined by element
-    fn generate_turn(&mut self, element: &Element) {
-        assert_eq!(element.elem_type, ElementType::Turn);
-        if element.argument == 0.0 {
+    /// Generate points for a turn through an angle, right if it is positive, rolling towards the inside or outside
+    fn generate_turn(&mut self, angle: f32, rolls: f32, roll_direction: TurnRollDirection) -> Vec<PilotError> {
+        let turn_time = angle.abs() * self.performance.turn_rate.recip();
+        let yaw_rate = self.performance.turn_rate.copysign(angle);
+        if rolls == 0.0 {
             // No rolls in turn, generate 75° entry/exit rolls
+            let bank = 75.0_f32.copysign(angle);
 
             // Entry roll
-            self.generate_roll(&Element {
-                elem_type: ElementType::Roll,
-                inverted: element.inverted,
-                angle: 75.0,
-                argument: 0.0,
-                });
+            self.fly_roll(bank);
 
             // Main turn
-            let turn_time = element.angle.abs() * self.performance.turn_rate.recip();
-            let attitude_rate = Vector3::<f32>::new( 0.0, 0.0, self.performance.turn_rate.copysign(element.angle) );
-
-            let mut current_time = self.sample_rate.recip();
-
-            while current_time < turn_time {
-                current_time += self.sample_rate.recip();
-                self.omega_b = self.get_invjacobian_earth2body() * attitude_rate;
-                self.generate_next_point();
-                }
+            self.set_rates(Vector3::<f32>::zeros(), Vector3::<f32>::new(0.0,0.0,yaw_rate));
+            self.propagate(turn_time);
 
             // Exit roll
-            self.generate_roll(&Element {
-                elem_type: ElementType::Roll,
-                inverted: element.inverted,
-                angle: -75.0,
-                argument: 0.0,
-                });
+            self.fly_roll(-bank);
             }
         else {
-            // Rolling turn
-            let turn_time = element.angle.abs() * self.performance.turn_rate.recip();
-
-            let attitude_rate = Vector3::<f32>::new(
-                element.argument / turn_time,
-                0.0,
-                self.performance.turn_rate.copysign(element.angle)
-                );
+            // Rolling turn, rolling to the right towards the inside of a turn to the right
+            let inside = if roll_direction == TurnRollDirection::Inside { 1.0 } else { -1.0 };
+            let roll_rate = inside * (rolls * 360.0).copysign(angle) / turn_time;
 
-            let mut current_time = self.sample_rate.recip();
-
-            while current_time < turn_time {
-                current_time += self.sample_rate.recip();
-                self.omega_b = self.get_invjacobian_earth2body() * attitude_rate;
-                self.generate_next_point();
-                }
+            self.set_rates(Vector3::<f32>::new(roll_rate,0.0,0.0), Vector3::<f32>::new(0.0,0.0,yaw_rate));
+            self.propagate(turn_time);
             }
+        Vec::new()
         }
 
-    /// Generate points for the roll defined by Element
+    /// Roll continuously through an angle, to the left if it is negative
+    fn fly_roll(&mut self, angle: f32) {
+        let roll_time = angle.abs() * self.performance.roll_rate.recip();
+        self.set_rates(Vector3::<f32>::new(self.performance.roll_rate.copysign(angle),0.0,0.0), Vector3::<f32>::zeros());
+        self.propagate(roll_time);
+        }
+
+    /// Generate points for a roll through an angle, to the left if it is negative, flown in a number of equal parts.
     ///  The aircraft velocity remains unchanged (i.e. follows CGT), therefore it needs to be set
-    ///  as part of the tailoring in the generate_figure
-    fn generate_roll(&mut self, element: &Element) {
-        assert_eq!(element.elem_type, ElementType::Roll);
+    ///  as part of the tailoring in the generate_figure. Returns the hesitation errors applied
+    fn generate_roll(&mut self, angle: f32, parts: u8) -> Vec<PilotError> {
+        if parts <= 1 {
+            self.fly_roll(angle);
+            return Vec::new();
+            }
+        let step = angle / parts as f32;
+        let shortfall = self.hesitation_shortfall().copysign(angle);
+        for _i in 1..parts {
+            self.fly_roll(step - shortfall);
+            // Advance on CGT for hesitation_time
+            self.set_rates(Vector3::<f32>::zeros(), Vector3::<f32>::zeros());
+            self.propagate(self.performance.hesitation_time);
+            }
+        // Final rolling element, making up any shortfall
+        self.fly_roll(step + shortfall * (parts - 1) as f32);
+        // In a hesitation roll, should hesitations modulo 180° be judged on ZLA?
+        self.active_errors.iter().filter(|error| matches!(error, PilotError::HesitationShort(_))).copied().collect()
+        }
 
-        if element.argument == 0.0 {
-            // Pure roll
-            let roll_time = element.angle.abs() * self.performance.roll_rate.recip();
-            self.omega_b = Vector3::<f32>::new(self.performance.roll_rate.copysign(element.angle),0.0,0.0);
+    /// Generate points for a snap roll. The nose breaks off the axis of the roll by the snap entry pitch as the
+    ///  autorotation builds up to the snap rate over the snap entry time
+    fn generate_flick(&mut self, angle: f32, negative: bool) -> Vec<PilotError> {
+        let performance = &self.performance;
+        let (rate, tilt, entry_time) = (performance.snap_rate, performance.snap_entry_pitch, performance.snap_entry_time);
+        self.generate_autorotation(angle, negative, rate, tilt, entry_time);
+        Vec::new()
+        }
 
-            let mut current_time = self.sample_rate.recip();
+    /// Generate points for a spin. Spins are flown between the lines of the figure like a slow snap roll
+    ///  with a deeper stall, the wing drop and the pull to the vertical are not modelled
+    fn generate_spin(&mut self, angle: f32, negative: bool) -> Vec<PilotError> {
+        self.generate_autorotation(angle, negative, self.performance.spin_rate, 40.0, 0.0);
+        Vec::new()
+        }
 
-            while current_time < roll_time {
-                current_time += self.sample_rate.recip();
-                self.generate_next_point();
+    /// Autorotate about axes tilted from the body x axis by tilt (degrees), nose up for a positive rotation
+    ///  and nose down for a negative one, rolling at the rate. Each half turn is flown in two parts, first about
+    ///  an axis in the plane of symmetry and then about the axis that ends in the attitude of a plain roll.
+    ///  Both parts turn through the same angle at the same tilt, so the rates are steady through the rotation
+    ///  once they have built up evenly from zero over the entry time (s)
+    fn generate_autorotation(&mut self, angle: f32, negative: bool, rate: f32, tilt: f32, entry_time: f32) {
+        let pitch = if negative { -1.0 } else { 1.0 };
+        let axis = Unit::new_normalize(Vector3::<f32>::new(
+            tilt.to_radians().cos().copysign(angle),
+            tilt.to_radians().sin() * pitch,
+            0.0,
+            ));
+        let half_turns = (angle.abs() / 180.0).ceil().max(1.0);
+        let half_turn = angle / half_turns;
+        // Rotation that twists the body x axis through half of the half turn
+        let quarter = (half_turn.abs() / 4.0).to_radians();
+        let rotation = 2.0 * quarter.sin().atan2(quarter.cos() * tilt.to_radians().cos()).to_degrees();
+        let time = rotation * tilt.to_radians().cos() / rate;
+
+        let first = UnitQuaternion::from_axis_angle(&axis, rotation.to_radians());
+        let roll = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), half_turn.to_radians());
+        let second = (first.inverse() * roll).axis().unwrap_or(axis);
+
+        // Building up evenly, the entry turns through half the angle it would at the full rate
+        let entry_time = entry_time.min(2.0 * time);
+        if entry_time > 0.0 {
+            for step in 0..AUTOROTATION_ENTRY_STEPS {
+                let fraction = (step as f32 + 0.5) / AUTOROTATION_ENTRY_STEPS as f32;
+                self.set_rates(axis.into_inner() * rotation / time * fraction, Vector3::<f32>::zeros());
+                self.propagate(entry_time / AUTOROTATION_ENTRY_STEPS as f32);
                 }
             }
-        else {
-            // Hesitation roll, recursive call
-            let divisions = element.argument as i8;
-            for _i in 1..divisions {
-                self.generate_roll(&Element {
-                    elem_type: ElementType::Roll,
-                    inverted: element.inverted,
-                    angle: element.angle / element.argument,
-                    argument: 0.0,
-                    });
-                // Advance on CGT for hesitation_time
-                self.omega_b = Vector3::<f32>::new(0.0,0.0,0.0);
-                let mut current_time = self.sample_rate.recip();
-                while current_time < self.performance.hesitation_time {
-                    current_time += self.sample_rate.recip();
-                    self.generate_next_point();
-                    }
-                }
-            // Generate final rolling element
-            self.generate_roll(&Element {
-                elem_type: ElementType::Roll,
-                inverted: element.inverted,
-                angle: element.angle / element.argument,
-                argument: 0.0,
-                });
+        for half_turn_index in 0..half_turns as usize {
+            let first_time = if half_turn_index == 0 { time - 0.5 * entry_time } else { time };
+            self.set_rates(axis.into_inner() * rotation / time, Vector3::<f32>::zeros());
+            self.propagate(first_time);
+            self.set_rates(second.into_inner() * rotation / time, Vector3::<f32>::zeros());
+            self.propagate(time);
             }
-        // In a hesitation roll, should hesitations modulo 180° be judged on ZLA?
-        self.normalise_attitude();
         }
 
-    fn generate_flick(&mut self, element: &Element) {
-        unimplemented!();
-        }
+    }
 
-    fn generate_spin(&mut self, element: &Element) {
-        unimplemented!();
-        }
+/// Iterator over the DataPoints of a sequence, generated figure by figure
+pub struct DataPointIter<'a> {
+    generator: &'a mut DataPointGenerator,
+    figures: Enumerate<slice::Iter<'a,Figure>>,
+    }
+
+impl<'a> Iterator for DataPointIter<'a> {
+    type Item = DataPoint;
 
-    }
\ No newline at end of file
+    fn next(&mut self) -> Option<DataPoint> {
+        loop {
+            if let Some(point) = self.generator.points.pop_front() {
+                return Some(point);
+                }
+            let (figure_idx, figure) = self.figures.next()?;
+            self.generator.generate_figure(figure_idx, figure);
+            }
+        }
+    }
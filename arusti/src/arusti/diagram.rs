@@ -0,0 +1,448 @@
+use std::io;
+use std::io::Write;
+use std::str::FromStr;
+
+extern crate nalgebra as na;
+use na::{Vector2, Vector3, Unit, UnitQuaternion};
+
+use crate::types::{Element, ElementType, Figure, LayoutCommand, Sequence, TurnRollDirection};
+
+/// Length of entry and exit lines (diagram units)
+const ENTRY_LENGTH: f32 = 10.0;
+/// Length of lines within figures
+const LINE_LENGTH: f32 = 20.0;
+/// Radius of loops and turns
+const RADIUS: f32 = 10.0;
+/// Radius of the hook drawn for tailslides
+const HOOK_RADIUS: f32 = 3.0;
+/// Length of the cap drawn for hammerheads
+const CAP_LENGTH: f32 = 4.0;
+/// Gap between figures, and the step of the `>` layout command
+const FIGURE_GAP: f32 = 10.0;
+/// Line drawn between consecutive rolls
+const ROLL_SPACING: f32 = 6.0;
+/// Size of roll, flick and spin symbols
+const SYMBOL_SIZE: f32 = 5.0;
+/// Drawn length of Y axis flight relative to X axis flight
+const DEPTH_SCALE: f32 = 0.5;
+/// Angle Y axis flight away from the judges is drawn at, above the X axis (degrees)
+const DEPTH_ANGLE: f32 = 30.0;
+/// Angle between the points of drawn arcs (degrees)
+const ARC_STEP: f32 = 5.0;
+/// Space around the drawing in the SVG
+const MARGIN: f32 = 5.0;
+
+/// View the sequence is drawn from
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Default)]
+#[derive(PartialEq)]
+pub enum Form {
+    /// The judges' view
+    #[default]
+    B,
+    /// The pilot's view, the mirror image of form B
+    C,
+    }
+
+impl FromStr for Form {
+    type Err = String;
+
+    fn from_str(form: &str) -> Result<Form, String> {
+        match form.to_lowercase().as_str() {
+            "b" => Ok(Form::B),
+            "c" => Ok(Form::C),
+            _ => Err(format!("Unknown form: {}", form)),
+            }
+        }
+    }
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct DiagramOptions {
+    pub form: Form,
+    /// SVG pixels per diagram unit
+    pub scale: f32,
+    /// Label each figure with its number
+    pub numbers: bool,
+    }
+
+impl Default for DiagramOptions {
+    fn default() -> DiagramOptions {
+        DiagramOptions {
+            form: Form::B,
+            scale: 3.0,
+            numbers: true,
+            }
+        }
+    }
+
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum Stroke {
+    /// Positive flight
+    Solid,
+    /// Inverted lines and pushed radii
+    Dashed,
+    /// Connectors between figures
+    Dotted,
+    }
+
+/// Part of a diagram, in diagram units with x to the right and y up
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum Shape {
+    /// Flight path, roll arrow or connector
+    Path { points: Vec<Vector2<f32>>, stroke: Stroke },
+    /// Arrowheads and flick and spin triangles. Negative flicks and spins are not filled
+    Polygon { points: Vec<Vector2<f32>>, filled: bool },
+    /// Start of a figure
+    Dot { centre: Vector2<f32>, radius: f32 },
+    /// Figure numbers and roll fractions
+    Label { position: Vector2<f32>, text: String },
+    }
+
+impl Shape {
+    fn points(&self) -> Vec<Vector2<f32>> {
+        match self {
+            Shape::Path { points, .. } | Shape::Polygon { points, .. } => points.clone(),
+            Shape::Dot { centre, radius } => vec![centre - Vector2::repeat(*radius), centre + Vector2::repeat(*radius)],
+            Shape::Label { position, text } => vec![*position, position + Vector2::new(2.5 * text.len() as f32, 4.0)],
+            }
+        }
+    }
+
+/// Fraction of a revolution as written on diagrams, e.g. "3/4" or "1 1/2", to the nearest eighth
+fn format_turns(angle: f32) -> String {
+    let eighths = (angle.abs() / 45.0).round() as u32;
+    let (whole, mut numerator) = (eighths / 8, eighths % 8);
+    let mut denominator = 8;
+    while numerator > 0 && numerator % 2 == 0 {
+        numerator /= 2;
+        denominator /= 2;
+        }
+    match (whole, numerator) {
+        (whole, 0) => whole.to_string(),
+        (0, numerator) => format!("{}/{}", numerator, denominator),
+        (whole, numerator) => format!("{} {}/{}", whole, numerator, denominator),
+        }
+    }
+
+/// Draws figures with a pen that flies the elements in three dimensions and projects its path onto the diagram
+struct Pen {
+    /// Position on the diagram
+    position: Vector2<f32>,
+    /// Direction of flight, with x towards the B end, y away from the judges and z up
+    forward: Vector3<f32>,
+    /// Direction of the top of the aircraft
+    up: Vector3<f32>,
+    /// Stroke of the last line, for the spacing between rolls
+    line_stroke: Stroke,
+    /// -1 when Y axis flight is drawn switched
+    depth_sign: f32,
+    shapes: Vec<Shape>,
+    }
+
+impl Pen {
+    fn new() -> Pen {
+        Pen {
+            position: Vector2::zeros(),
+            forward: Vector3::x(),
+            up: Vector3::z(),
+            line_stroke: Stroke::Solid,
+            depth_sign: 1.0,
+            shapes: Vec::new(),
+            }
+        }
+
+    fn project(&self, vector: &Vector3<f32>) -> Vector2<f32> {
+        let (sin, cos) = DEPTH_ANGLE.to_radians().sin_cos();
+        let depth = self.depth_sign * DEPTH_SCALE * vector[1];
+        Vector2::new(vector[0] + depth * cos, vector[2] + depth * sin)
+        }
+
+    /// Direction of flight on the diagram and the normal to it on the left
+    fn axes(&self) -> (Vector2<f32>, Vector2<f32>) {
+        let direction = self.project(&self.forward).try_normalize(1.0e-6).unwrap_or_else(Vector2::x);
+        (direction, Vector2::new(-direction[1], direction[0]))
+        }
+
+    fn path(&mut self, points: Vec<Vector2<f32>>, stroke: Stroke) {
+        self.shapes.push(Shape::Path { points, stroke });
+        }
+
+    fn label(&mut self, position: Vector2<f32>, text: String) {
+        self.shapes.push(Shape::Label { position, text });
+        }
+
+    fn line(&mut self, length: f32, stroke: Stroke) {
+        let start = self.position;
+        self.position += self.project(&self.forward) * length;
+        self.path(vec![start, self.position], stroke);
+        self.line_stroke = stroke;
+        }
+
+    /// Rotate the pen about an axis through a centre, given relative to the pen
+    fn arc(&mut self, axis: &Vector3<f32>, angle: f32, centre: &Vector3<f32>, stroke: Stroke) {
+        let axis = Unit::new_normalize(*axis);
+        let steps = (angle.abs() / ARC_STEP).ceil().max(1.0) as usize;
+        let start = self.position;
+        let points: Vec<Vector2<f32>> = (0..=steps)
+            .map(|i| {
+                let rotation = UnitQuaternion::from_axis_angle(&axis, (angle * i as f32 / steps as f32).to_radians());
+                start + self.project(&(centre - rotation * centre))
+                })
+            .collect();
+        let rotation = UnitQuaternion::from_axis_angle(&axis, angle.to_radians());
+        self.forward = rotation * self.forward;
+        self.up = rotation * self.up;
+        self.position = *points.last().unwrap();
+        self.path(points, stroke);
+        }
+
+    /// Pull (+ve) or push (-ve) through an angle
+    fn radius(&mut self, angle: f32, radius: f32, stroke: Stroke) {
+        let right = self.forward.cross(&self.up);
+        self.arc(&right, angle, &(self.up * radius.copysign(angle)), stroke);
+        }
+
+    /// Turn right (+ve) or left (-ve) through an angle about the vertical
+    fn turn(&mut self, angle: f32) {
+        let horizontal = Vector3::new(self.forward[0], self.forward[1], 0.0).try_normalize(1.0e-6).unwrap_or_else(Vector3::x);
+        let right = horizontal.cross(&Vector3::z());
+        self.arc(&Vector3::z(), -angle, &(right * RADIUS.copysign(angle)), Stroke::Solid);
+        }
+
+    fn roll(&mut self, angle: f32) {
+        let rotation = UnitQuaternion::from_axis_angle(&Unit::new_normalize(self.forward), angle.to_radians());
+        self.up = rotation * self.up;
+        }
+
+    fn arrowhead(&mut self, tip: Vector2<f32>, direction: Vector2<f32>) {
+        let side = Vector2::new(-direction[1], direction[0]);
+        let base = tip - direction * 1.5;
+        self.shapes.push(Shape::Polygon { points: vec![tip, base + side * 0.8, base - side * 0.8], filled: true });
+        }
+
+    /// Roll arrows on the left of the line for +ve rolls, with a tick for each hesitation
+    fn roll_arrows(&mut self, angle: f32, divisions: u32) {
+        let (direction, normal) = self.axes();
+        let normal = normal * angle.signum();
+        let turns = angle.abs() / 360.0;
+        let full = turns.floor() as usize;
+        let partial = turns - full as f32;
+        let count = full + if partial > 1.0e-3 { 1 } else { 0 };
+
+        let mut tip = self.position;
+        for i in 0..count {
+            let centre = self.position + direction * (i as f32 * 2.0);
+            let fraction = if i < full { 1.0 } else { partial };
+            let tail = if i < full { centre - normal * SYMBOL_SIZE } else { centre };
+            tip = centre + normal * SYMBOL_SIZE;
+            self.path(vec![tail, tip], Stroke::Solid);
+            self.arrowhead(tip, normal);
+
+            let ticks = (fraction * divisions as f32).round() as u32;
+            if divisions > 1 {
+                for tick in 0..ticks {
+                    let at = tail + (tip - tail) * ((tick as f32 + 0.5) / ticks as f32);
+                    self.path(vec![at - direction * 1.0, at + direction * 1.0], Stroke::Solid);
+                    }
+                }
+            }
+
+        let label = if divisions > 1 {
+            let points = (turns * divisions as f32).round() as u32;
+            if points == divisions { divisions.to_string() } else { format!("{}x{}", points, divisions) }
+            }
+        else if partial > 1.0e-3 && (partial - 0.5).abs() > 1.0e-3 {
+            format_turns(angle)
+            }
+        else {
+            return;
+            };
+        self.label(tip + normal * 2.0, label);
+        }
+
+    /// Triangle for a flick, on the line, or a spin, at the corner of the line. Negative flicks and spins are not filled
+    fn triangle(&mut self, angle: f32, negative: bool, spin: bool) {
+        let (direction, normal) = self.axes();
+        let normal = normal * if angle < 0.0 { -1.0 } else { 1.0 };
+        let p = self.position;
+        let points = if spin {
+            vec![p, p - direction * SYMBOL_SIZE, p + normal * SYMBOL_SIZE * 1.2]
+            }
+        else {
+            vec![p - direction * SYMBOL_SIZE * 0.6, p + direction * SYMBOL_SIZE * 0.6, p + normal * SYMBOL_SIZE * 1.2]
+            };
+        let tip = points[2];
+        self.shapes.push(Shape::Polygon { points, filled: !negative });
+        if (angle.abs() - 360.0).abs() > 1.0e-3 {
+            self.label(tip + normal * 2.0, format_turns(angle));
+            }
+        }
+
+    /// Apply the layout commands and move to the start of a figure
+    fn start_figure(&mut self, figure: &Figure, first: bool) {
+        let mut heading = Vector3::new(self.forward[0], self.forward[1], 0.0).try_normalize(1.0e-6).unwrap_or_else(Vector3::x);
+        let mut gap = if first { 0.0 } else { FIGURE_GAP };
+        for command in &figure.layout {
+            match *command {
+                LayoutCommand::EntryUpwind => heading = Vector3::x(),
+                LayoutCommand::EntryDownwind => heading = -Vector3::x(),
+                LayoutCommand::EntryTowardsJudges => heading = -Vector3::y(),
+                LayoutCommand::EntryAwayFromJudges => heading = Vector3::y(),
+                LayoutCommand::SwitchYAxis => self.depth_sign = -self.depth_sign,
+                LayoutCommand::SwitchXAxis => heading[0] = -heading[0],
+                LayoutCommand::MoveForward(steps) => gap += steps as f32 * FIGURE_GAP,
+                LayoutCommand::MoveBy(x, y) => self.position += Vector2::new(x as f32, -y as f32),
+                LayoutCommand::CurveTo(x, y) => {
+                    let start = self.position;
+                    let end = start + Vector2::new(x as f32, -y as f32);
+                    let control = (start + end) / 2.0 + Vector2::new(start[1] - end[1], end[0] - start[0]) * 0.25;
+                    let points = (0..=10)
+                        .map(|i| {
+                            let t = i as f32 / 10.0;
+                            start * (1.0 - t).powi(2) + control * (2.0 * t * (1.0 - t)) + end * t.powi(2)
+                            })
+                        .collect();
+                    self.path(points, Stroke::Dotted);
+                    self.position = end;
+                    },
+                }
+            }
+        self.forward = heading;
+        self.position += self.project(&heading) * gap;
+        let inverted = figure.elements.first().is_some_and(Element::inverted);
+        self.up = if inverted { -Vector3::z() } else { Vector3::z() };
+        }
+
+    fn draw_figure(&mut self, figure: &Figure, number: Option<usize>) {
+        self.shapes.push(Shape::Dot { centre: self.position, radius: 1.2 });
+        if let Some(number) = number {
+            self.label(self.position + Vector2::new(-2.0, 3.0), number.to_string());
+            }
+
+        let last = figure.elements.len().saturating_sub(1);
+        let mut rolling = false;
+        for (i, element) in figure.elements.iter().enumerate() {
+            let is_rolling = element.is_rolling();
+            if is_rolling && rolling {
+                self.line(ROLL_SPACING, self.line_stroke);
+                }
+            rolling = is_rolling;
+
+            match *element {
+                Element::Line { inverted, .. } => {
+                    let length = if i == 0 || i == last { ENTRY_LENGTH } else { LINE_LENGTH };
+                    self.line(length, if inverted { Stroke::Dashed } else { Stroke::Solid });
+                    },
+                Element::Radius { angle, .. } => {
+                    self.radius(angle, RADIUS, if angle < 0.0 { Stroke::Dashed } else { Stroke::Solid });
+                    },
+                Element::Turn { angle, rolls, roll_direction, .. } => {
+                    self.turn(angle / 2.0);
+                    if rolls != 0.0 {
+                        let sign = if roll_direction == TurnRollDirection::Outside { -1.0 } else { 1.0 };
+                        self.roll_arrows(sign * rolls * 360.0, 1);
+                        }
+                    self.turn(angle / 2.0);
+                    },
+                Element::Roll { angle, direction, divisions, .. } => {
+                    self.roll_arrows(direction.sign() * angle, divisions.max(1) as u32);
+                    self.roll(direction.sign() * angle);
+                    },
+                Element::Flick { angle, direction, negative, .. } | Element::Spin { angle, direction, negative, .. } => {
+                    self.triangle(direction.sign() * angle, negative, element.elem_type() == ElementType::Spin);
+                    self.roll(direction.sign() * angle);
+                    },
+                Element::Stall { yaw, pitch } => {
+                    if yaw != 0.0 {
+                        let start = self.position;
+                        self.position += self.project(&self.up) * CAP_LENGTH;
+                        self.path(vec![start, self.position], Stroke::Solid);
+                        let rotation = UnitQuaternion::from_axis_angle(&Unit::new_normalize(self.up), -yaw.to_radians());
+                        self.forward = rotation * self.forward;
+                        }
+                    if pitch != 0.0 {
+                        self.radius(pitch, HOOK_RADIUS, Stroke::Solid);
+                        }
+                    },
+                Element::Combining(_) => {},
+                }
+            }
+
+        let (_, normal) = self.axes();
+        self.path(vec![self.position - normal * 3.0, self.position + normal * 3.0], Stroke::Solid);
+        }
+    }
+
+/// Draw the Aresti diagram of a sequence, honouring its layout commands.
+///  Layout offsets are in form B directions, so form C mirrors them with the rest of the diagram
+pub fn draw(sequence: &Sequence, options: &DiagramOptions) -> Vec<Shape> {
+    let mut pen = Pen::new();
+    for (i, figure) in sequence.figures.iter().enumerate() {
+        pen.start_figure(figure, i == 0);
+        pen.draw_figure(figure, if options.numbers { Some(i + 1) } else { None });
+        }
+
+    let mut shapes = pen.shapes;
+    if options.form == Form::C {
+        let mirror = |point: &mut Vector2<f32>| point[0] = -point[0];
+        for shape in shapes.iter_mut() {
+            match shape {
+                Shape::Path { points, .. } | Shape::Polygon { points, .. } => points.iter_mut().for_each(mirror),
+                Shape::Dot { centre, .. } => mirror(centre),
+                Shape::Label { position, .. } => mirror(position),
+                }
+            }
+        }
+    shapes
+    }
+
+fn format_points(points: &[Vector2<f32>]) -> String {
+    points.iter().map(|point| format!("{:.2},{:.2}", point[0], -point[1])).collect::<Vec<String>>().join(" ")
+    }
+
+/// Write the Aresti diagram of a sequence as SVG
+pub fn write_svg<W: Write>(sequence: &Sequence, options: &DiagramOptions, mut writer: W) -> io::Result<()> {
+    let shapes = draw(sequence, options);
+    let (min, max) = shapes.iter()
+        .flat_map(|shape| shape.points())
+        .fold((Vector2::repeat(f32::INFINITY), Vector2::repeat(f32::NEG_INFINITY)), |(min, max), point| (min.inf(&point), max.sup(&point)));
+    let (min, max) = if shapes.is_empty() { (Vector2::zeros(), Vector2::zeros()) } else { (min, max) };
+    let size = max - min + Vector2::repeat(2.0 * MARGIN);
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" viewBox="{:.2} {:.2} {:.2} {:.2}">"#,
+        size[0] * options.scale, size[1] * options.scale, min[0] - MARGIN, -max[1] - MARGIN, size[0], size[1])?;
+    writeln!(writer, r#"<g fill="none" stroke="black" stroke-width="0.5" stroke-linecap="round" stroke-linejoin="round" font-family="sans-serif" font-size="4">"#)?;
+    for shape in &shapes {
+        match shape {
+            Shape::Path { points, stroke } => {
+                let dash = match stroke {
+                    Stroke::Solid => "",
+                    Stroke::Dashed => r#" stroke-dasharray="2,1.5""#,
+                    Stroke::Dotted => r#" stroke-dasharray="0.5,1.5""#,
+                    };
+                writeln!(writer, r#"<polyline points="{}"{}/>"#, format_points(points), dash)?;
+                },
+            Shape::Polygon { points, filled } => {
+                writeln!(writer, r#"<polygon points="{}" fill="{}"/>"#, format_points(points), if *filled { "black" } else { "white" })?;
+                },
+            Shape::Dot { centre, radius } => {
+                writeln!(writer, r#"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="black"/>"#, centre[0], -centre[1], radius)?;
+                },
+            Shape::Label { position, text } => {
+                writeln!(writer, r#"<text x="{:.2}" y="{:.2}" fill="black" stroke="none">{}</text>"#, position[0], -position[1], text)?;
+                },
+            }
+        }
+    writeln!(writer, "</g>")?;
+    writeln!(writer, "</svg>")?;
+    Ok(())
+    }
@@ -0,0 +1,346 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::olan::try_parse_sequence;
+use crate::recognition::roll_set_notation;
+use crate::types::{Element, ElementType, Figure, LayoutCommand, RollDirection, Sequence};
+
+/// Difference inside a figure found in both sequences
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum FigureChange {
+    /// Different rolls on a line of the figure, numbered from 0 at the entry line as in `Figure::roll_sets`.
+    ///  The rolls are written in OLAN
+    RollSet { line: usize, before: String, after: String },
+    /// The same rolls on a line, with some flown in the other direction
+    RollDirection { line: usize, before: String, after: String },
+    EntryInversion { before: bool, after: bool },
+    ExitInversion { before: bool, after: bool },
+    /// Pull (+ve) or push (-ve) of each radius in the figure (degrees)
+    Radii { before: Vec<f32>, after: Vec<f32> },
+    /// Turns of the figure, with their rolls
+    Turns { before: Vec<String>, after: Vec<String> },
+    /// Layout commands written before the figure, in OLAN
+    Layout { before: Vec<String>, after: Vec<String> },
+    /// Elements of the figure, when none of the other changes describe the difference
+    Elements { before: Vec<String>, after: Vec<String> },
+    }
+
+/// Figure added, removed, moved or changed between two sequences. Indices count from 0
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FigureDiff {
+    /// Figure of the new sequence
+    Added { index: usize },
+    /// Figure of the old sequence
+    Removed { index: usize },
+    /// The same figure at another position
+    Moved { from: usize, to: usize },
+    /// Figure of the same shape with different rolls, inversions or layout
+    Modified { from: usize, to: usize, changes: Vec<FigureChange> },
+    }
+
+/// Figures that differ between two sequences, in the order of the new sequence. Unchanged figures are left out
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Serialize)]
+pub struct SequenceDiff {
+    pub figures: Vec<FigureDiff>,
+    }
+
+impl SequenceDiff {
+    pub fn is_empty(&self) -> bool {
+        self.figures.is_empty()
+        }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+        }
+    }
+
+fn upright(inverted: bool) -> &'static str {
+    if inverted { "inverted" } else { "upright" }
+    }
+
+fn rolls_or_none(rolls: &str) -> String {
+    if rolls.is_empty() { "none".to_string() } else { format!("`{}`", rolls) }
+    }
+
+fn list_or_none(items: &[String]) -> String {
+    if items.is_empty() { "none".to_string() } else { items.join(", ") }
+    }
+
+fn radii(angles: &[f32]) -> String {
+    let radii: Vec<String> = angles.iter()
+        .map(|angle| format!("{} {}°", if *angle < 0.0 { "push" } else { "pull" }, angle.abs()))
+        .collect();
+    list_or_none(&radii)
+    }
+
+impl fmt::Display for FigureChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FigureChange::RollSet { line, before, after } =>
+                write!(f, "rolls on line {} changed from {} to {}", line + 1, rolls_or_none(before), rolls_or_none(after)),
+            FigureChange::RollDirection { line, before, after } if before == after =>
+                write!(f, "roll directions changed on line {}: `{}`", line + 1, before),
+            FigureChange::RollDirection { line, before, after } =>
+                write!(f, "roll directions changed on line {}: `{}` to `{}`", line + 1, before, after),
+            FigureChange::EntryInversion { before, after } => write!(f, "entry {} to {}", upright(*before), upright(*after)),
+            FigureChange::ExitInversion { before, after } => write!(f, "exit {} to {}", upright(*before), upright(*after)),
+            FigureChange::Radii { before, after } => write!(f, "radii changed from {} to {}", radii(before), radii(after)),
+            FigureChange::Turns { before, after } => write!(f, "turns changed from {} to {}", list_or_none(before), list_or_none(after)),
+            FigureChange::Layout { before, after } =>
+                write!(f, "layout changed from {} to {}", rolls_or_none(&before.join(" ")), rolls_or_none(&after.join(" "))),
+            FigureChange::Elements { before, after } => write!(f, "elements changed from {} to {}", list_or_none(before), list_or_none(after)),
+            }
+        }
+    }
+
+impl fmt::Display for FigureDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FigureDiff::Added { index } => write!(f, "Added figure {}", index + 1),
+            FigureDiff::Removed { index } => write!(f, "Removed figure {}", index + 1),
+            FigureDiff::Moved { from, to } => write!(f, "Moved figure {} to {}", from + 1, to + 1),
+            FigureDiff::Modified { from, to, changes } => {
+                if from == to {
+                    write!(f, "Changed figure {}", from + 1)?;
+                    }
+                else {
+                    write!(f, "Changed figure {}, now {}", from + 1, to + 1)?;
+                    }
+                for change in changes {
+                    write!(f, "\n    {}", change)?;
+                    }
+                Ok(())
+                },
+            }
+        }
+    }
+
+impl fmt::Display for SequenceDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, figure) in self.figures.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+                }
+            write!(f, "{}", figure)?;
+            }
+        Ok(())
+        }
+    }
+
+fn same_figure(a: &Figure, b: &Figure) -> bool {
+    a.elements == b.elements && a.layout == b.layout
+    }
+
+/// Pairs of (old, new) indices of the longest run of unchanged figures kept in order
+fn unchanged(before: &[Figure], after: &[Figure]) -> Vec<(usize, usize)> {
+    let mut lengths = vec![vec![0usize; after.len() + 1]; before.len() + 1];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            lengths[i][j] = if same_figure(&before[i], &after[j]) {
+                lengths[i + 1][j + 1] + 1
+                }
+            else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() && j < after.len() {
+        if same_figure(&before[i], &after[j]) {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+            }
+        else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+            }
+        else {
+            j += 1;
+            }
+        }
+    pairs
+    }
+
+/// Lines, radii, turns and stalls of a figure with rolls, inversions and looping direction left out.
+///  Figures of the same shape are paired as modified
+fn shape(figure: &Figure) -> Vec<(ElementType, i32, i32)> {
+    let mut shape: Vec<(ElementType, i32, i32)> = Vec::new();
+    for element in figure {
+        let key = match *element {
+            Element::Line { pitch, .. } => (ElementType::Line, pitch.round() as i32, 0),
+            Element::Radius { angle, .. } => (ElementType::Radius, angle.abs().round() as i32, 0),
+            Element::Turn { angle, .. } => (ElementType::Turn, angle.abs().round() as i32, 0),
+            Element::Stall { yaw, pitch } => (ElementType::Stall, yaw.round() as i32, pitch.round() as i32),
+            _ => continue,
+            };
+        if key.0 != ElementType::Line || shape.last() != Some(&key) {
+            shape.push(key);
+            }
+        }
+    shape
+    }
+
+fn notation(rolls: &[Element]) -> String {
+    roll_set_notation(rolls).unwrap_or_else(|| descriptions(rolls).join(", "))
+    }
+
+fn descriptions(elements: &[Element]) -> Vec<String> {
+    elements.iter().map(|element| element.to_string()).collect()
+    }
+
+fn layout_olan(command: &LayoutCommand) -> String {
+    match *command {
+        LayoutCommand::EntryUpwind => "eu".to_string(),
+        LayoutCommand::EntryDownwind => "ed".to_string(),
+        LayoutCommand::EntryTowardsJudges => "ej".to_string(),
+        LayoutCommand::EntryAwayFromJudges => "eja".to_string(),
+        LayoutCommand::SwitchYAxis => "/".to_string(),
+        LayoutCommand::SwitchXAxis => "//".to_string(),
+        LayoutCommand::MoveForward(steps) => format!("{}>", steps),
+        LayoutCommand::MoveBy(x, y) => format!("[{},{}]", x, y),
+        LayoutCommand::CurveTo(x, y) => format!("({},{})", x, y),
+        }
+    }
+
+/// The rotation with its direction and linking left out
+fn undirected(element: &Element) -> Element {
+    match *element {
+        Element::Roll { angle, divisions, .. } =>
+            Element::Roll { angle, direction: RollDirection::Right, divisions, linked: false },
+        Element::Flick { angle, negative, .. } =>
+            Element::Flick { angle, direction: RollDirection::Right, negative, linked: false },
+        Element::Spin { angle, negative, .. } =>
+            Element::Spin { angle, direction: RollDirection::Right, negative, linked: false },
+        other => other,
+        }
+    }
+
+/// Differences between two figures of the same shape
+pub fn figure_changes(before: &Figure, after: &Figure) -> Vec<FigureChange> {
+    let mut changes = Vec::new();
+
+    let inversion = |element: Option<&Element>| element.map(Element::inverted).unwrap_or(false);
+    let entry = (inversion(before.elements.first()), inversion(after.elements.first()));
+    if entry.0 != entry.1 {
+        changes.push(FigureChange::EntryInversion { before: entry.0, after: entry.1 });
+        }
+
+    let (before_rolls, after_rolls) = (before.roll_sets(), after.roll_sets());
+    for line in 0..before_rolls.len().max(after_rolls.len()) {
+        let old = before_rolls.get(line).map(Vec::as_slice).unwrap_or(&[]);
+        let new = after_rolls.get(line).map(Vec::as_slice).unwrap_or(&[]);
+        if old == new {
+            continue;
+            }
+        let (before, after) = (notation(old), notation(new));
+        if old.iter().map(undirected).eq(new.iter().map(undirected)) {
+            changes.push(FigureChange::RollDirection { line, before, after });
+            }
+        else {
+            changes.push(FigureChange::RollSet { line, before, after });
+            }
+        }
+
+    let exit = (inversion(before.elements.last()), inversion(after.elements.last()));
+    if exit.0 != exit.1 {
+        changes.push(FigureChange::ExitInversion { before: exit.0, after: exit.1 });
+        }
+
+    let radii = |figure: &Figure| -> Vec<f32> {
+        figure.elements.iter().filter_map(|element| match *element {
+            Element::Radius { angle, .. } => Some(angle),
+            _ => None,
+            }).collect()
+        };
+    let (before_radii, after_radii) = (radii(before), radii(after));
+    if before_radii != after_radii {
+        changes.push(FigureChange::Radii { before: before_radii, after: after_radii });
+        }
+
+    let turns = |figure: &Figure| -> Vec<Element> {
+        figure.elements.iter().filter(|element| element.elem_type() == ElementType::Turn).copied().collect()
+        };
+    let (before_turns, after_turns) = (turns(before), turns(after));
+    if before_turns != after_turns {
+        changes.push(FigureChange::Turns { before: descriptions(&before_turns), after: descriptions(&after_turns) });
+        }
+
+    if before.layout != after.layout {
+        let layout = |figure: &Figure| -> Vec<String> { figure.layout.iter().map(layout_olan).collect() };
+        changes.push(FigureChange::Layout { before: layout(before), after: layout(after) });
+        }
+
+    if changes.is_empty() && before.elements != after.elements {
+        changes.push(FigureChange::Elements { before: descriptions(&before.elements), after: descriptions(&after.elements) });
+        }
+    changes
+    }
+
+impl Sequence {
+    /// Figures added, removed, moved and changed to get from this sequence to another.
+    ///  Figures kept in order are matched first, then identical figures elsewhere as moved,
+    ///  then the rest in order by the shape they share as modified
+    pub fn diff(&self, after: &Sequence) -> SequenceDiff {
+        let (old, new) = (&self.figures, &after.figures);
+        let mut old_matched = vec![false; old.len()];
+        let mut new_matched = vec![false; new.len()];
+        for (i, j) in unchanged(old, new) {
+            old_matched[i] = true;
+            new_matched[j] = true;
+            }
+
+        let mut figures = Vec::new();
+        let mut pair = |matches: &dyn Fn(&Figure, &Figure) -> bool, figures: &mut Vec<FigureDiff>, moved: bool| {
+            for i in 0..old.len() {
+                if old_matched[i] {
+                    continue;
+                    }
+                let found = (0..new.len()).find(|&j| !new_matched[j] && matches(&old[i], &new[j]));
+                if let Some(j) = found {
+                    old_matched[i] = true;
+                    new_matched[j] = true;
+                    figures.push(if moved {
+                        FigureDiff::Moved { from: i, to: j }
+                        }
+                    else {
+                        FigureDiff::Modified { from: i, to: j, changes: figure_changes(&old[i], &new[j]) }
+                        });
+                    }
+                }
+            };
+        pair(&same_figure, &mut figures, true);
+        pair(&|a, b| shape(a) == shape(b), &mut figures, false);
+
+        figures.extend((0..old.len()).filter(|&i| !old_matched[i]).map(|index| FigureDiff::Removed { index }));
+        figures.extend((0..new.len()).filter(|&j| !new_matched[j]).map(|index| FigureDiff::Added { index }));
+
+        // Removed figures go before anything now at their position
+        figures.sort_by_key(|figure| match *figure {
+            FigureDiff::Removed { index } => (index, 0),
+            FigureDiff::Added { index } => (index, 1),
+            FigureDiff::Moved { to, .. } | FigureDiff::Modified { to, .. } => (to, 1),
+            });
+        SequenceDiff { figures }
+        }
+    }
+
+/// Difference between two OLAN strings
+pub fn diff_olan(before: &str, after: &str) -> Result<SequenceDiff, String> {
+    let before = try_parse_sequence(before.to_string())?;
+    let after = try_parse_sequence(after.to_string())?;
+    Ok(before.diff(&after))
+    }
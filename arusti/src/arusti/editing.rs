@@ -0,0 +1,238 @@
+use std::ops::Range;
+
+use crate::olan::parser::{inverted_elements, library_elements};
+use crate::types::{Element, Figure, FlatElement, Sequence};
+
+/// State of a sequence after an edit
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct EditReport {
+    /// Heading at the entry of each figure from the entry heading of the first, positive to the right and up to 180 (degrees)
+    pub entry_headings: Vec<f32>,
+    /// Indices of figures whose entry line does not continue the inversion state of the previous figure's exit line
+    pub continuity_breaks: Vec<usize>,
+    /// Rolls left out of a new base shape for lack of a line to fly them on
+    pub dropped_rolls: Vec<Element>,
+    }
+
+/// Index range of the elements between the entry and exit lines
+fn body(elements: &[Element]) -> Range<usize> {
+    let start = match elements.first() {
+        Some(Element::Line { .. }) => 1,
+        _ => 0,
+        };
+    let end = match elements.last() {
+        Some(Element::Line { .. }) if elements.len() > start => elements.len() - 1,
+        _ => elements.len(),
+        };
+    start..end
+    }
+
+/// Index ranges of the places rolls are flown in a figure: its lines, each covering the rotations flown on it,
+///  and rotations between radii with no line. Consecutive lines at the same pitch are one line, split by the rolls on it
+fn lines(elements: &[Element]) -> Vec<Range<usize>> {
+    let mut lines: Vec<Range<usize>> = Vec::new();
+    let mut line_pitch = None;
+    for (i, element) in elements.iter().enumerate() {
+        let contiguous = lines.last().is_some_and(|line| line.end == i);
+        match *element {
+            Element::Line { pitch, .. } => {
+                if contiguous && line_pitch.unwrap_or(pitch) == pitch {
+                    lines.last_mut().unwrap().end = i + 1;
+                    }
+                else {
+                    lines.push(i..i + 1);
+                    }
+                line_pitch = Some(pitch);
+                },
+            _ if element.is_rolling() || (contiguous && matches!(element, Element::Combining(_))) => {
+                if contiguous {
+                    lines.last_mut().unwrap().end = i + 1;
+                    }
+                else {
+                    lines.push(i..i + 1);
+                    }
+                },
+            _ => line_pitch = None,
+            }
+        }
+    lines
+    }
+
+/// Replace the rolls on a line of elements in library form. Rolls already on the line keep their place before or after
+///  the line element, and new rolls go after the entry line, before the exit line and in the middle of other lines.
+///  Rolls between radii are removed with their place
+fn put_rolls(elements: &mut Vec<Element>, line: usize, rolls: &[Element]) {
+    let ranges = lines(elements);
+    let range = ranges[line].clone();
+    let current = &elements[range.clone()];
+    let line_element = current.iter().find(|element| matches!(element, Element::Line { .. })).copied();
+    let (before, after) = if ranges.len() == 1 {
+        (true, true)
+        }
+    else if current.iter().any(Element::is_rolling) {
+        (matches!(current.first(), Some(Element::Line { .. })), matches!(current.last(), Some(Element::Line { .. })))
+        }
+    else {
+        (line + 1 < ranges.len(), line > 0)
+        };
+
+    let mut replacement = Vec::new();
+    if let Some(line_element) = line_element {
+        if before {
+            replacement.push(line_element);
+            }
+        replacement.extend_from_slice(rolls);
+        // A line in the middle of the figure only needs splitting by rolls, but a figure of one line keeps its entry and exit
+        if after && (!before || !rolls.is_empty() || ranges.len() == 1) {
+            replacement.push(line_element);
+            }
+        }
+    else {
+        replacement.extend_from_slice(rolls);
+        }
+    elements.splice(range, replacement);
+    }
+
+impl Figure {
+    /// Rotations of the figure grouped by the line they are flown on, numbered from 0 at the entry line.
+    ///  Every line has a group, empty if it has no rolls, and rolls between radii with no line have a group of their own
+    pub fn roll_sets(&self) -> Vec<Vec<Element>> {
+        lines(&self.elements).into_iter()
+            .map(|range| self.elements[range].iter().filter(|element| element.is_rolling()).copied().collect())
+            .collect()
+        }
+
+    /// Whether the entry line is inverted
+    fn entry_inverted(&self) -> bool {
+        self.elements.first().is_some_and(Element::inverted)
+        }
+
+    /// Elements as written for an upright entry, with the radii the rolls flipped unflipped
+    fn library_form(&self) -> Vec<Element> {
+        let body = body(&self.elements);
+        let mut flat = self.to_flat();
+        let library = library_elements(&flat[body.clone()], self.entry_inverted());
+        flat.splice(body, library);
+        Figure::from_flat(&flat).elements
+        }
+
+    /// Set the elements from library form, applying the inversions of the entry and the rolls.
+    ///  The exit line follows the figure, so may no longer continue into the next one
+    fn set_library_form(&mut self, elements: &[Element], entry_inverted: bool) {
+        let body = body(elements);
+        let mut flat: Vec<FlatElement> = elements.iter().map(Element::to_flat).collect();
+        let (inverted, exit_inverted) = inverted_elements(&flat[body.clone()], entry_inverted);
+        flat.splice(body.clone(), inverted);
+        if body.start > 0 {
+            flat[0].inverted = entry_inverted;
+            }
+        if body.end < elements.len() {
+            flat.last_mut().unwrap().inverted = exit_inverted;
+            }
+        self.elements = Figure::from_flat(&flat).elements;
+        }
+
+    /// Replace the rolls flown on a line, numbered from 0 at the entry line as in `roll_sets`
+    pub fn set_roll_set(&mut self, line: usize, rolls: &[Element]) -> Result<(), String> {
+        if let Some(element) = rolls.iter().find(|element| !element.is_rolling()) {
+            return Err(format!("{} is not a roll, snap or spin", element));
+            }
+        let mut elements = self.library_form();
+        let count = lines(&elements).len();
+        if line >= count {
+            return Err(format!("no line {} in a figure of {} lines", line, count));
+            }
+        put_rolls(&mut elements, line, rolls);
+        self.set_library_form(&elements, self.entry_inverted());
+        Ok(())
+        }
+
+    /// Fly the lines, radii, turns and stalls of another figure, keeping the entry and the rolls of this one.
+    ///  Rolls on the entry and exit lines stay there, and the rest move to the line with the same number.
+    ///  Returns the rolls left out for lack of a line
+    pub fn replace_shape(&mut self, shape: &Figure) -> Vec<Element> {
+        let roll_sets = self.roll_sets();
+        let mut elements: Vec<Element> = shape.library_form().into_iter().filter(|element| !element.is_rolling()).collect();
+        let count = lines(&elements).len();
+        let mut dropped = Vec::new();
+        if count == 0 {
+            dropped.extend(roll_sets.into_iter().flatten());
+            }
+        else {
+            let mut moved = vec![Vec::new(); count];
+            for (line, rolls) in roll_sets.iter().enumerate() {
+                let target = if line == 0 { Some(0) }
+                    else if line + 1 == roll_sets.len() { Some(count - 1) }
+                    else if line + 1 < count { Some(line) }
+                    else { None };
+                match target {
+                    Some(target) => moved[target].extend_from_slice(rolls),
+                    None => dropped.extend_from_slice(rolls),
+                    }
+                }
+            for (line, rolls) in moved.iter().enumerate() {
+                put_rolls(&mut elements, line, rolls);
+                }
+            }
+        self.set_library_form(&elements, self.entry_inverted());
+        dropped
+        }
+    }
+
+/// Keep an angle in (-180,180]
+fn wrap(angle: f32) -> f32 {
+    let angle = angle.rem_euclid(360.0);
+    if angle > 180.0 { angle - 360.0 } else { angle }
+    }
+
+impl Sequence {
+    /// Headings and continuity of the sequence as it stands
+    pub fn report(&self) -> EditReport {
+        let mut heading = 0.0;
+        let mut entry_headings = Vec::new();
+        for figure in &self.figures {
+            entry_headings.push(heading);
+            let turned = figure.geometry().states.last().map(|state| state.heading).unwrap_or(0.0);
+            heading = wrap(heading + turned);
+            }
+        EditReport {
+            entry_headings,
+            continuity_breaks: self.continuity_breaks(),
+            dropped_rolls: Vec::new(),
+            }
+        }
+
+    fn figure_mut(&mut self, index: usize) -> Result<&mut Figure, String> {
+        let count = self.figures.len();
+        self.figures.get_mut(index).ok_or_else(|| format!("no figure {} in a sequence of {}", index, count))
+        }
+
+    /// Insert a figure before the one at an index, or at the end for the number of figures
+    pub fn insert_figure(&mut self, index: usize, figure: Figure) -> Result<EditReport, String> {
+        if index > self.figures.len() {
+            return Err(format!("cannot insert figure {} in a sequence of {}", index, self.figures.len()));
+            }
+        self.figures.insert(index, figure);
+        Ok(self.report())
+        }
+
+    pub fn remove_figure(&mut self, index: usize) -> Result<(Figure, EditReport), String> {
+        self.figure_mut(index)?;
+        let figure = self.figures.remove(index);
+        Ok((figure, self.report()))
+        }
+
+    /// Fly the shape of another figure in place of a figure, keeping its entry and the rolls that have a line to go on
+    pub fn replace_shape(&mut self, index: usize, shape: &Figure) -> Result<EditReport, String> {
+        let dropped_rolls = self.figure_mut(index)?.replace_shape(shape);
+        Ok(EditReport { dropped_rolls, .. self.report() })
+        }
+
+    /// Replace the rolls on a line of a figure, numbered from 0 at the entry line
+    pub fn set_roll_set(&mut self, index: usize, line: usize, rolls: &[Element]) -> Result<EditReport, String> {
+        self.figure_mut(index)?.set_roll_set(line, rolls)?;
+        Ok(self.report())
+        }
+    }
@@ -0,0 +1,170 @@
+use std::io;
+use std::io::Write;
+
+extern crate nalgebra as na;
+use na::{Vector3, UnitQuaternion};
+
+use crate::data_generation::DataPoint;
+
+/// Presentation options for glTF output
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct GltfOptions {
+    /// Wingspan of the aircraft model and width of the ribbon (m)
+    pub wingspan: f32,
+    }
+
+impl Default for GltfOptions {
+    fn default() -> GltfOptions {
+        GltfOptions {
+            wingspan: 8.0,
+            }
+        }
+    }
+
+/// glTF is y up, so map the earth frame (north, east, down) to (east, up, south)
+fn to_gltf(vector: &Vector3<f32>) -> [f32; 3] {
+    [vector[1], -vector[2], -vector[0]]
+    }
+
+/// Rotation in the glTF frame as [x, y, z, w]
+fn to_gltf_rotation(attitude: &UnitQuaternion<f32>) -> [f32; 4] {
+    let quaternion = attitude.quaternion();
+    let axis = to_gltf(&Vector3::<f32>::new(quaternion.i, quaternion.j, quaternion.k));
+    [axis[0], axis[1], axis[2], quaternion.w]
+    }
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn encode_base64(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let triple = ((bytes[0] as u32) << 16) | ((bytes[1] as u32) << 8) | bytes[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[((triple >> (18 - 6 * i)) & 0x3f) as usize] as char);
+                }
+            else {
+                encoded.push('=');
+                }
+            }
+        }
+    encoded
+    }
+
+/// Binary buffer of little-endian floats, tracking the views and accessors that describe it
+struct BufferBuilder {
+    data: Vec<u8>,
+    buffer_views: Vec<String>,
+    accessors: Vec<String>,
+    }
+
+impl BufferBuilder {
+    fn new() -> BufferBuilder {
+        BufferBuilder {
+            data: Vec::new(),
+            buffer_views: Vec::new(),
+            accessors: Vec::new(),
+            }
+        }
+
+    /// Add an accessor over values grouped into elements of the given glTF type. Returns the accessor index
+    fn push(&mut self, values: &[f32], components: usize, accessor_type: &str, with_bounds: bool) -> usize {
+        let offset = self.data.len();
+        for value in values {
+            self.data.extend_from_slice(&value.to_le_bytes());
+            }
+        self.buffer_views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{}}}"#, offset, values.len() * 4
+            ));
+
+        let mut bounds = String::new();
+        if with_bounds {
+            let mut min = vec![f32::INFINITY; components];
+            let mut max = vec![f32::NEG_INFINITY; components];
+            for element in values.chunks(components) {
+                for (i, value) in element.iter().enumerate() {
+                    min[i] = min[i].min(*value);
+                    max[i] = max[i].max(*value);
+                    }
+                }
+            bounds = format!(r#","min":{:?},"max":{:?}"#, min, max);
+            }
+
+        self.accessors.push(format!(
+            r#"{{"bufferView":{},"componentType":5126,"count":{},"type":"{}"{}}}"#,
+            self.buffer_views.len() - 1, values.len() / components, accessor_type, bounds
+            ));
+        self.accessors.len() - 1
+        }
+    }
+
+/// Write a glTF 2.0 scene with an animated aircraft following the points and a wingtip ribbon along the path
+pub fn write_gltf<W: Write>(points: &[DataPoint], options: &GltfOptions, mut writer: W) -> io::Result<()> {
+    if points.len() < 2 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "glTF animation requires at least two points"));
+        }
+
+    let half_span = 0.5 * options.wingspan;
+    let length = 0.75 * options.wingspan;
+
+    // Simple dart: wing and fin triangles in the body frame
+    let aircraft_vertices = [
+        Vector3::<f32>::new(0.5 * length, 0.0, 0.0),
+        Vector3::<f32>::new(-0.5 * length, -half_span, 0.0),
+        Vector3::<f32>::new(-0.5 * length, half_span, 0.0),
+        Vector3::<f32>::new(0.5 * length, 0.0, 0.0),
+        Vector3::<f32>::new(-0.5 * length, 0.0, 0.0),
+        Vector3::<f32>::new(-0.5 * length, 0.0, -0.3 * options.wingspan),
+        ];
+    let aircraft_positions: Vec<f32> = aircraft_vertices.iter().flat_map(|v| to_gltf(v).to_vec()).collect();
+
+    // Ribbon joins the wingtips at each point
+    let ribbon_positions: Vec<f32> = points.iter().flat_map(|point| {
+        let wing = point.attitude * Vector3::<f32>::y() * half_span;
+        let mut tips = to_gltf(&(point.position - wing)).to_vec();
+        tips.extend_from_slice(&to_gltf(&(point.position + wing)));
+        tips
+        }).collect();
+
+    let start_time = points[0].time;
+    let times: Vec<f32> = points.iter().map(|point| point.time - start_time).collect();
+    let translations: Vec<f32> = points.iter().flat_map(|point| to_gltf(&point.position).to_vec()).collect();
+    let rotations: Vec<f32> = points.iter().flat_map(|point| to_gltf_rotation(&point.attitude).to_vec()).collect();
+
+    let mut buffer = BufferBuilder::new();
+    let aircraft_accessor = buffer.push(&aircraft_positions, 3, "VEC3", true);
+    let ribbon_accessor = buffer.push(&ribbon_positions, 3, "VEC3", true);
+    let time_accessor = buffer.push(&times, 1, "SCALAR", true);
+    let translation_accessor = buffer.push(&translations, 3, "VEC3", false);
+    let rotation_accessor = buffer.push(&rotations, 4, "VEC4", false);
+
+    write!(writer, "{{")?;
+    write!(writer, r#""asset":{{"version":"2.0","generator":"arusti"}},"#)?;
+    write!(writer, r#""scene":0,"scenes":[{{"nodes":[0,1]}}],"#)?;
+    write!(writer, r#""nodes":[{{"name":"Aircraft","mesh":0}},{{"name":"Ribbon","mesh":1}}],"#)?;
+    write!(writer, concat!(
+        r#""materials":["#,
+        r#"{{"name":"Aircraft","doubleSided":true,"pbrMetallicRoughness":{{"baseColorFactor":[0.8,0.1,0.1,1.0]}}}},"#,
+        r#"{{"name":"Ribbon","doubleSided":true,"pbrMetallicRoughness":{{"baseColorFactor":[0.1,0.3,0.8,1.0]}}}}"#,
+        r#"],"#,
+        ))?;
+    write!(writer, r#""meshes":["#)?;
+    write!(writer, r#"{{"name":"Aircraft","primitives":[{{"attributes":{{"POSITION":{}}},"mode":4,"material":0}}]}},"#, aircraft_accessor)?;
+    write!(writer, r#"{{"name":"Ribbon","primitives":[{{"attributes":{{"POSITION":{}}},"mode":5,"material":1}}]}}"#, ribbon_accessor)?;
+    write!(writer, "],")?;
+    write!(writer, r#""animations":[{{"name":"Flight","channels":["#)?;
+    write!(writer, r#"{{"sampler":0,"target":{{"node":0,"path":"translation"}}}},"#)?;
+    write!(writer, r#"{{"sampler":1,"target":{{"node":0,"path":"rotation"}}}}"#)?;
+    write!(writer, r#"],"samplers":["#)?;
+    write!(writer, r#"{{"input":{},"output":{},"interpolation":"LINEAR"}},"#, time_accessor, translation_accessor)?;
+    write!(writer, r#"{{"input":{},"output":{},"interpolation":"LINEAR"}}"#, time_accessor, rotation_accessor)?;
+    write!(writer, "]}}],")?;
+    write!(writer, r#""accessors":[{}],"#, buffer.accessors.join(","))?;
+    write!(writer, r#""bufferViews":[{}],"#, buffer.buffer_views.join(","))?;
+    write!(writer, r#""buffers":[{{"byteLength":{},"uri":"data:application/octet-stream;base64,{}"}}]"#,
+        buffer.data.len(), encode_base64(&buffer.data))?;
+    writeln!(writer, "}}")?;
+    Ok(())
+    }
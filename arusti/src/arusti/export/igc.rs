@@ -0,0 +1,68 @@
+use std::io;
+use std::io::Write;
+
+use crate::data_generation::DataPoint;
+use crate::geo::GeoOrigin;
+
+/// Placement of the earth frame origin and header details for IGC output
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct IgcOptions {
+    pub origin: GeoOrigin,
+    /// Date of the flight as (day, month, year)
+    pub date: (u8, u8, u16),
+    /// UTC time of day that DataPoint time zero corresponds to (s)
+    pub start_time: f64,
+    pub pilot: String,
+    pub glider_type: String,
+    }
+
+impl Default for IgcOptions {
+    fn default() -> IgcOptions {
+        IgcOptions {
+            origin: GeoOrigin::default(),
+            date: (1, 1, 2000),
+            start_time: 12.0 * 3600.0,
+            pilot: String::new(),
+            glider_type: String::new(),
+            }
+        }
+    }
+
+/// Format an angle as IGC degrees, minutes and thousandths of minutes
+fn format_igc_angle(angle: f64, degree_digits: usize, positive: char, negative: char) -> String {
+    let hemisphere = if angle < 0.0 { negative } else { positive };
+    let thousandths = (angle.abs() * 60_000.0).round() as u64;
+    format!("{:0width$}{:05}{}", thousandths / 60_000, thousandths % 60_000, hemisphere, width = degree_digits)
+    }
+
+pub fn write_igc<W: Write>(points: &[DataPoint], options: &IgcOptions, mut writer: W) -> io::Result<()> {
+    let (day, month, year) = options.date;
+    writeln!(writer, "AXXXARUSTI")?;
+    writeln!(writer, "HFDTEDATE:{:02}{:02}{:02}", day, month, year % 100)?;
+    writeln!(writer, "HFPLTPILOTINCHARGE:{}", options.pilot)?;
+    writeln!(writer, "HFGTYGLIDERTYPE:{}", options.glider_type)?;
+    writeln!(writer, "HFDTMGPSDATUM:WGS84")?;
+
+    // B records have one second resolution, so write the first fix in each second
+    let mut last_second = None;
+    for point in points {
+        let time_of_day = options.start_time + point.time as f64;
+        let second = time_of_day.floor() as u64;
+        if last_second == Some(second) {
+            continue;
+            }
+        last_second = Some(second);
+
+        let (latitude, longitude, altitude) = options.origin.to_wgs84(&point.position);
+        let altitude = altitude.round() as i64;
+
+        writeln!(writer, "B{:02}{:02}{:02}{}{}A{:05}{:05}",
+            (second / 3600) % 24, (second / 60) % 60, second % 60,
+            format_igc_angle(latitude, 2, 'N', 'S'),
+            format_igc_angle(longitude, 3, 'E', 'W'),
+            altitude, altitude
+            )?;
+        }
+    Ok(())
+    }
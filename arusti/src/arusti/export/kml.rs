@@ -0,0 +1,91 @@
+use std::io;
+use std::io::Write;
+
+use crate::data_generation::DataPoint;
+use crate::geo::GeoOrigin;
+
+/// Placement and presentation options for KML output
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct KmlOptions {
+    /// Placement of the box origin
+    pub origin: GeoOrigin,
+    /// Date of the flight as (day, month, year)
+    pub date: (u8, u8, u16),
+    /// UTC time of day that DataPoint time zero corresponds to (s)
+    pub start_time: f64,
+    /// Name of the KML document
+    pub name: String,
+    /// Link to a COLLADA model oriented along the track, e.g. "extra330.dae"
+    pub model: Option<String>,
+    }
+
+impl Default for KmlOptions {
+    fn default() -> KmlOptions {
+        KmlOptions {
+            origin: GeoOrigin::default(),
+            date: (1, 1, 2000),
+            start_time: 12.0 * 3600.0,
+            name: "arusti".to_string(),
+            model: None,
+            }
+        }
+    }
+
+/// ISO 8601 timestamp for a DataPoint
+fn format_when(point: &DataPoint, options: &KmlOptions) -> String {
+    let (day, month, year) = options.date;
+    let time_of_day = (options.start_time + point.time as f64).rem_euclid(86400.0);
+    let seconds = time_of_day % 60.0;
+    let minutes = (time_of_day / 60.0).floor() as u32 % 60;
+    let hours = (time_of_day / 3600.0).floor() as u32;
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:06.3}Z", year, month, day, hours, minutes, seconds)
+    }
+
+pub fn write_kml<W: Write>(points: &[DataPoint], options: &KmlOptions, mut writer: W) -> io::Result<()> {
+    let coordinates: Vec<(f64, f64, f64)> = points.iter().map(|point| options.origin.to_wgs84(&point.position)).collect();
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<kml xmlns="http://www.opengis.net/kml/2.2" xmlns:gx="http://www.google.com/kml/ext/2.2">"#)?;
+    writeln!(writer, "<Document>")?;
+    writeln!(writer, "<name>{}</name>", options.name)?;
+
+    // Static path for reviewing the whole sequence
+    writeln!(writer, "<Placemark>")?;
+    writeln!(writer, "<name>Path</name>")?;
+    writeln!(writer, "<LineString>")?;
+    writeln!(writer, "<altitudeMode>absolute</altitudeMode>")?;
+    writeln!(writer, "<coordinates>")?;
+    for (latitude, longitude, altitude) in &coordinates {
+        writeln!(writer, "{:.8},{:.8},{:.2}", longitude, latitude, altitude)?;
+        }
+    writeln!(writer, "</coordinates>")?;
+    writeln!(writer, "</LineString>")?;
+    writeln!(writer, "</Placemark>")?;
+
+    // Time-stamped track with the aircraft orientation
+    writeln!(writer, "<Placemark>")?;
+    writeln!(writer, "<name>Aircraft</name>")?;
+    writeln!(writer, "<gx:Track>")?;
+    writeln!(writer, "<altitudeMode>absolute</altitudeMode>")?;
+    for point in points {
+        writeln!(writer, "<when>{}</when>", format_when(point, options))?;
+        }
+    for (latitude, longitude, altitude) in &coordinates {
+        writeln!(writer, "<gx:coord>{:.8} {:.8} {:.2}</gx:coord>", longitude, latitude, altitude)?;
+        }
+    for point in points {
+        let (roll, pitch, yaw) = point.attitude.euler_angles();
+        let heading = (yaw.to_degrees() as f64 + options.origin.heading).rem_euclid(360.0);
+        writeln!(writer, "<gx:angles>{:.2} {:.2} {:.2}</gx:angles>", heading, pitch.to_degrees(), roll.to_degrees())?;
+        }
+    if let Some(model) = &options.model {
+        writeln!(writer, "<Model><Link><href>{}</href></Link></Model>", model)?;
+        }
+    writeln!(writer, "</gx:Track>")?;
+    writeln!(writer, "</Placemark>")?;
+
+    writeln!(writer, "</Document>")?;
+    writeln!(writer, "</kml>")?;
+    Ok(())
+    }
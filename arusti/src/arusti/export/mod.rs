@@ -0,0 +1,171 @@
+use std::io;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+extern crate nalgebra as na;
+use na::{Vector3, Quaternion, UnitQuaternion};
+
+use crate::data_generation::DataPoint;
+
+mod igc;
+mod kml;
+mod gltf;
+
+pub use igc::{IgcOptions, write_igc};
+pub use kml::{KmlOptions, write_kml};
+pub use gltf::{GltfOptions, write_gltf};
+
+/// Column schema shared by the tabular formats (CSV & Parquet). Each entry is (name, unit, description)
+///
+/// Positions are in the earth frame (x north, y east, z down) relative to the generator origin.
+/// Attitude is given both as the body-to-earth quaternion and as roll, pitch & yaw Euler angles.
+pub const COLUMNS: [(&str, &str, &str); 11] = [
+    ("time",  "s",   "Time of sample"),
+    ("x",     "m",   "North position"),
+    ("y",     "m",   "East position"),
+    ("z",     "m",   "Down position"),
+    ("qw",    "",    "Attitude quaternion scalar part"),
+    ("qx",    "",    "Attitude quaternion x part"),
+    ("qy",    "",    "Attitude quaternion y part"),
+    ("qz",    "",    "Attitude quaternion z part"),
+    ("roll",  "deg", "Roll angle"),
+    ("pitch", "deg", "Pitch angle"),
+    ("yaw",   "deg", "Yaw angle from north"),
+    ];
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub enum ExportFormat {
+    /// Comma separated values with a header row of the COLUMNS names
+    Csv,
+    /// Apache Parquet with one FLOAT column per entry in COLUMNS. Requires the `parquet` feature
+    Parquet,
+    /// IGC flight recorder format, positioned relative to the IgcOptions origin at one fix per second
+    Igc,
+    /// KML track with aircraft model orientation, positioned relative to the KmlOptions origin
+    Kml,
+    /// glTF 2.0 scene with an animated aircraft node and a wingtip ribbon along the path
+    Gltf,
+    }
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<ExportFormat, String> {
+        match format.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "parquet" => Ok(ExportFormat::Parquet),
+            "igc" => Ok(ExportFormat::Igc),
+            "kml" => Ok(ExportFormat::Kml),
+            "gltf" => Ok(ExportFormat::Gltf),
+            _ => Err(format!("Unknown export format: {}", format)),
+            }
+        }
+    }
+
+/// Values for a DataPoint in COLUMNS order
+pub fn get_row(point: &DataPoint) -> [f32; 11] {
+    let (roll, pitch, yaw) = point.attitude.euler_angles();
+    let quaternion = point.attitude.quaternion();
+    [
+        point.time,
+        point.position[0], point.position[1], point.position[2],
+        quaternion.w, quaternion.i, quaternion.j, quaternion.k,
+        roll.to_degrees(), pitch.to_degrees(), yaw.to_degrees(),
+        ]
+    }
+
+/// Write points in the given format. Formats with options use their defaults
+pub fn write_points<W: Write + Send>(format: ExportFormat, points: &[DataPoint], writer: W) -> io::Result<()> {
+    match format {
+        ExportFormat::Csv => write_csv(points, writer),
+        ExportFormat::Parquet => write_parquet(points, writer),
+        ExportFormat::Igc => write_igc(points, &IgcOptions::default(), writer),
+        ExportFormat::Kml => write_kml(points, &KmlOptions::default(), writer),
+        ExportFormat::Gltf => write_gltf(points, &GltfOptions::default(), writer),
+        }
+    }
+
+pub fn write_csv<W: Write>(points: &[DataPoint], mut writer: W) -> io::Result<()> {
+    let header: Vec<&str> = COLUMNS.iter().map(|(name,_,_)| *name).collect();
+    writeln!(writer, "{}", header.join(","))?;
+
+    for point in points {
+        let row: Vec<String> = get_row(point).iter().map(|value| value.to_string()).collect();
+        writeln!(writer, "{}", row.join(","))?;
+        }
+    Ok(())
+    }
+
+/// Read points from CSV with a header row naming at least the time, position and quaternion COLUMNS.
+///  Other columns, such as the Euler angles, are ignored
+pub fn read_csv<R: BufRead>(reader: R) -> io::Result<Vec<DataPoint>> {
+    fn invalid(message: String) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, message)
+        }
+
+    let mut lines = reader.lines();
+    let header = lines.next().ok_or_else(|| invalid("missing header row".to_string()))??;
+    let names: Vec<&str> = header.split(',').map(|name| name.trim()).collect();
+    let indices = ["time", "x", "y", "z", "qw", "qx", "qy", "qz"].iter()
+        .map(|column| names.iter().position(|name| name == column).ok_or_else(|| invalid(format!("missing column {}", column))))
+        .collect::<io::Result<Vec<usize>>>()?;
+
+    let mut points = Vec::new();
+    for (line_idx, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+            }
+        let fields: Vec<&str> = line.split(',').collect();
+        let values = indices.iter()
+            .map(|&i| fields.get(i).and_then(|field| field.trim().parse::<f32>().ok())
+                .ok_or_else(|| invalid(format!("invalid value on line {}", line_idx + 2))))
+            .collect::<io::Result<Vec<f32>>>()?;
+        points.push(DataPoint {
+            time: values[0],
+            position: Vector3::new(values[1], values[2], values[3]),
+            attitude: UnitQuaternion::from_quaternion(Quaternion::new(values[4], values[5], values[6], values[7])),
+            });
+        }
+    Ok(points)
+    }
+
+#[cfg(feature = "parquet")]
+pub fn write_parquet<W: Write + Send>(points: &[DataPoint], writer: W) -> io::Result<()> {
+    use std::sync::Arc;
+    use parquet::data_type::FloatType;
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+
+    fn to_io_error(error: parquet::errors::ParquetError) -> io::Error {
+        io::Error::other(error)
+        }
+
+    let fields: Vec<String> = COLUMNS.iter().map(|(name,_,_)| format!("REQUIRED FLOAT {};", name)).collect();
+    let schema = parse_message_type(&format!("message datapoint {{ {} }}", fields.join(" "))).map_err(to_io_error)?;
+
+    let rows: Vec<[f32; 11]> = points.iter().map(get_row).collect();
+
+    let mut file_writer = SerializedFileWriter::new(writer, Arc::new(schema), Arc::new(WriterProperties::builder().build()))
+        .map_err(to_io_error)?;
+    let mut row_group_writer = file_writer.next_row_group().map_err(to_io_error)?;
+    let mut column_idx = 0;
+    while let Some(mut column_writer) = row_group_writer.next_column().map_err(to_io_error)? {
+        let values: Vec<f32> = rows.iter().map(|row| row[column_idx]).collect();
+        column_writer.typed::<FloatType>().write_batch(&values, None, None).map_err(to_io_error)?;
+        column_writer.close().map_err(to_io_error)?;
+        column_idx += 1;
+        }
+    row_group_writer.close().map_err(to_io_error)?;
+    file_writer.close().map_err(to_io_error)?;
+    Ok(())
+    }
+
+#[cfg(not(feature = "parquet"))]
+pub fn write_parquet<W: Write + Send>(_points: &[DataPoint], _writer: W) -> io::Result<()> {
+    Err(io::Error::other("Parquet export requires the `parquet` feature"))
+    }
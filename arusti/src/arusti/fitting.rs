@@ -0,0 +1,267 @@
+extern crate nalgebra as na;
+use na::{Vector3, Matrix3};
+
+use crate::types::{Element, ElementType, RadiusSize, RollDirection, TurnRollDirection};
+use crate::data_generation::DataPoint;
+use crate::segmentation::{self, Segment, SegmentationOptions};
+
+/// Element recovered from a segment of flight data
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct ElementFit {
+    /// Fitted element. Rolls have the hesitation points per 360° of the parts flown, and are never linked
+    pub element: Element,
+    /// Length of a line, or radius of a radius or turn (m)
+    pub size: f32,
+    /// Direction of a line or roll, or the axis about which a radius or turn is flown, in the earth frame
+    pub axis: Vector3<f32>,
+    /// Distance of each sample from the fitted line or circle (m)
+    pub residuals: Vec<f32>,
+    /// Root mean square of the residuals (m)
+    pub rms_residual: f32,
+    /// How well the geometry fits the samples, from 0 (poor) to 1 (exact).
+    ///  A root mean square residual of 5% of the size gives 0.5
+    pub confidence: f32,
+    }
+
+fn confidence(rms_residual: f32, size: f32) -> f32 {
+    let scale = 0.05 * size.max(1.0);
+    1.0 / (1.0 + (rms_residual / scale).powi(2))
+    }
+
+fn rms(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+        }
+    (values.iter().map(|value| value * value).sum::<f32>() / values.len() as f32).sqrt()
+    }
+
+/// Centroid of the positions, with the principal axes of their spread ordered from largest to smallest
+fn principal_axes(points: &[DataPoint]) -> (Vector3<f32>, [Vector3<f32>; 3]) {
+    let centroid = points.iter().map(|point| point.position).sum::<Vector3<f32>>() / points.len() as f32;
+    // Decomposed in f64, as the f32 decomposition can fail when the points are nearly collinear
+    let covariance = points.iter()
+        .map(|point| (point.position - centroid).map(|v| v as f64))
+        .map(|offset| offset * offset.transpose())
+        .sum::<Matrix3<f64>>();
+    let eigen = covariance.symmetric_eigen();
+
+    let mut order = [0, 1, 2];
+    order.sort_by(|&a, &b| eigen.eigenvalues[b].total_cmp(&eigen.eigenvalues[a]));
+    let axis = |i: usize| eigen.eigenvectors.column(order[i]).map(|v| v as f32);
+    (centroid, [axis(0), axis(1), axis(2)])
+    }
+
+/// Whether most of the points are inverted
+fn mostly_inverted(points: &[DataPoint]) -> bool {
+    2 * points.iter().filter(|point| segmentation::is_inverted(&point.attitude)).count() > points.len()
+    }
+
+/// Total rotation about a body axis between the first and last points (degrees)
+fn body_rotation(points: &[DataPoint], axis: usize) -> f32 {
+    points.windows(2)
+        .map(|pair| (pair[0].attitude.inverse() * pair[1].attitude).scaled_axis()[axis])
+        .sum::<f32>()
+        .to_degrees()
+    }
+
+fn roll_angle(points: &[DataPoint]) -> f32 {
+    body_rotation(points, 0)
+    }
+
+/// Roll of a flick or spin (degrees). The autorotation cones about the roll axis, so the summed roll rate
+///  reads short. The roll is taken from the change in attitude, with the whole turns counted from the rate
+fn autorotation_angle(points: &[DataPoint]) -> f32 {
+    let change = points[0].attitude.inverse() * points[points.len() - 1].attitude;
+    let twist = 2.0 * change.i.atan2(change.w).to_degrees();
+    twist + ((roll_angle(points) - twist) / 360.0).round() * 360.0
+    }
+
+/// Rotation about the body y axis, +ve nose up (degrees)
+pub(crate) fn pitch_angle(points: &[DataPoint]) -> f32 {
+    body_rotation(points, 1)
+    }
+
+/// Fit a straight line to the positions, pitched at the climb angle of the line.
+///  None if there are fewer than two points
+pub fn fit_line(points: &[DataPoint]) -> Option<ElementFit> {
+    if points.len() < 2 {
+        return None;
+        }
+    let (centroid, axes) = principal_axes(points);
+    let mut direction = axes[0];
+    if direction.dot(&(points[points.len() - 1].position - points[0].position)) < 0.0 {
+        direction = -direction;
+        }
+
+    let along: Vec<f32> = points.iter().map(|point| (point.position - centroid).dot(&direction)).collect();
+    let length = along.iter().cloned().fold(f32::NEG_INFINITY, f32::max) - along.iter().cloned().fold(f32::INFINITY, f32::min);
+    let residuals: Vec<f32> = points.iter().zip(&along)
+        .map(|(point, distance)| (point.position - centroid - direction * *distance).norm())
+        .collect();
+    let rms_residual = rms(&residuals);
+
+    Some(ElementFit {
+        element: Element::Line {
+            pitch: (-direction[2]).clamp(-1.0, 1.0).asin().to_degrees(),
+            inverted: mostly_inverted(points),
+            },
+        size: length.max(0.0),
+        axis: direction,
+        residuals,
+        rms_residual,
+        confidence: confidence(rms_residual, length),
+        })
+    }
+
+/// Circle fitted to the positions, as (centre, plane normal, radius)
+fn fit_circle(points: &[DataPoint]) -> (Vector3<f32>, Vector3<f32>, f32) {
+    let (centroid, axes) = principal_axes(points);
+    let planar: Vec<(f32, f32)> = points.iter()
+        .map(|point| ((point.position - centroid).dot(&axes[0]), (point.position - centroid).dot(&axes[1])))
+        .collect();
+
+    // Algebraic fit of a² + b² + Da + Eb + F = 0
+    let mut normal_matrix = Matrix3::<f32>::zeros();
+    let mut rhs = Vector3::<f32>::zeros();
+    for (a, b) in &planar {
+        let row = Vector3::new(*a, *b, 1.0);
+        normal_matrix += row * row.transpose();
+        rhs -= row * (a * a + b * b);
+        }
+    let solution = normal_matrix.lu().solve(&rhs).unwrap_or_else(Vector3::zeros);
+    let (centre_a, centre_b) = (-0.5 * solution[0], -0.5 * solution[1]);
+    let radius = (centre_a * centre_a + centre_b * centre_b - solution[2]).max(0.0).sqrt();
+
+    (centroid + axes[0] * centre_a + axes[1] * centre_b, axes[2], radius)
+    }
+
+/// Fit a circular arc to the positions. Radii are flown in a vertical plane and turns in a horizontal one.
+///  None if there are fewer than three points
+fn fit_arc(points: &[DataPoint], elem_type: ElementType) -> Option<ElementFit> {
+    if points.len() < 3 {
+        return None;
+        }
+    let (centre, mut normal, radius) = fit_circle(points);
+
+    // Orient the normal so the flight sweeps positively about it
+    let sweep = points.windows(2).map(|pair| {
+        let from = pair[0].position - centre;
+        let to = pair[1].position - centre;
+        from.cross(&to).dot(&normal).atan2(from.dot(&to))
+        }).sum::<f32>();
+    if sweep < 0.0 {
+        normal = -normal;
+        }
+
+    let residuals: Vec<f32> = points.iter().map(|point| {
+        let offset = point.position - centre;
+        let out_of_plane = offset.dot(&normal);
+        let in_plane = (offset - normal * out_of_plane).norm();
+        (out_of_plane * out_of_plane + (in_plane - radius).powi(2)).sqrt()
+        }).collect();
+    let rms_residual = rms(&residuals);
+
+    let element = match elem_type {
+        ElementType::Turn => {
+            // Heading change of the attitude, which is unaffected by the entry and exit rolls.
+            //  +ve turns are to the right, i.e. clockwise about the down axis
+            let angle = points.windows(2)
+                .map(|pair| (pair[1].attitude * pair[0].attitude.inverse()).scaled_axis()[2])
+                .sum::<f32>()
+                .to_degrees();
+            let roll = roll_angle(points);
+            Element::Turn {
+                angle,
+                rolls: roll.abs() / 360.0,
+                roll_direction: if roll * angle < 0.0 { TurnRollDirection::Outside } else { TurnRollDirection::Inside },
+                inverted: segmentation::is_inverted(&points[0].attitude),
+                }
+            }
+        // Pitch of the attitude, +ve when pulled
+        _ => Element::Radius { angle: pitch_angle(points), size: RadiusSize::Free },
+        };
+
+    Some(ElementFit {
+        element,
+        size: radius,
+        axis: normal,
+        residuals,
+        rms_residual,
+        confidence: confidence(rms_residual, radius),
+        })
+    }
+
+pub fn fit_radius(points: &[DataPoint]) -> Option<ElementFit> {
+    fit_arc(points, ElementType::Radius)
+    }
+
+/// Fit a turn, with the rolls flown during the turn
+pub fn fit_turn(points: &[DataPoint]) -> Option<ElementFit> {
+    fit_arc(points, ElementType::Turn)
+    }
+
+/// Rotation about the body x axis at each pause in a roll, away from the ends of the points (degrees)
+pub(crate) fn hesitation_angles(points: &[DataPoint], options: &SegmentationOptions) -> Vec<f32> {
+    let states = segmentation::kinematics(points, options);
+    let rolling: Vec<bool> = states.iter().map(|state| state.body_rates[0].abs() > options.roll_threshold).collect();
+
+    let (first, last) = match (rolling.iter().position(|r| *r), rolling.iter().rposition(|r| *r)) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Vec::new(),
+        };
+    let mut angles = Vec::new();
+    let mut pause_start = None;
+    for i in first..=last {
+        match (rolling[i], pause_start) {
+            (false, None) => pause_start = Some(i),
+            (true, Some(start)) => {
+                // Measured at the middle of the pause
+                angles.push(roll_angle(&points[..=(start + i) / 2]));
+                pause_start = None;
+                }
+            _ => {}
+            }
+        }
+    angles
+    }
+
+/// Fit a roll, flick or spin. The angle is the rotation about the body x axis. Rolls are divided by their
+///  hesitations, and flicks and spins are negative when the nose is pushed during the autorotation.
+///  None if there are fewer than two points or the type is not a rotation
+pub fn fit_rotation(points: &[DataPoint], elem_type: ElementType, options: &SegmentationOptions) -> Option<ElementFit> {
+    let line = fit_line(points)?;
+    let angle = match elem_type {
+        ElementType::Roll => roll_angle(points),
+        _ => autorotation_angle(points),
+        };
+    let direction = RollDirection::from_angle(angle);
+    let negative = pitch_angle(points) < 0.0;
+    let element = match elem_type {
+        ElementType::Roll => {
+            let parts = (hesitation_angles(points, options).len() + 1) as u8;
+            Element::roll(angle, Element::hesitation_divisions(angle, parts))
+            },
+        ElementType::Flick => Element::Flick { angle: angle.abs(), direction, negative, linked: false },
+        ElementType::Spin => Element::Spin { angle: angle.abs(), direction, negative, linked: false },
+        _ => return None,
+        };
+    Some(ElementFit { element, ..line })
+    }
+
+/// Fit the element flown in a segment, or None if the segment is too short to fit
+pub fn fit_segment(points: &[DataPoint], segment: &Segment, options: &SegmentationOptions) -> Option<ElementFit> {
+    // Include the first sample of the next segment so no motion falls between segments
+    let points = &points[segment.start..(segment.end + 1).min(points.len())];
+    // Hesitations are found with a narrow rate window
+    let rotation_options = SegmentationOptions {
+        rate_window: options.rate_window.min(0.1),
+        .. options.clone()
+        };
+    match segment.elem_type {
+        ElementType::Line => fit_line(points),
+        ElementType::Radius => fit_radius(points),
+        ElementType::Turn => fit_turn(points),
+        _ => fit_rotation(points, segment.elem_type, &rotation_options),
+        }
+    }
@@ -0,0 +1,122 @@
+extern crate nalgebra as na;
+use na::{Vector3, Matrix3, Rotation3, UnitQuaternion};
+
+/// Mean radius of the earth (m)
+pub const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// Placement of the local earth frame (x north, y east, z down) on the WGS84 datum
+///
+/// The local frame may be rotated by heading so that its x axis follows e.g. the box axis.
+/// Conversions use a flat earth approximation, which is adequate over the extent of an aerobatic box.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub struct GeoOrigin {
+    /// Latitude of the origin (degrees, +ve north)
+    pub latitude: f64,
+    /// Longitude of the origin (degrees, +ve east)
+    pub longitude: f64,
+    /// Altitude of the origin above mean sea level (m)
+    pub altitude: f64,
+    /// Direction of the local x axis (degrees clockwise from true north)
+    pub heading: f64,
+    }
+
+impl Default for GeoOrigin {
+    fn default() -> GeoOrigin {
+        GeoOrigin::new(0.0, 0.0, 0.0)
+        }
+    }
+
+impl GeoOrigin {
+    pub fn new(latitude: f64, longitude: f64, altitude: f64) -> GeoOrigin {
+        GeoOrigin {
+            latitude,
+            longitude,
+            altitude,
+            heading: 0.0,
+            }
+        }
+
+    /// Convert a local position to (latitude, longitude, altitude)
+    pub fn to_wgs84(&self, position: &Vector3<f32>) -> (f64, f64, f64) {
+        let (sh, ch) = self.heading.to_radians().sin_cos();
+        let x = position[0] as f64;
+        let y = position[1] as f64;
+        let north = ch * x - sh * y;
+        let east = sh * x + ch * y;
+
+        let latitude = self.latitude + (north / EARTH_RADIUS).to_degrees();
+        let longitude = self.longitude + (east / (EARTH_RADIUS * self.latitude.to_radians().cos())).to_degrees();
+        let altitude = self.altitude - position[2] as f64;
+        (latitude, longitude, altitude)
+        }
+
+    /// Convert (latitude, longitude, altitude) to a local position
+    pub fn from_wgs84(&self, latitude: f64, longitude: f64, altitude: f64) -> Vector3<f32> {
+        let north = (latitude - self.latitude).to_radians() * EARTH_RADIUS;
+        let east = (longitude - self.longitude).to_radians() * EARTH_RADIUS * self.latitude.to_radians().cos();
+        let (sh, ch) = self.heading.to_radians().sin_cos();
+        Vector3::new(
+            (ch * north + sh * east) as f32,
+            (-sh * north + ch * east) as f32,
+            (self.altitude - altitude) as f32,
+            )
+        }
+
+    /// Convert an attitude relative to true north to one relative to the local x axis
+    pub fn attitude_from_wgs84(&self, attitude: &UnitQuaternion<f32>) -> UnitQuaternion<f32> {
+        UnitQuaternion::from_axis_angle(&Vector3::z_axis(), -self.heading.to_radians() as f32) * attitude
+        }
+    }
+
+/// Axis convention for local positions
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+#[derive(Default)]
+pub enum LocalFrame {
+    /// x north (or along the origin heading), y east, z down. Used throughout arusti
+    #[default]
+    Ned,
+    /// x east, y north (or along the origin heading), z up
+    Enu,
+    }
+
+impl std::str::FromStr for LocalFrame {
+    type Err = String;
+
+    fn from_str(frame: &str) -> Result<LocalFrame, String> {
+        match frame.to_lowercase().as_str() {
+            "ned" => Ok(LocalFrame::Ned),
+            "enu" => Ok(LocalFrame::Enu),
+            _ => Err(format!("Unknown local frame: {}", frame)),
+            }
+        }
+    }
+
+impl LocalFrame {
+    /// Rotation from the NED frame to this frame
+    fn rotation(&self) -> UnitQuaternion<f32> {
+        match self {
+            LocalFrame::Ned => UnitQuaternion::identity(),
+            LocalFrame::Enu => UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(Matrix3::new(
+                0.0, 1.0, 0.0,
+                1.0, 0.0, 0.0,
+                0.0, 0.0, -1.0,
+                ))),
+            }
+        }
+
+    /// Convert a NED position to this frame
+    pub fn from_ned(&self, position: &Vector3<f32>) -> Vector3<f32> {
+        self.rotation() * position
+        }
+
+    /// Convert a body-to-NED attitude to a body-to-local attitude. The body axes are unchanged
+    pub fn attitude_from_ned(&self, attitude: &UnitQuaternion<f32>) -> UnitQuaternion<f32> {
+        self.rotation() * attitude
+        }
+    }
@@ -0,0 +1,249 @@
+extern crate nalgebra as na;
+use na::{Unit, UnitQuaternion, Vector3};
+
+use crate::types::{Element, Figure, TurnRollDirection};
+
+/// Horizontal component of a direction below which it is treated as vertical
+const VERTICAL_TOLERANCE: f32 = 1.0e-3;
+/// Pitch within which a line is level or vertical (degrees)
+const PITCH_TOLERANCE: f32 = 0.5;
+
+/// Vertical direction of a line
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum LineDirection {
+    Level,
+    /// Climbing at less than vertical, e.g. a 45° up line
+    Climbing,
+    Diving,
+    VerticalUp,
+    VerticalDown,
+    }
+
+impl LineDirection {
+    pub fn from_pitch(pitch: f32) -> LineDirection {
+        if pitch.abs() <= PITCH_TOLERANCE {
+            LineDirection::Level
+            }
+        else if pitch >= 90.0 - PITCH_TOLERANCE {
+            LineDirection::VerticalUp
+            }
+        else if pitch <= PITCH_TOLERANCE - 90.0 {
+            LineDirection::VerticalDown
+            }
+        else if pitch > 0.0 {
+            LineDirection::Climbing
+            }
+        else {
+            LineDirection::Diving
+            }
+        }
+    }
+
+/// Attitude of the aircraft at an element boundary.
+///
+/// Directions are in the frame of the figure entry, with x along the entry heading, y to the left of it and z up.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub struct FlightState {
+    /// Direction of flight
+    pub forward: Vector3<f32>,
+    /// Direction of the top of the aircraft
+    pub up: Vector3<f32>,
+    /// Pitch of the flight path above the horizon (degrees)
+    pub pitch: f32,
+    /// Heading from the entry heading, positive to the right and up to 180. Kept from the previous state when vertical (degrees)
+    pub heading: f32,
+    /// Whether the top of the aircraft faces down. Kept from the previous state when vertical or on knife edge
+    pub inverted: bool,
+    }
+
+/// Round away float error from the rotations, without negative zero
+fn tidy(angle: f32) -> f32 {
+    (angle * 1000.0).round() / 1000.0 + 0.0
+    }
+
+impl FlightState {
+    /// Level flight along the entry heading
+    pub fn entry(inverted: bool) -> FlightState {
+        FlightState {
+            forward: Vector3::x(),
+            up: if inverted { -Vector3::z() } else { Vector3::z() },
+            pitch: 0.0,
+            heading: 0.0,
+            inverted,
+            }
+        }
+
+    /// State after rotating the aircraft, updating the angles from the new directions
+    fn rotated(&self, axis: &Vector3<f32>, angle: f32) -> FlightState {
+        let rotation = UnitQuaternion::from_axis_angle(&Unit::new_normalize(*axis), angle.to_radians());
+        let forward = rotation * self.forward;
+        let up = rotation * self.up;
+        let horizontal = forward.xy().norm();
+        let heading = if horizontal > VERTICAL_TOLERANCE { tidy((-forward[1]).atan2(forward[0]).to_degrees()) } else { self.heading };
+        let heading = if heading <= -180.0 { heading + 360.0 } else { heading };
+        let inverted = if horizontal > VERTICAL_TOLERANCE && up[2].abs() > VERTICAL_TOLERANCE { up[2] < 0.0 } else { self.inverted };
+        FlightState {
+            forward,
+            up,
+            pitch: tidy(forward[2].atan2(horizontal).to_degrees()),
+            heading,
+            inverted,
+            }
+        }
+
+    /// Direction of the right wing, the axis of a pull
+    fn right(&self) -> Vector3<f32> {
+        self.forward.cross(&self.up)
+        }
+
+    pub fn line_direction(&self) -> LineDirection {
+        LineDirection::from_pitch(self.pitch)
+        }
+    }
+
+impl Element {
+    /// Whether the element rotates the aircraft about its longitudinal axis: a roll, flick or spin.
+    ///  Rolls in rolling turns are part of the turn
+    pub fn is_rolling(&self) -> bool {
+        matches!(self, Element::Roll { .. } | Element::Flick { .. } | Element::Spin { .. })
+        }
+
+    /// Angle pulled or pushed through (degrees)
+    pub fn looping_angle(&self) -> f32 {
+        match self {
+            Element::Radius { angle, .. } => angle.abs(),
+            _ => 0.0,
+            }
+        }
+
+    /// Angle rolled through by a roll, flick or spin, -ve to the left (degrees)
+    pub fn rolling_angle(&self) -> f32 {
+        match *self {
+            Element::Roll { angle, direction, .. } | Element::Flick { angle, direction, .. } | Element::Spin { angle, direction, .. } => {
+                direction.sign() * angle
+                },
+            _ => 0.0,
+            }
+        }
+
+    /// Number of parts a roll is flown in, one more than its hesitations. Divisions are points per 360°,
+    ///  so a 2x4 roll through 180° is flown in two parts. Other elements are flown in one part
+    pub fn roll_parts(&self) -> u8 {
+        match *self {
+            Element::Roll { angle, divisions, .. } if divisions > 1 => ((angle * divisions as f32 / 360.0).round() as u8).max(1),
+            _ => 1,
+            }
+        }
+
+    /// Angle rolled through in a rolling turn, +ve towards the inside of the turn (degrees)
+    pub fn turn_roll(&self) -> f32 {
+        match *self {
+            Element::Turn { rolls, roll_direction: TurnRollDirection::Outside, .. } => -rolls * 360.0,
+            Element::Turn { rolls, .. } => rolls * 360.0,
+            _ => 0.0,
+            }
+        }
+
+    /// State of the aircraft after flying the element from a state
+    pub fn apply(&self, state: &FlightState) -> FlightState {
+        match *self {
+            Element::Line { .. } | Element::Combining(_) => *state,
+            Element::Radius { angle, .. } => state.rotated(&state.right(), angle),
+            Element::Roll { angle, direction, .. } | Element::Flick { angle, direction, .. } | Element::Spin { angle, direction, .. } => {
+                state.rotated(&state.forward, direction.sign() * angle)
+                },
+            Element::Turn { angle, rolls, roll_direction, .. } => {
+                let turned = state.rotated(&-Vector3::z(), angle);
+                let sign = if roll_direction == TurnRollDirection::Outside { -1.0 } else { 1.0 };
+                turned.rotated(&turned.forward, sign * rolls * 360.0)
+                },
+            Element::Stall { yaw, pitch } => {
+                let yawed = state.rotated(&-state.up, yaw);
+                yawed.rotated(&yawed.right(), pitch)
+                },
+            }
+        }
+    }
+
+/// A rolling element and where it is flown
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub struct RollPosition {
+    /// Index of the element in the figure
+    pub element: usize,
+    /// Direction of flight during the roll
+    pub direction: LineDirection,
+    }
+
+/// The attitude of a figure through its elements
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct FigureGeometry {
+    /// State at each element boundary: the entry, then the exit of each element
+    pub states: Vec<FlightState>,
+    /// Total angle pulled and pushed through in radii (degrees)
+    pub looping_angle: f32,
+    pub rolls: Vec<RollPosition>,
+    /// Index and direction of each line
+    pub lines: Vec<(usize, LineDirection)>,
+    }
+
+impl FigureGeometry {
+    /// State at the start of an element
+    pub fn entry(&self, element: usize) -> &FlightState {
+        &self.states[element]
+        }
+
+    /// State at the end of an element
+    pub fn exit(&self, element: usize) -> &FlightState {
+        &self.states[element + 1]
+        }
+
+    pub fn roll_count(&self) -> usize {
+        self.rolls.len()
+        }
+
+    /// Whether the aircraft is inverted at the end of the figure, whatever its exit line is marked as
+    pub fn ends_inverted(&self) -> bool {
+        self.states.last().is_some_and(|state| state.inverted)
+        }
+    }
+
+impl Figure {
+    /// Fly the elements from the entry, which is inverted if the first element is an inverted line
+    pub fn geometry(&self) -> FigureGeometry {
+        let inverted = self.elements.first().is_some_and(Element::inverted);
+        let mut states = vec![FlightState::entry(inverted)];
+        for element in &self.elements {
+            let next = element.apply(states.last().unwrap());
+            states.push(next);
+            }
+
+        let mut rolls = Vec::new();
+        let mut lines = Vec::new();
+        for (i, (element, state)) in self.elements.iter().zip(states.iter()).enumerate() {
+            if element.is_rolling() {
+                rolls.push(RollPosition { element: i, direction: state.line_direction() });
+                }
+            if let Element::Line { .. } = element {
+                lines.push((i, state.line_direction()));
+                }
+            }
+
+        FigureGeometry {
+            looping_angle: self.elements.iter().map(Element::looping_angle).sum(),
+            states,
+            rolls,
+            lines,
+            }
+        }
+    }
@@ -0,0 +1,163 @@
+use std::io;
+use std::str::FromStr;
+
+extern crate nalgebra as na;
+use na::{Vector3, Quaternion, UnitQuaternion};
+
+use super::{Fix, FixPosition, invalid};
+
+/// Columns holding the position
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum PositionColumns {
+    /// North, east and down in the local frame (m)
+    Local([String; 3]),
+    /// Latitude and longitude (degrees), and altitude above mean sea level (m)
+    Geodetic([String; 3]),
+    }
+
+/// Columns holding the body-to-earth attitude
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum AttitudeColumns {
+    /// Quaternion w, x, y and z parts
+    Quaternion([String; 4]),
+    /// Roll, pitch and yaw from north (degrees)
+    Euler([String; 3]),
+    }
+
+/// Names of the CSV columns to read. The default reads the columns written by `export::write_csv`
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct ColumnMapping {
+    pub time: String,
+    /// Multiplier to convert the time column to seconds, e.g. 0.001 for milliseconds
+    pub time_scale: f64,
+    pub position: PositionColumns,
+    /// Attitude columns. Without them the attitude is taken along the track
+    pub attitude: Option<AttitudeColumns>,
+    }
+
+fn names<const N: usize>(names: [&str; N]) -> [String; N] {
+    names.map(str::to_string)
+    }
+
+impl Default for ColumnMapping {
+    fn default() -> ColumnMapping {
+        ColumnMapping {
+            time: "time".to_string(),
+            time_scale: 1.0,
+            position: PositionColumns::Local(names(["x", "y", "z"])),
+            attitude: Some(AttitudeColumns::Quaternion(names(["qw", "qx", "qy", "qz"]))),
+            }
+        }
+    }
+
+/// Columns mapped to all the keys, or an error naming the first unmapped key
+fn mapped<const N: usize>(get: &dyn Fn(&str) -> Option<String>, keys: [&str; N]) -> Result<[String; N], String> {
+    if let Some(key) = keys.iter().find(|key| get(key).is_none()) {
+        return Err(format!("missing column key: {}", key));
+        }
+    Ok(keys.map(|key| get(key).unwrap_or_default()))
+    }
+
+impl FromStr for ColumnMapping {
+    type Err = String;
+
+    /// Parse a list of key=column pairs, e.g. "time=t,time_scale=0.001,lat=Lat,lon=Lng,alt=Alt,roll=Roll,pitch=Pitch,yaw=Yaw".
+    ///  Keys are time, time_scale, x, y, z, lat, lon, alt, qw, qx, qy, qz, roll, pitch and yaw.
+    ///  Unmapped time and local position keys default to their own names, and the attitude to none
+    fn from_str(mapping: &str) -> Result<ColumnMapping, String> {
+        let mut pairs = Vec::new();
+        for pair in mapping.split(',').filter(|pair| !pair.trim().is_empty()) {
+            let (key, column) = pair.split_once('=').ok_or_else(|| format!("expected key=column, found {}", pair))?;
+            let key = key.trim();
+            if !["time", "time_scale", "x", "y", "z", "lat", "lon", "alt", "qw", "qx", "qy", "qz", "roll", "pitch", "yaw"].contains(&key) {
+                return Err(format!("Unknown column key: {}", key));
+                }
+            pairs.push((key.to_string(), column.trim().to_string()));
+            }
+        let get = |key: &str| pairs.iter().find(|(name, _)| name == key).map(|(_, column)| column.clone());
+        let any = |keys: &[&str]| keys.iter().any(|key| get(key).is_some());
+
+        let position = if any(&["lat", "lon", "alt"]) {
+            PositionColumns::Geodetic(mapped(&get, ["lat", "lon", "alt"])?)
+            }
+        else {
+            let column = |key: &str| get(key).unwrap_or_else(|| key.to_string());
+            PositionColumns::Local([column("x"), column("y"), column("z")])
+            };
+        let attitude = if any(&["qw", "qx", "qy", "qz"]) {
+            Some(AttitudeColumns::Quaternion(mapped(&get, ["qw", "qx", "qy", "qz"])?))
+            }
+        else if any(&["roll", "pitch", "yaw"]) {
+            Some(AttitudeColumns::Euler(mapped(&get, ["roll", "pitch", "yaw"])?))
+            }
+        else {
+            None
+            };
+        let time_scale = match get("time_scale") {
+            Some(scale) => scale.parse().map_err(|_| format!("invalid time_scale: {}", scale))?,
+            None => 1.0,
+            };
+
+        Ok(ColumnMapping {
+            time: get("time").unwrap_or_else(|| "time".to_string()),
+            time_scale,
+            position,
+            attitude,
+            })
+        }
+    }
+
+/// Read fixes from CSV with a header row, using the given column mapping. Other columns are ignored
+pub fn read_csv(text: &str, mapping: &ColumnMapping) -> io::Result<Vec<Fix>> {
+    let mut lines = text.lines();
+    let header = lines.next().ok_or_else(|| invalid("missing header row".to_string()))?;
+    let names: Vec<&str> = header.split(',').map(|name| name.trim()).collect();
+    let index = |column: &String| names.iter().position(|name| name == column).ok_or_else(|| invalid(format!("missing column {}", column)));
+
+    let time_index = index(&mapping.time)?;
+    let position_indices = match &mapping.position {
+        PositionColumns::Local(columns) | PositionColumns::Geodetic(columns) => columns.iter().map(index).collect::<io::Result<Vec<usize>>>()?,
+        };
+    let attitude_indices = match &mapping.attitude {
+        Some(AttitudeColumns::Quaternion(columns)) => columns.iter().map(index).collect::<io::Result<Vec<usize>>>()?,
+        Some(AttitudeColumns::Euler(columns)) => columns.iter().map(index).collect::<io::Result<Vec<usize>>>()?,
+        None => Vec::new(),
+        };
+
+    let mut fixes = Vec::new();
+    for (line_idx, line) in lines.enumerate() {
+        if line.trim().is_empty() {
+            continue;
+            }
+        let fields: Vec<&str> = line.split(',').collect();
+        let value = |i: usize| fields.get(i).and_then(|field| field.trim().parse::<f64>().ok())
+            .ok_or_else(|| invalid(format!("invalid value on line {}", line_idx + 2)));
+        let values = |indices: &[usize]| indices.iter().map(|&i| value(i)).collect::<io::Result<Vec<f64>>>();
+
+        let position = values(&position_indices)?;
+        let position = match mapping.position {
+            PositionColumns::Local(_) => FixPosition::Local(Vector3::new(position[0] as f32, position[1] as f32, position[2] as f32)),
+            PositionColumns::Geodetic(_) => FixPosition::Geodetic(position[0], position[1], position[2]),
+            };
+        let attitude = values(&attitude_indices)?;
+        let attitude = match mapping.attitude {
+            Some(AttitudeColumns::Quaternion(_)) => Some(UnitQuaternion::from_quaternion(Quaternion::new(
+                attitude[0] as f32, attitude[1] as f32, attitude[2] as f32, attitude[3] as f32))),
+            Some(AttitudeColumns::Euler(_)) => Some(UnitQuaternion::from_euler_angles(
+                attitude[0].to_radians() as f32, attitude[1].to_radians() as f32, attitude[2].to_radians() as f32)),
+            None => None,
+            };
+        fixes.push(Fix {
+            time: value(time_index)? * mapping.time_scale,
+            position,
+            attitude,
+            });
+        }
+    Ok(fixes)
+    }
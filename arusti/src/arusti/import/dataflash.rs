@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::io;
+
+extern crate nalgebra as na;
+use na::UnitQuaternion;
+
+use super::{Fix, FixPosition, interpolate_attitude};
+
+const HEADER: [u8; 2] = [0xA3, 0x95];
+const FMT_TYPE: u8 = 0x80;
+/// Length of a FMT message: header, type, length, 4 byte name, 16 byte format and 64 byte columns
+const FMT_LENGTH: usize = 89;
+
+/// Layout of a message type, from its FMT message
+struct MessageFormat {
+    name: String,
+    length: usize,
+    format: String,
+    columns: Vec<String>,
+    }
+
+/// A logged message with its numeric fields
+struct Message {
+    name: String,
+    fields: HashMap<String, f64>,
+    }
+
+impl Message {
+    /// Time of the message (s)
+    fn time(&self) -> Option<f64> {
+        self.fields.get("TimeUS").map(|time| time * 1.0e-6)
+            .or_else(|| self.fields.get("TimeMS").map(|time| time * 1.0e-3))
+        }
+
+    fn field(&self, name: &str) -> Option<f64> {
+        self.fields.get(name).cloned()
+        }
+    }
+
+fn read_string(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).trim_end_matches('\0').to_string()
+    }
+
+/// Size in bytes and scale of a DataFlash format character. Scale is None for fields that are not numbers
+fn field_layout(format: char) -> Option<(usize, Option<f64>)> {
+    match format {
+        'b' | 'B' | 'M' => Some((1, Some(1.0))),
+        'h' | 'H' => Some((2, Some(1.0))),
+        'i' | 'I' | 'f' => Some((4, Some(1.0))),
+        'd' | 'q' | 'Q' => Some((8, Some(1.0))),
+        'c' | 'C' => Some((2, Some(0.01))),
+        'e' | 'E' => Some((4, Some(0.01))),
+        'L' => Some((4, Some(1.0e-7))),
+        'n' => Some((4, None)),
+        'N' => Some((16, None)),
+        'Z' => Some((64, None)),
+        'a' => Some((64, None)),
+        _ => None,
+        }
+    }
+
+fn read_field(format: char, bytes: &[u8]) -> f64 {
+    match format {
+        'b' => bytes[0] as i8 as f64,
+        'B' | 'M' => bytes[0] as f64,
+        'h' | 'c' => i16::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        'H' | 'C' => u16::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        'i' | 'e' | 'L' => i32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        'I' | 'E' => u32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        'f' => f32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        'd' => f64::from_le_bytes(bytes.try_into().unwrap()),
+        'q' => i64::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        'Q' => u64::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        _ => f64::NAN,
+        }
+    }
+
+fn decode(format: &MessageFormat, payload: &[u8]) -> Message {
+    let mut fields = HashMap::new();
+    let mut offset = 0;
+    for (format_char, column) in format.format.chars().zip(format.columns.iter()) {
+        let (size, scale) = match field_layout(format_char) {
+            Some(layout) => layout,
+            None => break,
+            };
+        if offset + size > payload.len() {
+            break;
+            }
+        if let Some(scale) = scale {
+            fields.insert(column.clone(), read_field(format_char, &payload[offset..offset + size]) * scale);
+            }
+        offset += size;
+        }
+    Message { name: format.name.clone(), fields }
+    }
+
+/// Messages of a binary log, skipping any bytes that cannot be decoded
+fn read_binary(data: &[u8]) -> Vec<Message> {
+    let mut formats: HashMap<u8, MessageFormat> = HashMap::new();
+    let mut messages = Vec::new();
+    let mut i = 0;
+    while i + 3 <= data.len() {
+        if data[i..i + 2] != HEADER {
+            i += 1;
+            continue;
+            }
+        let msg_type = data[i + 2];
+        if msg_type == FMT_TYPE && i + FMT_LENGTH <= data.len() {
+            let body = &data[i + 3..i + FMT_LENGTH];
+            let format = MessageFormat {
+                name: read_string(&body[2..6]),
+                length: body[1] as usize,
+                format: read_string(&body[6..22]),
+                columns: read_string(&body[22..86]).split(',').map(str::to_string).collect(),
+                };
+            formats.insert(body[0], format);
+            i += FMT_LENGTH;
+            continue;
+            }
+        match formats.get(&msg_type) {
+            Some(format) if format.length >= 3 && i + format.length <= data.len() => {
+                messages.push(decode(format, &data[i + 3..i + format.length]));
+                i += format.length;
+                },
+            _ => i += 1,
+            }
+        }
+    messages
+    }
+
+/// Messages of a text log, where values are already scaled
+fn read_text(text: &str) -> Vec<Message> {
+    let mut columns: HashMap<String, Vec<String>> = HashMap::new();
+    let mut messages = Vec::new();
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+        match fields.first() {
+            Some(&"FMT") if fields.len() > 5 => {
+                columns.insert(fields[3].to_string(), fields[5..].iter().map(|column| column.to_string()).collect());
+                },
+            Some(name) => if let Some(names) = columns.get(*name) {
+                let fields = names.iter().zip(fields[1..].iter())
+                    .filter_map(|(column, value)| value.parse::<f64>().ok().map(|value| (column.clone(), value)))
+                    .collect();
+                messages.push(Message { name: name.to_string(), fields });
+                },
+            None => (),
+            }
+        }
+    messages
+    }
+
+/// Read an ArduPilot DataFlash log, binary or text.
+///  Positions come from the EKF POS messages, or GPS messages with a 3D fix where there are none.
+///  Attitudes come from ATT messages, interpolated to the time of each position
+pub fn read_dataflash(data: &[u8]) -> io::Result<Vec<Fix>> {
+    let messages = if data.starts_with(&HEADER) {
+        read_binary(data)
+        }
+    else {
+        read_text(&String::from_utf8_lossy(data))
+        };
+
+    let attitudes: Vec<(f64, UnitQuaternion<f32>)> = messages.iter()
+        .filter(|message| message.name == "ATT")
+        .filter_map(|message| {
+            let angle = |name: &str| message.field(name).map(|angle| angle.to_radians() as f32);
+            Some((message.time()?, UnitQuaternion::from_euler_angles(angle("Roll")?, angle("Pitch")?, angle("Yaw")?)))
+            })
+        .collect();
+
+    let source = if messages.iter().any(|message| message.name == "POS") { "POS" } else { "GPS" };
+    let fixes = messages.iter()
+        .filter(|message| message.name == source)
+        .filter(|message| message.field("Status").map(|status| status >= 3.0).unwrap_or(true))
+        .filter_map(|message| {
+            let time = message.time()?;
+            Some(Fix {
+                time,
+                position: FixPosition::Geodetic(message.field("Lat")?, message.field("Lng")?, message.field("Alt")?),
+                attitude: interpolate_attitude(&attitudes, time),
+                })
+            })
+        .collect();
+    Ok(fixes)
+    }
@@ -0,0 +1,62 @@
+use std::io;
+
+use super::{Fix, FixPosition, invalid};
+
+/// Days from 1970-01-01 to a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+    }
+
+/// Seconds since 1970 from an ISO 8601 timestamp, e.g. 2026-10-19T12:00:00.5Z or with a +01:00 offset
+fn parse_timestamp(text: &str) -> Option<f64> {
+    let (date, time) = text.trim().split_at(text.trim().find('T')?);
+    let mut date_fields = date.split('-').map(|field| field.parse::<i64>());
+    let (year, month, day) = (date_fields.next()?.ok()?, date_fields.next()?.ok()?, date_fields.next()?.ok()?);
+
+    let time = &time[1..];
+    let (time, offset) = match time.find(['Z', '+', '-']) {
+        Some(i) if &time[i..] == "Z" => (&time[..i], 0.0),
+        Some(i) => {
+            let sign = if time[i..].starts_with('-') { -1.0 } else { 1.0 };
+            let mut offset_fields = time[i + 1..].split(':').map(|field| field.parse::<f64>());
+            let hours = offset_fields.next()?.ok()?;
+            let minutes = offset_fields.next().unwrap_or(Ok(0.0)).ok()?;
+            (&time[..i], sign * (hours * 3600.0 + minutes * 60.0))
+            },
+        None => (time, 0.0),
+        };
+    let mut time_fields = time.split(':').map(|field| field.parse::<f64>());
+    let (hours, minutes, seconds) = (time_fields.next()?.ok()?, time_fields.next()?.ok()?, time_fields.next()?.ok()?);
+    Some(days_from_civil(year, month, day) as f64 * 86400.0 + hours * 3600.0 + minutes * 60.0 + seconds - offset)
+    }
+
+fn child_text<'a>(node: roxmltree::Node<'a, 'a>, name: &str) -> Option<&'a str> {
+    node.children().find(|child| child.tag_name().name() == name).and_then(|child| child.text())
+    }
+
+/// Read the track points of a GPX file
+pub fn read_gpx(text: &str) -> io::Result<Vec<Fix>> {
+    let doc = roxmltree::Document::parse(text).map_err(|error| invalid(error.to_string()))?;
+    doc.descendants()
+        .filter(|node| node.tag_name().name() == "trkpt")
+        .map(|point| {
+            let error = || invalid(format!("invalid track point on line {}", doc.text_pos_at(point.range().start).row));
+            let attribute = |name: &str| point.attribute(name).and_then(|value| value.parse::<f64>().ok()).ok_or_else(error);
+            let time = child_text(point, "time").and_then(parse_timestamp).ok_or_else(error)?;
+            let altitude = match child_text(point, "ele") {
+                Some(elevation) => elevation.trim().parse().map_err(|_| error())?,
+                None => 0.0,
+                };
+            Ok(Fix {
+                time,
+                position: FixPosition::Geodetic(attribute("lat")?, attribute("lon")?, altitude),
+                attitude: None,
+                })
+            })
+        .collect()
+    }
@@ -0,0 +1,41 @@
+use std::io;
+
+use super::{Fix, FixPosition, invalid, parse_time_of_day, unwrap_days};
+
+/// Parse an IGC angle of degrees, minutes and thousandths of minutes followed by the hemisphere
+fn parse_igc_angle(text: &str, degree_digits: usize, negative: char) -> Option<f64> {
+    let degrees: f64 = text.get(0..degree_digits)?.parse().ok()?;
+    let thousandths: f64 = text.get(degree_digits..degree_digits + 5)?.parse().ok()?;
+    let angle = degrees + thousandths / 60_000.0;
+    match text.get(degree_digits + 5..)? {
+        hemisphere if hemisphere.starts_with(negative) => Some(-angle),
+        _ => Some(angle),
+        }
+    }
+
+/// Read the B record fixes of an IGC file. GNSS altitude is used, or pressure altitude where there is none
+pub fn read_igc(text: &str) -> io::Result<Vec<Fix>> {
+    let mut fixes = Vec::new();
+    for (line_idx, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        if !line.starts_with('B') {
+            continue;
+            }
+        let error = || invalid(format!("invalid B record on line {}", line_idx + 1));
+        let field = |start: usize, end: usize| line.get(start..end).ok_or_else(error);
+
+        let time = parse_time_of_day(field(1, 7)?).ok_or_else(error)?;
+        let latitude = parse_igc_angle(field(7, 15)?, 2, 'S').ok_or_else(error)?;
+        let longitude = parse_igc_angle(field(15, 24)?, 3, 'W').ok_or_else(error)?;
+        let pressure_altitude: f64 = field(25, 30)?.parse().map_err(|_| error())?;
+        let gnss_altitude: f64 = field(30, 35)?.parse().map_err(|_| error())?;
+        let altitude = if gnss_altitude != 0.0 { gnss_altitude } else { pressure_altitude };
+        fixes.push(Fix {
+            time,
+            position: FixPosition::Geodetic(latitude, longitude, altitude),
+            attitude: None,
+            });
+        }
+    unwrap_days(&mut fixes);
+    Ok(fixes)
+    }
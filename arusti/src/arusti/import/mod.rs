@@ -0,0 +1,221 @@
+use std::io;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+
+extern crate nalgebra as na;
+use na::{Vector3, UnitQuaternion};
+
+use crate::data_generation::DataPoint;
+use crate::geo::{GeoOrigin, LocalFrame};
+
+mod dataflash;
+mod ulog;
+mod nmea;
+mod gpx;
+mod igc;
+mod csv;
+
+pub use dataflash::read_dataflash;
+pub use ulog::read_ulog;
+pub use nmea::read_nmea;
+pub use gpx::read_gpx;
+pub use igc::read_igc;
+pub use csv::{ColumnMapping, PositionColumns, AttitudeColumns, read_csv};
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub enum ImportFormat {
+    /// ArduPilot DataFlash log, either binary (.bin) or text (.log). Uses POS or GPS for position and ATT for attitude
+    DataFlash,
+    /// PX4 ULog. Uses vehicle_global_position or vehicle_gps_position for position and vehicle_attitude for attitude
+    ULog,
+    /// NMEA 0183 GGA sentences
+    Nmea,
+    /// GPX track points
+    Gpx,
+    /// IGC flight recorder B records
+    Igc,
+    /// Comma separated values with a header row, read with the ImportOptions column mapping
+    Csv,
+    }
+
+impl FromStr for ImportFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<ImportFormat, String> {
+        match format.to_lowercase().as_str() {
+            "dataflash" | "bin" | "log" => Ok(ImportFormat::DataFlash),
+            "ulog" | "ulg" => Ok(ImportFormat::ULog),
+            "nmea" => Ok(ImportFormat::Nmea),
+            "gpx" => Ok(ImportFormat::Gpx),
+            "igc" => Ok(ImportFormat::Igc),
+            "csv" => Ok(ImportFormat::Csv),
+            _ => Err(format!("Unknown import format: {}", format)),
+            }
+        }
+    }
+
+impl ImportFormat {
+    /// Guess the format from a file extension
+    pub fn from_path(path: &Path) -> Option<ImportFormat> {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| extension.parse().ok())
+        }
+    }
+
+/// Position of a logged fix
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum FixPosition {
+    /// (latitude, longitude, altitude above mean sea level) in degrees and metres
+    Geodetic(f64, f64, f64),
+    /// Already in the local NED frame (m)
+    Local(Vector3<f32>),
+    }
+
+/// A sample read from a log, before conversion to a DataPoint
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub struct Fix {
+    /// Time of the sample (s). Only differences between fixes are used
+    pub time: f64,
+    pub position: FixPosition,
+    /// Body-to-NED attitude, if the log records it. Relative to true north for Geodetic positions, or the local frame for Local ones
+    pub attitude: Option<UnitQuaternion<f32>>,
+    }
+
+/// Options for converting logged fixes to DataPoints
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Default)]
+pub struct ImportOptions {
+    /// Placement of the local frame, e.g. at the box centre with x along the box axis. Defaults to the first fix, with x north
+    pub origin: Option<GeoOrigin>,
+    /// Axes of the local frame. The rest of arusti expects Ned
+    pub frame: LocalFrame,
+    /// Columns to read for the Csv format
+    pub columns: ColumnMapping,
+    }
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+    }
+
+/// Read points in the given format
+pub fn read_points<R: Read>(format: ImportFormat, mut reader: R, options: &ImportOptions) -> io::Result<Vec<DataPoint>> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    let text = || String::from_utf8(data.clone()).map_err(|error| invalid(error.to_string()));
+    let fixes = match format {
+        ImportFormat::DataFlash => read_dataflash(&data)?,
+        ImportFormat::ULog => read_ulog(&data)?,
+        ImportFormat::Nmea => read_nmea(&text()?)?,
+        ImportFormat::Gpx => read_gpx(&text()?)?,
+        ImportFormat::Igc => read_igc(&text()?)?,
+        ImportFormat::Csv => read_csv(&text()?, &options.columns)?,
+        };
+    if fixes.is_empty() {
+        return Err(invalid("no position fixes found".to_string()));
+        }
+    Ok(fixes_to_points(&fixes, options))
+    }
+
+/// Attitude flying straight along the track, without roll
+fn track_attitude(velocity: &Vector3<f32>) -> UnitQuaternion<f32> {
+    let horizontal = velocity.xy().norm();
+    if horizontal == 0.0 && velocity[2] == 0.0 {
+        return UnitQuaternion::identity();
+        }
+    UnitQuaternion::from_euler_angles(0.0, (-velocity[2]).atan2(horizontal), velocity[1].atan2(velocity[0]))
+    }
+
+/// Convert fixes to DataPoints in the local frame, with time zero at the first fix.
+///  Fixes without an attitude are given one along the track
+pub fn fixes_to_points(fixes: &[Fix], options: &ImportOptions) -> Vec<DataPoint> {
+    let origin = options.origin.unwrap_or_else(|| {
+        fixes.iter()
+            .find_map(|fix| match fix.position {
+                FixPosition::Geodetic(latitude, longitude, altitude) => Some(GeoOrigin::new(latitude, longitude, altitude)),
+                FixPosition::Local(_) => None,
+                })
+            .unwrap_or_default()
+        });
+    let positions: Vec<Vector3<f32>> = fixes.iter()
+        .map(|fix| match fix.position {
+            FixPosition::Geodetic(latitude, longitude, altitude) => origin.from_wgs84(latitude, longitude, altitude),
+            FixPosition::Local(position) => position,
+            })
+        .collect();
+    let start_time = fixes.first().map(|fix| fix.time).unwrap_or(0.0);
+
+    fixes.iter().enumerate()
+        .map(|(i, fix)| {
+            let attitude = match (fix.attitude, fix.position) {
+                (Some(attitude), FixPosition::Geodetic(..)) => origin.attitude_from_wgs84(&attitude),
+                (Some(attitude), FixPosition::Local(_)) => attitude,
+                (None, _) => {
+                    let before = i.saturating_sub(1);
+                    let after = (i + 1).min(fixes.len() - 1);
+                    let duration = (fixes[after].time - fixes[before].time) as f32;
+                    let velocity = if duration > 0.0 { (positions[after] - positions[before]) / duration } else { Vector3::zeros() };
+                    track_attitude(&velocity)
+                    },
+                };
+            DataPoint {
+                time: (fix.time - start_time) as f32,
+                position: options.frame.from_ned(&positions[i]),
+                attitude: options.frame.attitude_from_ned(&attitude),
+                }
+            })
+        .collect()
+    }
+
+/// Attitude at a time, interpolated between samples sorted by time. The samples are searched by bisection,
+///  as a long log has an attitude sample for every few milliseconds
+fn interpolate_attitude(samples: &[(f64, UnitQuaternion<f32>)], time: f64) -> Option<UnitQuaternion<f32>> {
+    let after = samples.partition_point(|(sample_time, _)| *sample_time < time);
+    match after {
+        0 => samples.first().map(|(_, attitude)| *attitude),
+        i if i < samples.len() => {
+            let (start, from) = samples[i - 1];
+            let (end, to) = samples[i];
+            let fraction = if end > start { ((time - start) / (end - start)) as f32 } else { 0.0 };
+            Some(from.try_slerp(&to, fraction, 1.0e-6).unwrap_or(to))
+            },
+        _ => samples.last().map(|(_, attitude)| *attitude),
+        }
+    }
+
+/// Time of day in seconds from hhmmss with optional fractional seconds
+fn parse_time_of_day(text: &str) -> Option<f64> {
+    if text.len() < 6 || !text.is_char_boundary(6) {
+        return None;
+        }
+    let hours: f64 = text[0..2].parse().ok()?;
+    let minutes: f64 = text[2..4].parse().ok()?;
+    let seconds: f64 = text[4..].parse().ok()?;
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+    }
+
+/// Make times of day increase through midnight
+fn unwrap_days(fixes: &mut [Fix]) {
+    let mut offset = 0.0;
+    let mut last = None;
+    for fix in fixes.iter_mut() {
+        if let Some(last) = last {
+            if fix.time + offset < last - 43200.0 {
+                offset += 86400.0;
+                }
+            }
+        fix.time += offset;
+        last = Some(fix.time);
+        }
+    }
@@ -0,0 +1,60 @@
+use std::io;
+
+use super::{Fix, FixPosition, parse_time_of_day, unwrap_days};
+
+/// Parse an NMEA angle of degrees and decimal minutes, e.g. 5230.1234
+fn parse_nmea_angle(text: &str, hemisphere: &str, negative: &str) -> Option<f64> {
+    let value: f64 = text.parse().ok()?;
+    let degrees = (value / 100.0).trunc();
+    let angle = degrees + (value - degrees * 100.0) / 60.0;
+    if hemisphere == negative { Some(-angle) } else { Some(angle) }
+    }
+
+/// Check the XOR checksum after the '*', if there is one
+fn checksum_matches(sentence: &str) -> bool {
+    match sentence.rfind('*') {
+        Some(star) => {
+            let checksum = sentence[1..star].bytes().fold(0u8, |checksum, byte| checksum ^ byte);
+            u8::from_str_radix(sentence[star + 1..].trim(), 16).map(|expected| expected == checksum).unwrap_or(false)
+            },
+        None => true,
+        }
+    }
+
+/// Read the GGA sentences of an NMEA 0183 log. Sentences without a fix or with a bad checksum are skipped
+pub fn read_nmea(text: &str) -> io::Result<Vec<Fix>> {
+    let mut fixes = Vec::new();
+    for line in text.lines() {
+        let sentence = line.trim();
+        if !sentence.starts_with('$') || sentence.get(3..6) != Some("GGA") {
+            continue;
+            }
+        if !checksum_matches(sentence) {
+            log::warn!("Skipping NMEA sentence with bad checksum: {}", sentence);
+            continue;
+            }
+
+        let body = sentence.split('*').next().unwrap_or(sentence);
+        let fields: Vec<&str> = body.split(',').collect();
+        if fields.len() < 10 || fields[6] == "0" || fields[6].is_empty() {
+            continue;
+            }
+        let fix = (|| {
+            Some(Fix {
+                time: parse_time_of_day(fields[1])?,
+                position: FixPosition::Geodetic(
+                    parse_nmea_angle(fields[2], fields[3], "S")?,
+                    parse_nmea_angle(fields[4], fields[5], "W")?,
+                    fields[9].parse().ok()?,
+                    ),
+                attitude: None,
+                })
+            })();
+        match fix {
+            Some(fix) => fixes.push(fix),
+            None => log::warn!("Skipping invalid NMEA sentence: {}", sentence),
+            }
+        }
+    unwrap_days(&mut fixes);
+    Ok(fixes)
+    }
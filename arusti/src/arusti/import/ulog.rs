@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::io;
+
+extern crate nalgebra as na;
+use na::{Quaternion, UnitQuaternion};
+
+use super::{Fix, FixPosition, interpolate_attitude, invalid};
+
+const MAGIC: [u8; 7] = [b'U', b'L', b'o', b'g', 0x01, 0x12, 0x35];
+/// Magic, version and start timestamp
+const HEADER_LENGTH: usize = 16;
+
+/// A field of a message format, as "type name" or "type[n] name"
+struct Field {
+    type_name: String,
+    count: usize,
+    name: String,
+    }
+
+/// Formats of each message name, from the 'F' messages
+type Formats = HashMap<String, Vec<Field>>;
+
+fn parse_format(text: &str) -> Option<(String, Vec<Field>)> {
+    let (name, fields) = text.split_once(':')?;
+    let fields = fields.split(';')
+        .filter(|field| !field.is_empty())
+        .map(|field| {
+            let (type_name, name) = field.split_once(' ')?;
+            let (type_name, count) = match type_name.split_once('[') {
+                Some((type_name, count)) => (type_name, count.trim_end_matches(']').parse().ok()?),
+                None => (type_name, 1),
+                };
+            Some(Field { type_name: type_name.to_string(), count, name: name.to_string() })
+            })
+        .collect::<Option<Vec<Field>>>()?;
+    Some((name.to_string(), fields))
+    }
+
+/// Size in bytes of a basic type
+fn basic_size(type_name: &str) -> Option<usize> {
+    match type_name {
+        "int8_t" | "uint8_t" | "bool" | "char" => Some(1),
+        "int16_t" | "uint16_t" => Some(2),
+        "int32_t" | "uint32_t" | "float" => Some(4),
+        "int64_t" | "uint64_t" | "double" => Some(8),
+        _ => None,
+        }
+    }
+
+/// Size in bytes of a type, or None if it is unknown. An error if a format contains itself,
+///  directly or through another format
+fn type_size(type_name: &str, formats: &Formats) -> Result<Option<usize>, String> {
+    nested_size(type_name, formats, &mut Vec::new())
+    }
+
+fn nested_size<'a>(type_name: &'a str, formats: &'a Formats, visiting: &mut Vec<&'a str>) -> Result<Option<usize>, String> {
+    if let Some(size) = basic_size(type_name) {
+        return Ok(Some(size));
+        }
+    let format = match formats.get(type_name) {
+        Some(format) => format,
+        None => return Ok(None),
+        };
+    if visiting.contains(&type_name) {
+        return Err(format!("format {} contains itself", type_name));
+        }
+    visiting.push(type_name);
+    let mut size = Some(0);
+    for field in format {
+        let field_size = nested_size(&field.type_name, formats, visiting)?;
+        size = size.zip(field_size).map(|(size, field_size)| size + field_size * field.count);
+        }
+    visiting.pop();
+    Ok(size)
+    }
+
+fn read_basic(type_name: &str, bytes: &[u8]) -> f64 {
+    match type_name {
+        "int8_t" => bytes[0] as i8 as f64,
+        "uint8_t" | "bool" | "char" => bytes[0] as f64,
+        "int16_t" => i16::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        "uint16_t" => u16::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        "int32_t" => i32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        "uint32_t" => u32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        "float" => f32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        "int64_t" => i64::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        "uint64_t" => u64::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        "double" => f64::from_le_bytes(bytes.try_into().unwrap()),
+        _ => f64::NAN,
+        }
+    }
+
+/// Decode the fields of a message into values named e.g. "lat", "q[0]" or "nested.x".
+///  Returns the number of bytes read, stopping early if the data is truncated or a type is unknown
+fn decode(format: &[Field], formats: &Formats, data: &[u8], prefix: &str, values: &mut HashMap<String, f64>) -> Result<usize, String> {
+    let mut offset = 0;
+    for field in format {
+        let size = match type_size(&field.type_name, formats)? {
+            Some(size) => size,
+            None => return Ok(offset),
+            };
+        for i in 0..field.count {
+            if offset + size > data.len() {
+                return Ok(offset);
+                }
+            let name = if field.count > 1 { format!("{}{}[{}]", prefix, field.name, i) } else { format!("{}{}", prefix, field.name) };
+            if basic_size(&field.type_name).is_some() {
+                if !field.name.starts_with("_padding") {
+                    values.insert(name, read_basic(&field.type_name, &data[offset..offset + size]));
+                    }
+                }
+            else if let Some(nested) = formats.get(&field.type_name) {
+                decode(nested, formats, &data[offset..offset + size], &format!("{}.", name), values)?;
+                }
+            offset += size;
+            }
+        }
+    Ok(offset)
+    }
+
+/// Samples of each topic by name, for the first instance of each topic
+fn read_topics(data: &[u8]) -> io::Result<HashMap<String, Vec<HashMap<String, f64>>>> {
+    if data.len() < HEADER_LENGTH || data[0..7] != MAGIC {
+        return Err(invalid("not a ULog file".to_string()));
+        }
+
+    let mut formats: Formats = HashMap::new();
+    let mut subscriptions: HashMap<u16, String> = HashMap::new();
+    let mut topics: HashMap<String, Vec<HashMap<String, f64>>> = HashMap::new();
+    let mut i = HEADER_LENGTH;
+    while i + 3 <= data.len() {
+        let size = u16::from_le_bytes([data[i], data[i + 1]]) as usize;
+        let msg_type = data[i + 2];
+        let body = match data.get(i + 3..i + 3 + size) {
+            Some(body) => body,
+            None => break,
+            };
+        match msg_type {
+            b'F' => if let Some((name, fields)) = parse_format(&String::from_utf8_lossy(body)) {
+                formats.insert(name, fields);
+                },
+            b'A' if size >= 3 => {
+                let multi_id = body[0];
+                let msg_id = u16::from_le_bytes([body[1], body[2]]);
+                if multi_id == 0 {
+                    subscriptions.insert(msg_id, String::from_utf8_lossy(&body[3..]).to_string());
+                    }
+                },
+            b'D' if size >= 2 => {
+                let msg_id = u16::from_le_bytes([body[0], body[1]]);
+                if let Some(name) = subscriptions.get(&msg_id) {
+                    if let Some(format) = formats.get(name) {
+                        let mut values = HashMap::new();
+                        decode(format, &formats, &body[2..], "", &mut values).map_err(invalid)?;
+                        topics.entry(name.clone()).or_default().push(values);
+                        }
+                    }
+                },
+            _ => (),
+            }
+        i += 3 + size;
+        }
+    Ok(topics)
+    }
+
+/// Read a PX4 ULog.
+///  Positions come from vehicle_global_position, or vehicle_gps_position where there is none.
+///  Attitudes come from vehicle_attitude, interpolated to the time of each position
+pub fn read_ulog(data: &[u8]) -> io::Result<Vec<Fix>> {
+    let topics = read_topics(data)?;
+    let samples = |name: &str| topics.get(name).map(|samples| samples.as_slice()).unwrap_or(&[]);
+    let time = |sample: &HashMap<String, f64>| sample.get("timestamp").map(|time| time * 1.0e-6);
+
+    let attitudes: Vec<(f64, UnitQuaternion<f32>)> = samples("vehicle_attitude").iter()
+        .filter_map(|sample| {
+            let part = |i: usize| sample.get(&format!("q[{}]", i)).map(|value| *value as f32);
+            Some((time(sample)?, UnitQuaternion::from_quaternion(Quaternion::new(part(0)?, part(1)?, part(2)?, part(3)?))))
+            })
+        .collect();
+
+    let position = |sample: &HashMap<String, f64>| -> Option<FixPosition> {
+        let field = |name: &str| sample.get(name).cloned();
+        if let (Some(latitude), Some(longitude), Some(altitude)) = (field("latitude_deg"), field("longitude_deg"), field("altitude_msl_m")) {
+            return Some(FixPosition::Geodetic(latitude, longitude, altitude));
+            }
+        match sample.contains_key("fix_type") {
+            // vehicle_gps_position before v1.14 logged integer degrees * 1e7 and millimetres
+            true => Some(FixPosition::Geodetic(field("lat")? * 1.0e-7, field("lon")? * 1.0e-7, field("alt")? * 1.0e-3)),
+            false => Some(FixPosition::Geodetic(field("lat")?, field("lon")?, field("alt")?)),
+            }
+        };
+
+    let source = if topics.contains_key("vehicle_global_position") { "vehicle_global_position" } else { "vehicle_gps_position" };
+    let fixes = samples(source).iter()
+        .filter(|sample| sample.get("fix_type").map(|fix_type| *fix_type >= 3.0).unwrap_or(true))
+        .filter_map(|sample| {
+            let time = time(sample)?;
+            Some(Fix {
+                time,
+                position: position(sample)?,
+                attitude: interpolate_attitude(&attitudes, time),
+                })
+            })
+        .collect();
+    Ok(fixes)
+    }
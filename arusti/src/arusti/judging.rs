@@ -0,0 +1,351 @@
+extern crate nalgebra as na;
+use na::Vector3;
+
+use std::fmt;
+
+use crate::types::{Element, ElementType, Figure, Sequence};
+use crate::data_generation::DataPoint;
+use crate::fitting::{self, ElementFit};
+use crate::pilot_errors::{self, PilotError};
+use crate::recognition;
+use crate::segmentation::{self, FigureSegment, Segment, SegmentationOptions};
+
+/// Fraction of a line at each end over which its attitude is measured
+const LINE_END_FRACTION: f32 = 0.2;
+
+/// Options for judging a flight
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Default)]
+pub struct JudgingOptions {
+    pub segmentation: SegmentationOptions,
+    /// Heading of the main axis (degrees, clockwise from north). Defaults to the heading of the first line flown
+    pub reference_heading: Option<f32>,
+    }
+
+/// A judging fault found in a flown figure
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum Fault {
+    /// Line pitched off its intended angle (degrees)
+    LinePitch(f32),
+    /// Line yawed off the axis (degrees)
+    LineHeading(f32),
+    /// Radius pulled or pushed through the wrong angle (degrees)
+    RadiusAngle(f32),
+    /// Ratio of the largest to the smallest radius flown in a looping element
+    UnevenRadius(f32),
+    /// Turn through the wrong angle (degrees)
+    TurnAngle(f32),
+    /// Roll in a rolling turn over (+ve) or under (-ve) rotated (degrees)
+    TurnRoll(f32),
+    /// Roll, snap or spin over (+ve) or under (-ve) rotated (degrees)
+    RollRotation(f32),
+    /// Number of hesitation points missing (or added)
+    MissingPoints(u8),
+    /// Hesitation held away from its point (degrees)
+    HesitationAngle(f32),
+    /// Expected element that was not flown
+    MissingElement(ElementType),
+    /// Element flown that was not expected
+    ExtraElement(ElementType),
+    /// No flight data for the figure
+    FigureNotFlown,
+    }
+
+impl Fault {
+    /// Downgrade (in points) for the fault. One point per 5°, with figures zeroed for missing or extra elements
+    pub fn downgrade(&self) -> f32 {
+        match *self {
+            Fault::LinePitch(angle)
+            | Fault::LineHeading(angle)
+            | Fault::RadiusAngle(angle)
+            | Fault::TurnAngle(angle)
+            | Fault::TurnRoll(angle)
+            | Fault::RollRotation(angle)
+            | Fault::HesitationAngle(angle) => pilot_errors::angle_downgrade(angle),
+            Fault::UnevenRadius(scale) => PilotError::UnevenRadius(scale).downgrade(),
+            Fault::MissingPoints(count) => PilotError::MissingPoints(count).downgrade(),
+            Fault::MissingElement(_) | Fault::ExtraElement(_) | Fault::FigureNotFlown => 10.0,
+            }
+        }
+    }
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Fault::LinePitch(angle) => write!(f, "line pitch off by {:.0}°", angle),
+            Fault::LineHeading(angle) => write!(f, "line heading off by {:.0}°", angle),
+            Fault::RadiusAngle(angle) => write!(f, "radius off by {:.0}°", angle),
+            Fault::UnevenRadius(scale) => write!(f, "radius changes by {:.0}%", (scale - 1.0) * 100.0),
+            Fault::TurnAngle(angle) => write!(f, "turn off by {:.0}°", angle),
+            Fault::TurnRoll(angle) => write!(f, "roll in turn off by {:.0}°", angle),
+            Fault::RollRotation(angle) => write!(f, "rotation off by {:.0}°", angle),
+            Fault::MissingPoints(count) => write!(f, "{} hesitation points wrong", count),
+            Fault::HesitationAngle(angle) => write!(f, "hesitation off by {:.0}°", angle),
+            Fault::MissingElement(elem_type) => write!(f, "{:?} not flown", elem_type),
+            Fault::ExtraElement(elem_type) => write!(f, "{:?} not in figure", elem_type),
+            Fault::FigureNotFlown => write!(f, "figure not flown"),
+            }
+        }
+    }
+
+/// A downgrade for a fault in one element of a figure
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub struct Downgrade {
+    /// Expected element, or the flown element if it was not expected. None for a figure that was not flown
+    pub element: Option<Element>,
+    pub fault: Fault,
+    pub points: f32,
+    /// Time at which the element starts, or the figure if the element was not flown (s)
+    pub time: f32,
+    }
+
+/// Mark for a single figure, from 0 to 10
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct FigureMark {
+    pub mark: f32,
+    pub downgrades: Vec<Downgrade>,
+    /// Flight data for the figure, or None if it was not flown
+    pub flown: Option<FigureSegment>,
+    }
+
+fn body_direction(point: &DataPoint) -> Vector3<f32> {
+    point.attitude * Vector3::x()
+    }
+
+/// Heading clockwise from north (degrees)
+fn heading(direction: &Vector3<f32>) -> f32 {
+    direction[1].atan2(direction[0]).to_degrees()
+    }
+
+/// Angle of the direction above the horizon (degrees)
+fn pitch(direction: &Vector3<f32>) -> f32 {
+    (-direction[2]).clamp(-1.0, 1.0).asin().to_degrees()
+    }
+
+/// Wrap an angle into -180 to 180 degrees
+fn wrap(angle: f32) -> f32 {
+    (angle + 180.0).rem_euclid(360.0) - 180.0
+    }
+
+/// Mean body direction over the start and end of a line
+fn line_ends(points: &[DataPoint]) -> [Vector3<f32>; 2] {
+    let count = ((points.len() as f32 * LINE_END_FRACTION).ceil() as usize).max(1);
+    let mean = |window: &[DataPoint]| window.iter().map(body_direction).sum::<Vector3<f32>>().normalize();
+    [mean(&points[..count]), mean(&points[points.len() - count..])]
+    }
+
+/// Faults in a line, measured from the attitude at each end so drift along the line is seen in full
+fn line_faults(expected_pitch: f32, points: &[DataPoint], reference_heading: f32) -> Vec<Fault> {
+    let ends = line_ends(points);
+    let mut faults = Vec::new();
+
+    let pitch_error = ends.iter().map(|end| (pitch(end) - expected_pitch).abs()).fold(0.0, f32::max);
+    faults.push(Fault::LinePitch(pitch_error));
+
+    // Vertical lines have no heading, and any yaw shows as pitch error
+    if expected_pitch.abs() < 60.0 {
+        let heading_error = ends.iter()
+            .map(|end| {
+                let offset = wrap(heading(end) - reference_heading);
+                // Lines may be flown along or across the axis
+                (offset - (offset / 90.0).round() * 90.0).abs()
+                })
+            .fold(0.0, f32::max);
+        faults.push(Fault::LineHeading(heading_error));
+        }
+    faults
+    }
+
+/// Looping elements are split into parts of this angle to check the radius is constant (degrees)
+const RADIUS_PART_ANGLE: f32 = 90.0;
+
+fn radius_faults(expected_angle: f32, flown_angle: f32, points: &[DataPoint]) -> Vec<Fault> {
+    let mut faults = vec![Fault::RadiusAngle((expected_angle - flown_angle).abs())];
+    // The radius of a looping element should be constant
+    if expected_angle.abs() >= 2.0 * RADIUS_PART_ANGLE {
+        let mut sizes = Vec::new();
+        let mut part_start = 0;
+        for i in 1..points.len() {
+            if fitting::pitch_angle(&points[part_start..=i]).abs() >= RADIUS_PART_ANGLE {
+                sizes.extend(fitting::fit_radius(&points[part_start..=i]).map(|fit| fit.size));
+                part_start = i;
+                }
+            }
+        let smallest = sizes.iter().cloned().fold(f32::INFINITY, f32::min);
+        let largest = sizes.iter().cloned().fold(0.0, f32::max);
+        if sizes.len() >= 2 && smallest > 0.0 {
+            faults.push(Fault::UnevenRadius(largest / smallest));
+            }
+        }
+    faults
+    }
+
+/// Largest error in the rotation between hesitations, a step of 360° over the divisions (degrees)
+fn hesitation_error(divisions: u8, points: &[DataPoint], options: &SegmentationOptions) -> f32 {
+    let step = 360.0 / divisions as f32;
+    let mut previous = 0.0;
+    let mut error: f32 = 0.0;
+    for angle in fitting::hesitation_angles(points, options) {
+        error = error.max((angle.abs() - previous - step).abs());
+        previous = angle.abs();
+        }
+    error
+    }
+
+fn element_faults(expected: &Element, flown: &Element, points: &[DataPoint], reference_heading: f32, options: &SegmentationOptions) -> Vec<Fault> {
+    match (*expected, *flown) {
+        (Element::Line { pitch, .. }, _) => line_faults(pitch, points, reference_heading),
+        (Element::Radius { angle: expected_angle, .. }, Element::Radius { angle, .. }) => radius_faults(expected_angle, angle, points),
+        (Element::Turn { angle: expected_angle, .. }, Element::Turn { angle, .. }) => vec![
+            Fault::TurnAngle((angle.abs() - expected_angle.abs()).abs()),
+            Fault::TurnRoll(flown.turn_roll() - expected.turn_roll()),
+            ],
+        _ if expected.is_rolling() => {
+            let mut faults = vec![Fault::RollRotation(flown.rolling_angle().abs() - expected.rolling_angle().abs())];
+            if let (Element::Roll { divisions, .. }, Element::Roll { .. }) = (*expected, *flown) {
+                let (expected_parts, parts) = (expected.roll_parts(), flown.roll_parts());
+                if expected_parts != parts {
+                    faults.push(Fault::MissingPoints(expected_parts.abs_diff(parts)));
+                    }
+                else if expected_parts > 1 {
+                    faults.push(Fault::HesitationAngle(hesitation_error(divisions, points, options)));
+                    }
+                }
+            faults
+            }
+        _ => Vec::new(),
+        }
+    }
+
+/// Fits of the elements of a figure that could be fitted, with their segments
+fn fit_figure(points: &[DataPoint], figure: &FigureSegment, options: &SegmentationOptions) -> Vec<(Segment, ElementFit)> {
+    figure.elements.iter()
+        .filter_map(|segment| fitting::fit_segment(points, segment, options).map(|fit| (*segment, fit)))
+        .collect()
+    }
+
+/// Judge a flown figure against the expected figure
+pub fn judge_figure(points: &[DataPoint], flown: &FigureSegment, expected: &Figure, options: &SegmentationOptions, reference_heading: f32) -> FigureMark {
+    let expected_elements = recognition::normalise(&expected.elements);
+    let fits = fit_figure(points, flown, options);
+    let flown_elements: Vec<Element> = fits.iter().map(|(_, fit)| fit.element).collect();
+    let (_, pairs) = recognition::align(&expected_elements, &flown_elements);
+
+    // Hesitations are found with a narrow rate window, as when fitting
+    let rotation_options = SegmentationOptions {
+        rate_window: options.rate_window.min(0.1),
+        .. options.clone()
+        };
+
+    let figure_time = points[flown.start].time;
+    let mut downgrades = Vec::new();
+    for pair in pairs {
+        match pair {
+            (Some(i), Some(j)) => {
+                let segment = &fits[j].0;
+                let element_points = &points[segment.start..(segment.end + 1).min(points.len())];
+                for fault in element_faults(&expected_elements[i], &flown_elements[j], element_points, reference_heading, &rotation_options) {
+                    let deduction = fault.downgrade();
+                    if deduction > 0.0 {
+                        downgrades.push(Downgrade { element: Some(expected_elements[i]), fault, points: deduction, time: element_points[0].time });
+                        }
+                    }
+                }
+            // Lines of no particular length may be left out or added
+            (Some(i), None) if expected_elements[i].elem_type() != ElementType::Line => {
+                let fault = Fault::MissingElement(expected_elements[i].elem_type());
+                downgrades.push(Downgrade { element: Some(expected_elements[i]), fault, points: fault.downgrade(), time: figure_time });
+                }
+            (None, Some(j)) if flown_elements[j].elem_type() != ElementType::Line => {
+                let fault = Fault::ExtraElement(flown_elements[j].elem_type());
+                downgrades.push(Downgrade { element: Some(flown_elements[j]), fault, points: fault.downgrade(), time: points[fits[j].0.start].time });
+                }
+            _ => {}
+            }
+        }
+
+    let total: f32 = downgrades.iter().map(|downgrade| downgrade.points).sum();
+    FigureMark {
+        mark: (10.0 - total).max(0.0),
+        downgrades,
+        flown: Some(flown.clone()),
+        }
+    }
+
+/// Flown figure aligned with each expected figure, or None if it was not flown. Edit distance over the
+///  figures, with substitutions costed by the alignment of their elements as in recognition
+fn align_figures(expected: &[Vec<Element>], flown: &[Vec<Element>]) -> Vec<Option<usize>> {
+    let expected_gaps: Vec<f32> = expected.iter().map(|elements| recognition::chain_cost(elements, &[])).collect();
+    let flown_gaps: Vec<f32> = flown.iter().map(|elements| recognition::chain_cost(&[], elements)).collect();
+    let substitution = |i: usize, j: usize| recognition::chain_cost(&expected[i], &flown[j]);
+
+    let mut costs = vec![vec![0.0; flown.len() + 1]; expected.len() + 1];
+    for j in 0..flown.len() {
+        costs[0][j + 1] = costs[0][j] + flown_gaps[j];
+        }
+    for i in 0..expected.len() {
+        costs[i + 1][0] = costs[i][0] + expected_gaps[i];
+        for j in 0..flown.len() {
+            costs[i + 1][j + 1] = (costs[i][j] + substitution(i, j))
+                .min(costs[i][j + 1] + expected_gaps[i])
+                .min(costs[i + 1][j] + flown_gaps[j]);
+            }
+        }
+
+    // Trace the cheapest path back from the end of both sequences
+    let mut aligned = vec![None; expected.len()];
+    let (mut i, mut j) = (expected.len(), flown.len());
+    while i > 0 && j > 0 {
+        if (costs[i - 1][j - 1] + substitution(i - 1, j - 1) - costs[i][j]).abs() < 1e-4 {
+            aligned[i - 1] = Some(j - 1);
+            i -= 1;
+            j -= 1;
+            }
+        else if (costs[i - 1][j] + expected_gaps[i - 1] - costs[i][j]).abs() < 1e-4 {
+            i -= 1;
+            }
+        else {
+            j -= 1;
+            }
+        }
+    aligned
+    }
+
+/// Judge a flight against the expected sequence. Flown figures are aligned with the sequence by their
+///  elements, so a figure left out or added does not shift the rest, and figures not flown are marked zero
+pub fn judge_flight(points: &[DataPoint], sequence: &Sequence, options: &JudgingOptions) -> Vec<FigureMark> {
+    let figures = segmentation::segment_figures(points, &options.segmentation);
+    let reference_heading = options.reference_heading.unwrap_or_else(|| {
+        figures.first()
+            .and_then(|figure| figure.elements.first())
+            .map_or(0.0, |segment| heading(&line_ends(&points[segment.start..segment.end])[0]))
+        });
+
+    let expected: Vec<Vec<Element>> = sequence.figures.iter().map(|figure| recognition::normalise(&figure.elements)).collect();
+    let flown: Vec<Vec<Element>> = figures.iter()
+        .map(|figure| fit_figure(points, figure, &options.segmentation).into_iter().map(|(_, fit)| fit.element).collect())
+        .collect();
+
+    sequence.figures.iter().zip(align_figures(&expected, &flown))
+        .map(|(expected, aligned)| match aligned {
+            Some(j) => judge_figure(points, &figures[j], expected, &options.segmentation, reference_heading),
+            None => FigureMark {
+                mark: 0.0,
+                downgrades: vec![Downgrade {
+                    element: None,
+                    fault: Fault::FigureNotFlown,
+                    points: 10.0,
+                    time: points.last().map_or(0.0, |point| point.time),
+                    }],
+                flown: None,
+                },
+            })
+        .collect()
+    }
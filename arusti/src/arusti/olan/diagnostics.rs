@@ -0,0 +1,379 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::olan::figure_defs::FIGURE_KEYS;
+use crate::olan::parser::{parse_with_sources, ExitCorrection, FigureSource};
+
+/// Something wrong, or corrected by the parser, in an OLAN string
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum DiagnosticKind {
+    /// The OLAN does not match the grammar. Holds what was expected
+    Syntax(String),
+    /// The grammar stopped matching here, so the rest of the OLAN is ignored by the parser
+    Unparsed,
+    /// Figure letters in capitals
+    Capitals,
+    /// Roll written as a fraction or a diagram label rather than in OLAN, e.g. `1/2` or `2x4`
+    RollNotation { written: String, olan: String },
+    /// Brackets around inner rolls or a curve are not closed, or closed without being opened
+    UnbalancedBrackets,
+    /// The figure ends the other way up to its written exit line, so the parser flipped its last invertible radius
+    ///  and everything after it. Holds the new radius angle (degrees) and whether the exit is written inverted
+    ExitInverted { radius: f32, inverted: bool },
+    /// The figure ends the other way up to its written exit line and has no radius to flip, so the exit is left as written
+    ExitMismatch { inverted: bool },
+    }
+
+/// A problem found in an OLAN string, with suggested replacements for the part of the string it is about
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct Diagnostic {
+    /// Index of the figure, when the OLAN parses
+    pub figure: Option<usize>,
+    /// Byte range of the OLAN the diagnostic is about
+    pub span: Range<usize>,
+    /// The OLAN in the span
+    pub text: String,
+    pub kind: DiagnosticKind,
+    /// Replacements for the span, most likely first
+    pub suggestions: Vec<String>,
+    }
+
+fn upright(inverted: bool) -> &'static str {
+    if inverted { "inverted" } else { "upright" }
+    }
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiagnosticKind::Syntax(expected) => write!(f, "does not parse, {}", expected),
+            DiagnosticKind::Unparsed => write!(f, "not parsed, the parser stops here and ignores the rest of the sequence"),
+            DiagnosticKind::Capitals => write!(f, "OLAN is written in lower case"),
+            DiagnosticKind::RollNotation { written, olan } => write!(f, "`{}` is not OLAN roll notation, use `{}`", written, olan),
+            DiagnosticKind::UnbalancedBrackets => write!(f, "unbalanced brackets"),
+            DiagnosticKind::ExitInverted { radius, inverted } => {
+                let direction = if *radius < 0.0 { "push" } else { "pull" };
+                write!(f, "exit line {} via {} on last {}° radius", upright(*inverted), direction, radius.abs())
+                },
+            DiagnosticKind::ExitMismatch { inverted } => {
+                write!(f, "exit line {} as written, but the figure ends {} with no radius to change that", upright(*inverted), upright(!*inverted))
+                },
+            }
+        }
+    }
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(figure) = self.figure {
+            write!(f, "Figure {} ", figure + 1)?;
+            }
+        write!(f, "`{}`: {}", self.text, self.kind)?;
+        if let Some(suggestion) = self.suggestions.first() {
+            write!(f, "; did you mean `{}`?", suggestion)?;
+            }
+        Ok(())
+        }
+    }
+
+/// Words of the sequence and their byte offsets
+fn words(olan: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    for word in olan.split(' ') {
+        if !word.is_empty() {
+            words.push((start, word));
+            }
+        start += word.len() + 1;
+        }
+    words
+    }
+
+/// OLAN for a roll written as `n/m` revolutions or as the diagram label `nxm` for n points of an m point roll
+fn roll_olan(n: u32, separator: char, m: u32) -> Option<String> {
+    match separator {
+        'x' if [2, 4, 8].contains(&m) => Some(format!("{}{}", n, m)),
+        '/' if [1, 2, 4, 8].contains(&m) && 8 * n % m == 0 => {
+            let eighths = 8 * n / m;
+            match eighths {
+                2 => Some("4".to_string()),
+                8 => Some("1".to_string()),
+                16 => Some("9".to_string()),
+                4 | 6 | 10 | 12 | 14 => Some((eighths / 2).to_string()),
+                1 | 3 | 5 | 7 | 9 => Some(format!("{}8", eighths)),
+                _ => None,
+                }
+            },
+        _ => None,
+        }
+    }
+
+/// Rewrite rolls written as fractions or diagram labels
+fn rewrite_rolls(word: &str) -> (Vec<DiagnosticKind>, String) {
+    let chars: Vec<char> = word.chars().collect();
+    let mut kinds = Vec::new();
+    let mut rewritten = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if i + 2 < chars.len() && chars[i].is_ascii_digit() && chars[i + 2].is_ascii_digit() {
+            let (n, m) = (chars[i].to_digit(10).unwrap(), chars[i + 2].to_digit(10).unwrap());
+            if let Some(olan) = roll_olan(n, chars[i + 1], m) {
+                kinds.push(DiagnosticKind::RollNotation { written: chars[i..i + 3].iter().collect(), olan: olan.clone() });
+                rewritten.push_str(&olan);
+                i += 3;
+                continue;
+                }
+            }
+        rewritten.push(chars[i]);
+        i += 1;
+        }
+    (kinds, rewritten)
+    }
+
+/// Close unclosed brackets after the rolls that follow them and drop closing brackets that were never opened.
+///  None if the brackets already balance
+fn balance_brackets(word: &str) -> Option<String> {
+    let chars: Vec<char> = word.chars().collect();
+    let mut open = Vec::new();
+    let mut unopened = Vec::new();
+    for (i, c) in chars.iter().enumerate() {
+        match c {
+            '(' => open.push(i),
+            ')' if open.pop().is_none() => unopened.push(i),
+            _ => {},
+            }
+        }
+    if open.is_empty() && unopened.is_empty() {
+        return None;
+        }
+
+    // Closing bracket to insert after each unclosed one, or None to drop an unclosed one with nothing after it
+    let is_roll = |c: &char| c.is_ascii_digit() || ",;.'`ifs".contains(*c);
+    let mut closes = Vec::new();
+    for &i in &open {
+        let close = if i == 0 {
+            // A curve layout command
+            Some(chars.len())
+            }
+        else {
+            let rolls = chars[i + 1..].iter().take_while(|c| is_roll(c)).count();
+            if rolls > 0 { Some(i + 1 + rolls) } else { None }
+            };
+        closes.push((i, close));
+        }
+
+    let mut balanced = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if closes.iter().any(|(_, close)| *close == Some(i)) {
+            balanced.push(')');
+            }
+        let dropped = unopened.contains(&i) || closes.iter().any(|(open, close)| *open == i && close.is_none());
+        if !dropped {
+            balanced.push(*c);
+            }
+        }
+    if closes.iter().any(|(_, close)| *close == Some(chars.len())) {
+        balanced.push(')');
+        }
+    Some(balanced)
+    }
+
+/// Mistakes in the notation of one word of the sequence, with the word corrected for all of them
+fn notation_fixes(word: &str) -> (Vec<DiagnosticKind>, String) {
+    let mut kinds = Vec::new();
+    let mut fixed = word.to_string();
+    if fixed.chars().any(|c| c.is_ascii_uppercase()) {
+        kinds.push(DiagnosticKind::Capitals);
+        fixed = fixed.to_ascii_lowercase();
+        }
+    let (roll_kinds, rewritten) = rewrite_rolls(&fixed);
+    kinds.extend(roll_kinds);
+    fixed = rewritten;
+    if let Some(balanced) = balance_brackets(&fixed) {
+        kinds.push(DiagnosticKind::UnbalancedBrackets);
+        fixed = balanced;
+        }
+    (kinds, fixed)
+    }
+
+/// Whether the OLAN parses completely
+fn parses(olan: &str) -> bool {
+    parse_with_sources(olan).is_ok_and(|parsed| parsed.end == olan.trim_end().len())
+    }
+
+/// Written entry and exit inversion of one figure, if it parses completely without its exit being corrected
+fn clean_figure(figure: &str) -> Option<(bool, bool)> {
+    let parsed = parse_with_sources(figure).ok().filter(|parsed| parsed.end == figure.len())?;
+    match parsed.sources.as_slice() {
+        [source] if source.exit_correction.is_none() => Some((source.entry_inverted, source.exit_inverted)),
+        _ => None,
+        }
+    }
+
+/// The figure with its entry line written the other way up
+fn toggle_entry(figure: &str) -> String {
+    let body = figure.trim_start_matches(['~', '+', '-']);
+    let extension = &figure[..figure.len() - body.len()];
+    if extension.contains('-') {
+        format!("{}{}", extension.replace('-', ""), body)
+        }
+    else {
+        format!("-{}{}", extension, body)
+        }
+    }
+
+/// The figure with its exit line written the other way up
+fn toggle_exit(figure: &str) -> String {
+    let body = figure.trim_end_matches(['~', '+', '-']);
+    let extension = &figure[body.len()..];
+    if extension.contains('-') {
+        format!("{}{}", body, extension.replace('-', ""))
+        }
+    else {
+        format!("{}{}-", body, extension)
+        }
+    }
+
+/// The figure with the `i` prefix of its letters added or removed, if the result is in the figure library
+fn toggle_prefix(figure: &str, key: Range<usize>) -> Option<String> {
+    let letters = &figure[key.clone()];
+    let toggled = match letters.strip_prefix('i') {
+        Some(upright) => upright.to_string(),
+        None => format!("i{}", letters),
+        };
+    FIGURE_KEYS.contains(&toggled.as_str()).then(|| format!("{}{}{}", &figure[..key.start], toggled, &figure[key.end..]))
+    }
+
+/// Byte range of the word containing a position
+fn word_around(olan: &str, position: usize) -> Range<usize> {
+    let start = olan[..position].rfind(' ').map_or(0, |space| space + 1);
+    let end = olan[position..].find(' ').map_or(olan.len(), |length| position + length);
+    start..end
+    }
+
+/// Written inversion of the lines either side of a figure: the exit of the one before and the entry of the one after
+struct Neighbours {
+    previous_exit: Option<bool>,
+    next_entry: Option<bool>,
+    }
+
+fn exit_diagnostic(olan: &str, figure: usize, source: &FigureSource, neighbours: &Neighbours) -> Option<Diagnostic> {
+    let kind = match source.exit_correction? {
+        ExitCorrection::RadiusInverted(radius) => DiagnosticKind::ExitInverted { radius, inverted: source.exit_inverted },
+        ExitCorrection::LeftAsWritten => DiagnosticKind::ExitMismatch { inverted: source.exit_inverted },
+        };
+
+    // Rewrite the exit line, or use the inverted form of the figure, which flies it with the entry the other way up
+    let text = &olan[source.span.clone()];
+    let key = source.key.clone().map(|key| key.start - source.span.start..key.end - source.span.start);
+    let prefixed = key.and_then(|key| toggle_prefix(text, key));
+    let candidates = vec![Some(toggle_exit(text)), prefixed.clone(), prefixed.map(|prefixed| toggle_entry(&prefixed))];
+    let continuous = |candidate: &String| clean_figure(candidate).is_some_and(|(entry, exit)| {
+        neighbours.previous_exit.unwrap_or(entry) == entry && neighbours.next_entry.unwrap_or(exit) == exit
+        });
+    Some(Diagnostic {
+        figure: Some(figure),
+        span: source.span.clone(),
+        text: text.to_string(),
+        kind,
+        suggestions: candidates.into_iter().flatten().filter(continuous).collect(),
+        })
+    }
+
+/// Words with an `i` added or removed before the figure letters that make the whole OLAN parse
+fn prefix_suggestions(olan: &str, span: &Range<usize>) -> Vec<String> {
+    let word = &olan[span.clone()];
+    let mut candidates = Vec::new();
+    for (i, c) in word.char_indices() {
+        let letter_run_start = c.is_ascii_lowercase() && !word[..i].ends_with(|previous: char| previous.is_ascii_lowercase());
+        if !letter_run_start {
+            continue;
+            }
+        if c == 'i' {
+            candidates.push(format!("{}{}", &word[..i], &word[i + 1..]));
+            }
+        candidates.push(format!("{}i{}", &word[..i], &word[i..]));
+        }
+    candidates.into_iter()
+        .filter(|candidate| parses(&format!("{}{}{}", &olan[..span.start], candidate, &olan[span.end..])))
+        .collect()
+    }
+
+/// Explain the corrections the parser makes to an OLAN string and find common mistakes in it, suggesting fixes for both
+pub fn diagnose(olan: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (start, word) in words(olan) {
+        let (kinds, fixed) = notation_fixes(word);
+        for kind in kinds {
+            diagnostics.push(Diagnostic {
+                figure: None,
+                span: start..start + word.len(),
+                text: word.to_string(),
+                kind,
+                suggestions: vec![fixed.clone()],
+                });
+            }
+        }
+
+    match parse_with_sources(olan) {
+        Ok(parsed) => {
+            for diagnostic in &mut diagnostics {
+                diagnostic.figure = parsed.sources.iter().position(|source| source.span.contains(&diagnostic.span.start));
+                }
+
+            // The parser stops at the first word it cannot match, which may be part way through a figure
+            let stopped = olan[parsed.end..].find(|c: char| c != ' ').map(|offset| word_around(olan, parsed.end + offset));
+            for (i, source) in parsed.sources.iter().enumerate() {
+                if stopped.as_ref().is_some_and(|stopped| source.span.end > stopped.start) {
+                    break;
+                    }
+                let neighbours = Neighbours {
+                    previous_exit: i.checked_sub(1).map(|previous| parsed.sources[previous].exit_inverted),
+                    next_entry: parsed.sources.get(i + 1).map(|next| next.entry_inverted),
+                    };
+                diagnostics.extend(exit_diagnostic(olan, i, source, &neighbours));
+                }
+            if let Some(span) = stopped {
+                diagnostics.push(Diagnostic {
+                    figure: None,
+                    text: olan[span.clone()].to_string(),
+                    kind: DiagnosticKind::Unparsed,
+                    suggestions: prefix_suggestions(olan, &span),
+                    span,
+                    });
+                }
+            },
+        Err(error) => {
+            let span = word_around(olan, error.position);
+            diagnostics.push(Diagnostic {
+                figure: None,
+                text: olan[span.clone()].to_string(),
+                kind: DiagnosticKind::Syntax(error.summary),
+                suggestions: prefix_suggestions(olan, &span),
+                span,
+                });
+            },
+        }
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+    diagnostics
+    }
+
+/// The OLAN with the first suggestion of each diagnostic applied. Suggestions overlapping an earlier one are skipped
+pub fn corrected(olan: &str, diagnostics: &[Diagnostic]) -> String {
+    let mut corrected = String::new();
+    let mut position = 0;
+    for diagnostic in diagnostics {
+        if let Some(suggestion) = diagnostic.suggestions.first() {
+            if diagnostic.span.start >= position {
+                corrected.push_str(&olan[position..diagnostic.span.start]);
+                corrected.push_str(suggestion);
+                position = diagnostic.span.end;
+                }
+            }
+        }
+    corrected.push_str(&olan[position..]);
+    corrected
+    }
@@ -0,0 +1,124 @@
+use std::ops::{Add, Neg, Sub};
+
+use crate::types::{ElementType, FlatElement};
+
+/// Angle in whole degrees, so the parser's pitch and inversion arithmetic is exact
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+#[derive(Eq)]
+pub struct Degrees(pub i32);
+
+impl Degrees {
+    /// The same direction in [0,360)
+    pub fn normalised(self) -> Degrees {
+        Degrees(self.0.rem_euclid(360))
+        }
+
+    /// Straight up or down, as a pitch
+    pub fn is_vertical(self) -> bool {
+        matches!(self.normalised().0, 90 | 270)
+        }
+
+    /// As a pitch, whether flight along it is upside down: past the vertical and before the next
+    pub fn is_inverted(self) -> bool {
+        let pitch = self.normalised().0;
+        90 < pitch && pitch < 270
+        }
+
+    /// As a roll, whether it leaves the aircraft closer to inverted than upright. Knife edge counts as inverted
+    pub fn ends_inverted(self) -> bool {
+        let roll = self.normalised().0;
+        (90..=270).contains(&roll)
+        }
+
+    pub fn to_f32(self) -> f32 {
+        self.0 as f32
+        }
+    }
+
+impl Add for Degrees {
+    type Output = Degrees;
+    fn add(self, other: Degrees) -> Degrees {
+        Degrees(self.0 + other.0)
+        }
+    }
+
+impl Sub for Degrees {
+    type Output = Degrees;
+    fn sub(self, other: Degrees) -> Degrees {
+        Degrees(self.0 - other.0)
+        }
+    }
+
+impl Neg for Degrees {
+    type Output = Degrees;
+    fn neg(self) -> Degrees {
+        Degrees(-self.0)
+        }
+    }
+
+/// An element in the flat form with exact angles, as built by the parser and figure library.
+///  Converted to a `FlatElement` once the figure is complete
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub struct OlanElement {
+    pub elem_type: ElementType,
+    pub inverted: bool,
+    pub angle: Degrees,
+    /// Whole number in every element type: divisions, radius matching, roll degrees in turns, pitch in stalls
+    pub argument: i32,
+    }
+
+impl OlanElement {
+    pub fn new(elem_type: ElementType) -> OlanElement {
+        OlanElement {
+            elem_type,
+            inverted: false,
+            angle: Degrees(0),
+            argument: 0,
+            }
+        }
+
+    pub fn line(angle: i32) -> OlanElement {
+        OlanElement { angle: Degrees(angle), .. OlanElement::new(ElementType::Line) }
+        }
+
+    pub fn invline(angle: i32) -> OlanElement {
+        OlanElement { inverted: true, .. OlanElement::line(angle) }
+        }
+
+    pub fn radius(angle: i32) -> OlanElement {
+        OlanElement { angle: Degrees(angle), .. OlanElement::new(ElementType::Radius) }
+        }
+
+    pub fn stall(yaw: i32, pitch: i32) -> OlanElement {
+        OlanElement { angle: Degrees(yaw), argument: pitch, .. OlanElement::new(ElementType::Stall) }
+        }
+
+    pub fn combining(argument: i8) -> OlanElement {
+        OlanElement { argument: argument as i32, .. OlanElement::new(ElementType::Combining) }
+        }
+
+    /// Element from the flat form, rounding to whole degrees
+    pub fn from_flat(flat: &FlatElement) -> OlanElement {
+        OlanElement {
+            elem_type: flat.elem_type,
+            inverted: flat.inverted,
+            angle: Degrees(flat.angle.round() as i32),
+            argument: flat.argument.round() as i32,
+            }
+        }
+
+    pub fn to_flat(self) -> FlatElement {
+        FlatElement {
+            elem_type: self.elem_type,
+            inverted: self.inverted,
+            angle: self.angle.to_f32(),
+            argument: self.argument as f32,
+            }
+        }
+    }
@@ -1,492 +1,506 @@
-use crate::types::{Element,ElementType};
+use crate::types::ElementType;
+use crate::olan::element::{Degrees,OlanElement};
 use crate::olan::parser::Rule;
 use pest::iterators::Pair;
 
-pub fn get_elements_for_single_line(figure_pair: Pair<Rule>) -> Vec<Element> {
+/// Keys of every figure in the library, including the inverted entry forms
+pub const FIGURE_KEYS: [&str; 75] = [
+    "d", "id", "v", "iv", "z", "iz",
+    "t", "it", "k", "ik", "zt", "izt", "kz", "ikz",
+    "a", "m", "o", "io", "qo", "iqo", "dq", "idq", "qq", "iqq",
+    "c", "ic", "rc", "irc", "g", "ig", "p", "ip", "rp", "irp", "q", "iq", "rq", "irq", "y", "iy", "ry", "iry",
+    "cc", "rcc", "oo", "icc", "ircc", "ioo", "ooo",
+    "b", "pb", "bb", "pbb", "db", "rdb",
+    "h", "dh", "hd", "dhd", "ta", "ita",
+    "n", "in", "pn", "ipn", "w", "iw", "gg", "igg",
+    "mm", "zb", "rzb", "bz", "rbz", "zy",
+    ];
+
+pub fn get_elements_for_single_line(figure_pair: Pair<Rule>) -> Vec<OlanElement> {
     match figure_pair.as_str() {
-        "d"  => vec![ Element::radius(  45.0), Element::line( 45.0), Element::combining(0), Element::line( 45.0), Element::radius( -45.0) ],
-        "id" => vec![ Element::radius( -45.0), Element::line(-45.0), Element::combining(0), Element::line(-45.0), Element::radius(  45.0) ],
-        "v"  => vec![ Element::radius(  90.0), Element::line( 90.0), Element::combining(0), Element::line( 90.0), Element::radius( -90.0) ],
-        "iv" => vec![ Element::radius( -90.0), Element::line(-90.0), Element::combining(0), Element::line(-90.0), Element::radius(  90.0) ],
-        "z"  => vec![ Element::radius( 135.0), Element::line( 45.0), Element::combining(0), Element::line( 45.0), Element::radius(-135.0) ],
-        "iz" => vec![ Element::radius(-135.0), Element::line(-45.0), Element::combining(0), Element::line(-45.0), Element::radius( 135.0) ],
+        "d"  => vec![ OlanElement::radius(  45), OlanElement::line( 45), OlanElement::combining(0), OlanElement::line( 45), OlanElement::radius( -45) ],
+        "id" => vec![ OlanElement::radius( -45), OlanElement::line(-45), OlanElement::combining(0), OlanElement::line(-45), OlanElement::radius(  45) ],
+        "v"  => vec![ OlanElement::radius(  90), OlanElement::line( 90), OlanElement::combining(0), OlanElement::line( 90), OlanElement::radius( -90) ],
+        "iv" => vec![ OlanElement::radius( -90), OlanElement::line(-90), OlanElement::combining(0), OlanElement::line(-90), OlanElement::radius(  90) ],
+        "z"  => vec![ OlanElement::radius( 135), OlanElement::line( 45), OlanElement::combining(0), OlanElement::line( 45), OlanElement::radius(-135) ],
+        "iz" => vec![ OlanElement::radius(-135), OlanElement::line(-45), OlanElement::combining(0), OlanElement::line(-45), OlanElement::radius( 135) ],
         _ => { unreachable!(); }
         }
     }
 
-pub fn get_elements_for_twin_line(figure_pair: Pair<Rule>) -> Vec<Element> {
+pub fn get_elements_for_twin_line(figure_pair: Pair<Rule>) -> Vec<OlanElement> {
     match figure_pair.as_str() {
-        "t"   => vec![ Element::radius(  45.0), Element::line( 45.0), Element::radius(-135.0), Element::line(-90.0), Element::radius(  90.0) ],
-        "it"  => vec![ Element::radius( -45.0), Element::line(-45.0), Element::radius( 135.0), Element::line( 90.0), Element::radius( -90.0) ],
-        "k"   => vec![ Element::radius(  90.0), Element::line( 90.0), Element::radius(-135.0), Element::line(-45.0), Element::radius(  45.0) ],
-        "ik"  => vec![ Element::radius( -90.0), Element::line(-90.0), Element::radius( 135.0), Element::line( 45.0), Element::radius( -45.0) ],
-        "zt"  => vec![ Element::radius( 135.0), Element::line( 45.0), Element::radius( 135.0), Element::line(-90.0), Element::radius(  90.0) ],
-        "izt" => vec![ Element::radius(-135.0), Element::line(-45.0), Element::radius(-135.0), Element::line( 90.0), Element::radius( -90.0) ],
-        "kz"  => vec![ Element::radius(  90.0), Element::line( 90.0), Element::radius(-135.0), Element::line(-45.0), Element::radius(-135.0) ],
-        "ikz" => vec![ Element::radius( -90.0), Element::line(-90.0), Element::radius( 135.0), Element::line( 45.0), Element::radius( 135.0) ],
+        "t"   => vec![ OlanElement::radius(  45), OlanElement::line( 45), OlanElement::radius(-135), OlanElement::line(-90), OlanElement::radius(  90) ],
+        "it"  => vec![ OlanElement::radius( -45), OlanElement::line(-45), OlanElement::radius( 135), OlanElement::line( 90), OlanElement::radius( -90) ],
+        "k"   => vec![ OlanElement::radius(  90), OlanElement::line( 90), OlanElement::radius(-135), OlanElement::line(-45), OlanElement::radius(  45) ],
+        "ik"  => vec![ OlanElement::radius( -90), OlanElement::line(-90), OlanElement::radius( 135), OlanElement::line( 45), OlanElement::radius( -45) ],
+        "zt"  => vec![ OlanElement::radius( 135), OlanElement::line( 45), OlanElement::radius( 135), OlanElement::line(-90), OlanElement::radius(  90) ],
+        "izt" => vec![ OlanElement::radius(-135), OlanElement::line(-45), OlanElement::radius(-135), OlanElement::line( 90), OlanElement::radius( -90) ],
+        "kz"  => vec![ OlanElement::radius(  90), OlanElement::line( 90), OlanElement::radius(-135), OlanElement::line(-45), OlanElement::radius(-135) ],
+        "ikz" => vec![ OlanElement::radius( -90), OlanElement::line(-90), OlanElement::radius( 135), OlanElement::line( 45), OlanElement::radius( 135) ],
         _ => { unreachable!(); }
         }
     }
 
-pub fn get_elements_for_loop_figure(figure_pair: Pair<Rule>) -> Vec<Element> {
+pub fn get_elements_for_loop_figure(figure_pair: Pair<Rule>) -> Vec<OlanElement> {
     match figure_pair.as_str() {
-        "a"   => vec![ Element::radius(-180.0) ],
-        "m"   => vec![ Element::radius( 180.0) ],
-        "o"   => vec![ Element::radius( 180.0), Element::combining(0), Element::radius( 180.0) ],
-        "io"  => vec![ Element::radius(-180.0), Element::combining(0), Element::radius(-180.0) ],
+        "a"   => vec![ OlanElement::radius(-180) ],
+        "m"   => vec![ OlanElement::radius( 180) ],
+        "o"   => vec![ OlanElement::radius( 180), OlanElement::combining(0), OlanElement::radius( 180) ],
+        "io"  => vec![ OlanElement::radius(-180), OlanElement::combining(0), OlanElement::radius(-180) ],
         "qo"  => vec![
-            Element::radius(90.0), Element::line(90.0),
-            Element::radius(90.0), Element::line(0.0),
-            Element::radius(90.0), Element::line(-90.0),
-            Element::radius(90.0), Element::line(0.0)
+            OlanElement::radius(90), OlanElement::line(90),
+            OlanElement::radius(90), OlanElement::line(0),
+            OlanElement::radius(90), OlanElement::line(-90),
+            OlanElement::radius(90), OlanElement::line(0)
             ],
         "iqo" => vec![
-            Element::radius(-90.0), Element::line(-90.0),
-            Element::radius(-90.0), Element::line(0.0),
-            Element::radius(-90.0), Element::line(90.0),
-            Element::radius(-90.0), Element::line(0.0)
+            OlanElement::radius(-90), OlanElement::line(-90),
+            OlanElement::radius(-90), OlanElement::line(0),
+            OlanElement::radius(-90), OlanElement::line(90),
+            OlanElement::radius(-90), OlanElement::line(0)
             ],
         "dq"  => vec![
-            Element::radius(45.0), Element::line( 45.0),
-            Element::radius(90.0), Element::line( 45.0),
-            Element::radius(90.0), Element::line(-45.0),
-            Element::combining(1), Element::line(-45.0),
-            Element::radius(90.0), Element::line(-45.0),
-            Element::radius(45.0)
+            OlanElement::radius(45), OlanElement::line( 45),
+            OlanElement::radius(90), OlanElement::line( 45),
+            OlanElement::radius(90), OlanElement::line(-45),
+            OlanElement::combining(1), OlanElement::line(-45),
+            OlanElement::radius(90), OlanElement::line(-45),
+            OlanElement::radius(45)
             ],
         "idq" => vec![
-            Element::radius(-45.0), Element::line(-45.0),
-            Element::radius(-90.0), Element::line(-45.0),
-            Element::radius(-90.0), Element::line( 45.0),
-            Element::combining(1), Element::line( 45.0),
-            Element::radius(-90.0), Element::line( 45.0),
-            Element::radius(-45.0)
+            OlanElement::radius(-45), OlanElement::line(-45),
+            OlanElement::radius(-90), OlanElement::line(-45),
+            OlanElement::radius(-90), OlanElement::line( 45),
+            OlanElement::combining(1), OlanElement::line( 45),
+            OlanElement::radius(-90), OlanElement::line( 45),
+            OlanElement::radius(-45)
             ],
         "qq"  => vec![
-            Element::radius(45.0), Element::line( 45.0),
-            Element::radius(45.0), Element::line( 90.0),
-            Element::radius(45.0), Element::line( 45.0),
-            Element::radius(45.0), Element::line(  0.0),
-            Element::radius(45.0), Element::line(-45.0),
-            Element::radius(45.0), Element::line(-90.0),
-            Element::radius(45.0), Element::line(-45.0),
-            Element::radius(45.0) 
+            OlanElement::radius(45), OlanElement::line( 45),
+            OlanElement::radius(45), OlanElement::line( 90),
+            OlanElement::radius(45), OlanElement::line( 45),
+            OlanElement::radius(45), OlanElement::line(  0),
+            OlanElement::radius(45), OlanElement::line(-45),
+            OlanElement::radius(45), OlanElement::line(-90),
+            OlanElement::radius(45), OlanElement::line(-45),
+            OlanElement::radius(45) 
             ],
         "iqq" => vec![
-            Element::radius(-45.0), Element::line(-45.0),
-            Element::radius(-45.0), Element::line(-90.0),
-            Element::radius(-45.0), Element::line(-45.0),
-            Element::radius(-45.0), Element::line(  0.0),
-            Element::radius(-45.0), Element::line( 45.0),
-            Element::radius(-45.0), Element::line( 90.0),
-            Element::radius(-45.0), Element::line( 45.0),
-            Element::radius(-45.0) 
+            OlanElement::radius(-45), OlanElement::line(-45),
+            OlanElement::radius(-45), OlanElement::line(-90),
+            OlanElement::radius(-45), OlanElement::line(-45),
+            OlanElement::radius(-45), OlanElement::line(  0),
+            OlanElement::radius(-45), OlanElement::line( 45),
+            OlanElement::radius(-45), OlanElement::line( 90),
+            OlanElement::radius(-45), OlanElement::line( 45),
+            OlanElement::radius(-45) 
             ],
         _ => { unreachable!(); }
         }
     }
 
-pub fn get_elements_for_loop_line_combo(figure_pair: Pair<Rule>) -> Vec<Element> {
+pub fn get_elements_for_loop_line_combo(figure_pair: Pair<Rule>) -> Vec<OlanElement> {
     match figure_pair.as_str() {
         "c"   => vec![
-            Element::radius(225.0),
-            Element::invline(-45.0), Element::combining(0), Element::invline(-45.0),
-            Element::radius(-45.0)
+            OlanElement::radius(225),
+            OlanElement::invline(-45), OlanElement::combining(0), OlanElement::invline(-45),
+            OlanElement::radius(-45)
             ],
         "ic"  => vec![
-            Element::radius(-225.0),
-            Element::invline(45.0), Element::combining(0), Element::invline(45.0),
-            Element::radius(45.0)
+            OlanElement::radius(-225),
+            OlanElement::invline(45), OlanElement::combining(0), OlanElement::invline(45),
+            OlanElement::radius(45)
             ],
         "rc"  => vec![
-            Element::radius(45.0),
-            Element::line(45.0), Element::combining(0), Element::line(45.0),
-            Element::radius(-225.0)
+            OlanElement::radius(45),
+            OlanElement::line(45), OlanElement::combining(0), OlanElement::line(45),
+            OlanElement::radius(-225)
             ],
         "irc" => vec![
-            Element::radius(-45.0),
-            Element::line(-45.0), Element::combining(0), Element::line(-45.0),
-            Element::radius(225.0)
+            OlanElement::radius(-45),
+            OlanElement::line(-45), OlanElement::combining(0), OlanElement::line(-45),
+            OlanElement::radius(225)
             ],
         "g"   => vec![
-            Element::radius(45.0),
-            Element::line(45.0), Element::combining(-1), Element::line(45.0),
-            Element::radius(-270.0),
-            Element::invline(45.0), Element::combining(0), Element::invline(45.0),
-            Element::radius(45.0)
+            OlanElement::radius(45),
+            OlanElement::line(45), OlanElement::combining(-1), OlanElement::line(45),
+            OlanElement::radius(-270),
+            OlanElement::invline(45), OlanElement::combining(0), OlanElement::invline(45),
+            OlanElement::radius(45)
             ],
         "ig"  => vec![
-            Element::radius(-45.0),
-            Element::line(-45.0), Element::combining(-1), Element::line(-45.0),
-            Element::radius(270.0),
-            Element::invline(-45.0), Element::combining(0), Element::invline(-45.0),
-            Element::radius(-45.0)
+            OlanElement::radius(-45),
+            OlanElement::line(-45), OlanElement::combining(-1), OlanElement::line(-45),
+            OlanElement::radius(270),
+            OlanElement::invline(-45), OlanElement::combining(0), OlanElement::invline(-45),
+            OlanElement::radius(-45)
             ],
         "p"   => vec![
-            Element::radius(270.0),
-            Element::line(-90.0), Element::combining(0), Element::line(-90.0),
-            Element::radius(90.0)
+            OlanElement::radius(270),
+            OlanElement::line(-90), OlanElement::combining(0), OlanElement::line(-90),
+            OlanElement::radius(90)
             ],
         "ip"  => vec![
-            Element::radius(-270.0),
-            Element::line(90.0), Element::combining(0), Element::line(90.0),
-            Element::radius(-90.0)
+            OlanElement::radius(-270),
+            OlanElement::line(90), OlanElement::combining(0), OlanElement::line(90),
+            OlanElement::radius(-90)
             ],
         "rp"  => vec![
-            Element::radius(90.0),
-            Element::line(90.0), Element::combining(-1), Element::line(90.0),
-            Element::radius(270.0)
+            OlanElement::radius(90),
+            OlanElement::line(90), OlanElement::combining(-1), OlanElement::line(90),
+            OlanElement::radius(270)
             ],
         "irp" => vec![
-            Element::radius(-90.0),
-            Element::line(-90.0), Element::combining(-1), Element::line(-90.0),
-            Element::radius(-270.0)
+            OlanElement::radius(-90),
+            OlanElement::line(-90), OlanElement::combining(-1), OlanElement::line(-90),
+            OlanElement::radius(-270)
             ],
         "q"   => vec![
-            Element::radius(315.0),
-            Element::line(-45.0), Element::combining(0), Element::line(-45.0),
-            Element::radius(45.0)
+            OlanElement::radius(315),
+            OlanElement::line(-45), OlanElement::combining(0), OlanElement::line(-45),
+            OlanElement::radius(45)
             ],
         "iq"  => vec![
-            Element::radius(-315.0),
-            Element::line(45.0), Element::combining(0), Element::line(45.0),
-            Element::radius(-45.0)
+            OlanElement::radius(-315),
+            OlanElement::line(45), OlanElement::combining(0), OlanElement::line(45),
+            OlanElement::radius(-45)
             ],
         "rq"  => vec![
-            Element::radius(45.0),
-            Element::line(45.0), Element::combining(-1), Element::line(45.0),
-            Element::radius(315.0)
+            OlanElement::radius(45),
+            OlanElement::line(45), OlanElement::combining(-1), OlanElement::line(45),
+            OlanElement::radius(315)
             ],
         "irq" => vec![
-            Element::radius(-45.0),
-            Element::line(-45.0), Element::combining(-1), Element::line(-45.0),
-            Element::radius(-315.0)
+            OlanElement::radius(-45),
+            OlanElement::line(-45), OlanElement::combining(-1), OlanElement::line(-45),
+            OlanElement::radius(-315)
             ],
         "y"   => vec![
-            Element::radius(45.0),
-            Element::line(45.0), Element::combining(-1), Element::line(45.0),
-            Element::radius(225.0),
-            Element::line(-90.0), Element::combining(0), Element::line(-90.0),
-            Element::radius(90.0)
+            OlanElement::radius(45),
+            OlanElement::line(45), OlanElement::combining(-1), OlanElement::line(45),
+            OlanElement::radius(225),
+            OlanElement::line(-90), OlanElement::combining(0), OlanElement::line(-90),
+            OlanElement::radius(90)
             ],
         "iy"  => vec![
-            Element::radius(-45.0),
-            Element::line(-45.0), Element::combining(-1), Element::line(-45.0),
-            Element::radius(-225.0),
-            Element::line(90.0), Element::combining(0), Element::line(90.0),
-            Element::radius(-90.0)
+            OlanElement::radius(-45),
+            OlanElement::line(-45), OlanElement::combining(-1), OlanElement::line(-45),
+            OlanElement::radius(-225),
+            OlanElement::line(90), OlanElement::combining(0), OlanElement::line(90),
+            OlanElement::radius(-90)
             ],
         "ry"  => vec![
-            Element::radius(90.0),
-            Element::line(90.0), Element::combining(-1), Element::line(90.0),
-            Element::radius(225.0),
-            Element::line(-45.0), Element::combining(0), Element::line(45.0),
-            Element::radius(45.0)
+            OlanElement::radius(90),
+            OlanElement::line(90), OlanElement::combining(-1), OlanElement::line(90),
+            OlanElement::radius(225),
+            OlanElement::line(-45), OlanElement::combining(0), OlanElement::line(45),
+            OlanElement::radius(45)
             ],
         "iry" => vec![
-            Element::radius(-90.0),
-            Element::line(-90.0), Element::combining(-1), Element::line(-90.0),
-            Element::radius(-225.0),
-            Element::line(45.0), Element::combining(0), Element::line(45.0),
-            Element::radius(-45.0)
+            OlanElement::radius(-90),
+            OlanElement::line(-90), OlanElement::combining(-1), OlanElement::line(-90),
+            OlanElement::radius(-225),
+            OlanElement::line(45), OlanElement::combining(0), OlanElement::line(45),
+            OlanElement::radius(-45)
             ],
         _ => { unreachable!(); }
         }
     }
 
-pub fn get_elements_for_double_loop(figure_pair: Pair<Rule>) -> Vec<Element> {
+pub fn get_elements_for_double_loop(figure_pair: Pair<Rule>) -> Vec<OlanElement> {
     match figure_pair.as_str() {
         "cc" => vec![
-            Element::line(0.0), Element::combining(-1),
-            Element::radius(225.0),
-            Element::invline(-45.0), Element::combining(1), Element::invline(-45.0),
-            Element::radius(-270.0),
-            Element::line(-45.0), Element::combining(0), Element::line(-45.0),
-            Element::radius(45.0)
+            OlanElement::line(0), OlanElement::combining(-1),
+            OlanElement::radius(225),
+            OlanElement::invline(-45), OlanElement::combining(1), OlanElement::invline(-45),
+            OlanElement::radius(-270),
+            OlanElement::line(-45), OlanElement::combining(0), OlanElement::line(-45),
+            OlanElement::radius(45)
             ],
         "rcc" => vec![
-            Element::radius(45.0),
-            Element::line(45.0), Element::combining(-1), Element::line(45.0), 
-            Element::radius(-270.0),
-            Element::invline(45.0), Element::combining(1), Element::invline(45.0),
-            Element::radius(225.0),
-            Element::combining(0),
-            Element::line(0.0)
+            OlanElement::radius(45),
+            OlanElement::line(45), OlanElement::combining(-1), OlanElement::line(45), 
+            OlanElement::radius(-270),
+            OlanElement::invline(45), OlanElement::combining(1), OlanElement::invline(45),
+            OlanElement::radius(225),
+            OlanElement::combining(0),
+            OlanElement::line(0)
             ],
         "oo" => vec![
-            Element::radius(180.0),
-            Element::radius(-360.0),
-            Element::radius(180.0)
+            OlanElement::radius(180),
+            OlanElement::radius(-360),
+            OlanElement::radius(180)
             ],
         "icc" => vec![
-            Element::line(0.0), Element::combining(-1),
-            Element::radius(-225.0),
-            Element::invline(45.0), Element::combining(1), Element::invline(45.0),
-            Element::radius(270.0),
-            Element::line(45.0), Element::combining(0), Element::line(45.0),
-            Element::radius(-45.0)
+            OlanElement::line(0), OlanElement::combining(-1),
+            OlanElement::radius(-225),
+            OlanElement::invline(45), OlanElement::combining(1), OlanElement::invline(45),
+            OlanElement::radius(270),
+            OlanElement::line(45), OlanElement::combining(0), OlanElement::line(45),
+            OlanElement::radius(-45)
             ],
         "ircc" => vec![
-            Element::radius(-45.0),
-            Element::line(-45.0), Element::combining(-1), Element::line(-45.0),
-            Element::line(270.0),
-            Element::invline(-45.0), Element::combining(1), Element::invline(-45.0),
-            Element::line(-225.0),
-            Element::combining(0),
-            Element::line(0.0)
+            OlanElement::radius(-45),
+            OlanElement::line(-45), OlanElement::combining(-1), OlanElement::line(-45),
+            OlanElement::line(270),
+            OlanElement::invline(-45), OlanElement::combining(1), OlanElement::invline(-45),
+            OlanElement::line(-225),
+            OlanElement::combining(0),
+            OlanElement::line(0)
             ],
         "ioo" => vec![
-            Element::radius(-180.0),
-            Element::radius(360.0),
-            Element::radius(-180.0)
+            OlanElement::radius(-180),
+            OlanElement::radius(360),
+            OlanElement::radius(-180)
             ],
         "ooo" => vec![
-            Element::radius(360.0),
-            Element::radius(-360.0),
+            OlanElement::radius(360),
+            OlanElement::radius(-360),
             ],
         _ => { unreachable!(); }
         }
     }
 
-pub fn get_elements_for_humpty(figure_pair: Pair<Rule>) -> Vec<Element> {
+pub fn get_elements_for_humpty(figure_pair: Pair<Rule>) -> Vec<OlanElement> {
     match figure_pair.as_str() {
         "b" => vec![
-            Element::radius(90.0),
-            Element::line(90.0), Element::combining(-1), Element::line(90.0),
-            Element::radius(180.0),
-            Element::line(-90.0), Element::combining(0), Element::line(-90.0),
-            Element::radius(90.0),
+            OlanElement::radius(90),
+            OlanElement::line(90), OlanElement::combining(-1), OlanElement::line(90),
+            OlanElement::radius(180),
+            OlanElement::line(-90), OlanElement::combining(0), OlanElement::line(-90),
+            OlanElement::radius(90),
             ],
         "pb" => vec![
-            Element::radius(90.0),
-            Element::line(90.0), Element::combining(-1), Element::line(90.0),
-            Element::radius(-180.0),
-            Element::line(-90.0), Element::combining(0), Element::line(-90.0),
-            Element::radius(90.0),
+            OlanElement::radius(90),
+            OlanElement::line(90), OlanElement::combining(-1), OlanElement::line(90),
+            OlanElement::radius(-180),
+            OlanElement::line(-90), OlanElement::combining(0), OlanElement::line(-90),
+            OlanElement::radius(90),
             ],
         "bb" => vec![
-            Element::radius(90.0),
-            Element::line(90.0), Element::combining(-1), Element::line(90.0),
-            Element::radius(180.0),
-            Element::line(-90.0), Element::combining(1), Element::line(-90.0),
-            Element::radius(180.0),
-            Element::line(90.0), Element::combining(0), Element::line(90.0),
-            Element::radius(-90.0),
+            OlanElement::radius(90),
+            OlanElement::line(90), OlanElement::combining(-1), OlanElement::line(90),
+            OlanElement::radius(180),
+            OlanElement::line(-90), OlanElement::combining(1), OlanElement::line(-90),
+            OlanElement::radius(180),
+            OlanElement::line(90), OlanElement::combining(0), OlanElement::line(90),
+            OlanElement::radius(-90),
             ],
         "pbb" => vec![
-            Element::radius(90.0),
-            Element::line(90.0), Element::combining(-1), Element::line(90.0),
-            Element::radius(-180.0),
-            Element::line(-90.0), Element::combining(1), Element::line(-90.0),
-            Element::radius(180.0),
-            Element::line(90.0), Element::combining(0), Element::line(90.0),
-            Element::radius(-90.0),
+            OlanElement::radius(90),
+            OlanElement::line(90), OlanElement::combining(-1), OlanElement::line(90),
+            OlanElement::radius(-180),
+            OlanElement::line(-90), OlanElement::combining(1), OlanElement::line(-90),
+            OlanElement::radius(180),
+            OlanElement::line(90), OlanElement::combining(0), OlanElement::line(90),
+            OlanElement::radius(-90),
             ],
         "db" => vec![
-            Element::radius(45.0),
-            Element::line(45.0), Element::combining(-1), Element::line(90.0),
-            Element::radius(180.0),
-            Element::invline(-45.0), Element::combining(0), Element::invline(-45.0),
-            Element::radius(-45.0),
+            OlanElement::radius(45),
+            OlanElement::line(45), OlanElement::combining(-1), OlanElement::line(90),
+            OlanElement::radius(180),
+            OlanElement::invline(-45), OlanElement::combining(0), OlanElement::invline(-45),
+            OlanElement::radius(-45),
             ],
         "rdb" => vec![
-            Element::radius(45.0),
-            Element::line(45.0), Element::combining(-1), Element::line(90.0),
-            Element::radius(-180.0),
-            Element::invline(-45.0), Element::combining(0), Element::invline(-45.0),
-            Element::radius(-45.0),
+            OlanElement::radius(45),
+            OlanElement::line(45), OlanElement::combining(-1), OlanElement::line(90),
+            OlanElement::radius(-180),
+            OlanElement::invline(-45), OlanElement::combining(0), OlanElement::invline(-45),
+            OlanElement::radius(-45),
             ],
         _ => { unreachable!(); }
         }
     }
 
-pub fn get_elements_for_hammerhead(figure_pair: Pair<Rule>) -> Vec<Element> {
+pub fn get_elements_for_hammerhead(figure_pair: Pair<Rule>) -> Vec<OlanElement> {
     match figure_pair.as_str() {
         "h" => vec![
-            Element::radius(90.0),
-            Element::line(90.0), Element::combining(-1), Element::line(90.0),
-            Element::stall(180.0,0.0),
-            Element::line(-90.0), Element::combining(0), Element::line(-90.0),
-            Element::radius(90.0),
+            OlanElement::radius(90),
+            OlanElement::line(90), OlanElement::combining(-1), OlanElement::line(90),
+            OlanElement::stall(180,0),
+            OlanElement::line(-90), OlanElement::combining(0), OlanElement::line(-90),
+            OlanElement::radius(90),
             ],
         "dh"   => vec![
-            Element::radius(45.0),
-            Element::line(45.0), Element::combining(-1), Element::line(45.0),
-            Element::radius(45.0),
-            Element::line(90.0), Element::combining(1), Element::line(90.0),
-            Element::stall(180.0,0.0),
-            Element::line(-90.0), Element::combining(0), Element::line(-90.0),
-            Element::radius(90.0),
+            OlanElement::radius(45),
+            OlanElement::line(45), OlanElement::combining(-1), OlanElement::line(45),
+            OlanElement::radius(45),
+            OlanElement::line(90), OlanElement::combining(1), OlanElement::line(90),
+            OlanElement::stall(180,0),
+            OlanElement::line(-90), OlanElement::combining(0), OlanElement::line(-90),
+            OlanElement::radius(90),
             ],
         "hd"  => vec![ 
-            Element::radius(90.0),
-            Element::line(90.0), Element::combining(-1), Element::line(90.0),
-            Element::stall(180.0,0.0),
-            Element::line(-90.0), Element::combining(1), Element::line(-90.0),
-            Element::radius(45.0),
-            Element::line(-45.0), Element::combining(0), Element::line(-45.0),
-            Element::radius(45.0),
+            OlanElement::radius(90),
+            OlanElement::line(90), OlanElement::combining(-1), OlanElement::line(90),
+            OlanElement::stall(180,0),
+            OlanElement::line(-90), OlanElement::combining(1), OlanElement::line(-90),
+            OlanElement::radius(45),
+            OlanElement::line(-45), OlanElement::combining(0), OlanElement::line(-45),
+            OlanElement::radius(45),
             ],
         "dhd"  => vec![
-            Element::radius(45.0),
-            Element::line(45.0), Element::combining(-1), Element::line(45.0),
-            Element::radius(45.0),
-            Element::line(90.0), Element::combining(1), Element::line(90.0),
-            Element::stall(180.0,0.0),
-            Element::line(-90.0), Element::combining(2), Element::line(-90.0),
-            Element::radius(45.0),
-            Element::line(-45.0), Element::combining(0), Element::line(-45.0),
-            Element::radius(45.0),
+            OlanElement::radius(45),
+            OlanElement::line(45), OlanElement::combining(-1), OlanElement::line(45),
+            OlanElement::radius(45),
+            OlanElement::line(90), OlanElement::combining(1), OlanElement::line(90),
+            OlanElement::stall(180,0),
+            OlanElement::line(-90), OlanElement::combining(2), OlanElement::line(-90),
+            OlanElement::radius(45),
+            OlanElement::line(-45), OlanElement::combining(0), OlanElement::line(-45),
+            OlanElement::radius(45),
             ],
         "ta" => vec![
-            Element::radius(90.0),
-            Element::line(90.0), Element::combining(-1), Element::line(90.0),
-            Element::stall(0.0,-180.0),
-            Element::line(-90.0), Element::combining(0), Element::line(-90.0),
-            Element::radius(90.0),
+            OlanElement::radius(90),
+            OlanElement::line(90), OlanElement::combining(-1), OlanElement::line(90),
+            OlanElement::stall(0,-180),
+            OlanElement::line(-90), OlanElement::combining(0), OlanElement::line(-90),
+            OlanElement::radius(90),
             ],
         "ita" => vec![
-            Element::radius(90.0),
-            Element::line(90.0), Element::combining(-1), Element::line(90.0),
-            Element::stall(0.0,180.0),
-            Element::line(-90.0), Element::combining(0), Element::line(-90.0),
-            Element::radius(90.0),
+            OlanElement::radius(90),
+            OlanElement::line(90), OlanElement::combining(-1), OlanElement::line(90),
+            OlanElement::stall(0,180),
+            OlanElement::line(-90), OlanElement::combining(0), OlanElement::line(-90),
+            OlanElement::radius(90),
             ],
         _ => { unreachable!(); }
         }
     }
 
-pub fn get_elements_for_three_roll(figure_pair: Pair<Rule>) -> Vec<Element> {
+pub fn get_elements_for_three_roll(figure_pair: Pair<Rule>) -> Vec<OlanElement> {
     match figure_pair.as_str() {
         "n"   => vec![
-            Element::radius(90.0),
-            Element::line(90.0), Element::combining(-1), Element::line(90.0),
-            Element::radius(135.0),
-            Element::invline(-45.0), Element::combining(1), Element::invline(-45.0),
-            Element::radius(-135.0),
-            Element::line(90.0), Element::combining(0), Element::line(90.0),
-            Element::radius(-90.0),
+            OlanElement::radius(90),
+            OlanElement::line(90), OlanElement::combining(-1), OlanElement::line(90),
+            OlanElement::radius(135),
+            OlanElement::invline(-45), OlanElement::combining(1), OlanElement::invline(-45),
+            OlanElement::radius(-135),
+            OlanElement::line(90), OlanElement::combining(0), OlanElement::line(90),
+            OlanElement::radius(-90),
             ],
         "in"  => vec![
-            Element::radius(-90.0),
-            Element::line(-90.0), Element::combining(-1), Element::line(-90.0),
-            Element::radius(135.0),
-            Element::line(45.0), Element::combining(1), Element::line(45.0),
-            Element::radius(-135.0),
-            Element::line(-90.0), Element::combining(0), Element::line(-90.0),
-            Element::radius(90.0),
+            OlanElement::radius(-90),
+            OlanElement::line(-90), OlanElement::combining(-1), OlanElement::line(-90),
+            OlanElement::radius(135),
+            OlanElement::line(45), OlanElement::combining(1), OlanElement::line(45),
+            OlanElement::radius(-135),
+            OlanElement::line(-90), OlanElement::combining(0), OlanElement::line(-90),
+            OlanElement::radius(90),
             ],
         "pn"  => vec![
-            Element::radius(90.0),
-            Element::line(90.0), Element::combining(-1), Element::line(90.0),
-            Element::radius(-135.0),
-            Element::line(-45.0), Element::combining(1), Element::line(-45.0),
-            Element::radius(135.0),
-            Element::line(90.0), Element::combining(0), Element::line(90.0),
-            Element::radius(-90.0),
+            OlanElement::radius(90),
+            OlanElement::line(90), OlanElement::combining(-1), OlanElement::line(90),
+            OlanElement::radius(-135),
+            OlanElement::line(-45), OlanElement::combining(1), OlanElement::line(-45),
+            OlanElement::radius(135),
+            OlanElement::line(90), OlanElement::combining(0), OlanElement::line(90),
+            OlanElement::radius(-90),
             ],
         "ipn" => vec![
-            Element::radius(-90.0),
-            Element::line(90.0), Element::combining(-1), Element::line(90.0),
-            Element::radius(-135.0),
-            Element::invline(45.0), Element::combining(1), Element::invline(45.0),
-            Element::radius(135.0),
-            Element::line(-90.0), Element::combining(0), Element::line(-90.0),
-            Element::radius(90.0),
+            OlanElement::radius(-90),
+            OlanElement::line(90), OlanElement::combining(-1), OlanElement::line(90),
+            OlanElement::radius(-135),
+            OlanElement::invline(45), OlanElement::combining(1), OlanElement::invline(45),
+            OlanElement::radius(135),
+            OlanElement::line(-90), OlanElement::combining(0), OlanElement::line(-90),
+            OlanElement::radius(90),
             ],
         "w"   => vec![
-            Element::radius(45.0),
-            Element::line(45.0), Element::combining(-1), Element::line(45.0),
-            Element::radius(-135.0),
-            Element::line(-90.0), Element::combining(1), Element::line(-90.0),
-            Element::radius(135.0),
-            Element::line(45.0), Element::combining(0), Element::line(45.0),
-            Element::radius(-45.0),
+            OlanElement::radius(45),
+            OlanElement::line(45), OlanElement::combining(-1), OlanElement::line(45),
+            OlanElement::radius(-135),
+            OlanElement::line(-90), OlanElement::combining(1), OlanElement::line(-90),
+            OlanElement::radius(135),
+            OlanElement::line(45), OlanElement::combining(0), OlanElement::line(45),
+            OlanElement::radius(-45),
             ],
         "iw"  => vec![
-            Element::radius(-45.0),
-            Element::line(-45.0), Element::combining(-1), Element::line(-45.0),
-            Element::radius(135.0),
-            Element::line(90.0), Element::combining(1), Element::line(90.0),
-            Element::radius(-135.0),
-            Element::line(-45.0), Element::combining(0), Element::line(-45.0),
-            Element::radius(45.0),
+            OlanElement::radius(-45),
+            OlanElement::line(-45), OlanElement::combining(-1), OlanElement::line(-45),
+            OlanElement::radius(135),
+            OlanElement::line(90), OlanElement::combining(1), OlanElement::line(90),
+            OlanElement::radius(-135),
+            OlanElement::line(-45), OlanElement::combining(0), OlanElement::line(-45),
+            OlanElement::radius(45),
             ],
         "gg"  => vec![
-            Element::radius(45.0),
-            Element::line(45.0), Element::combining(-1), Element::line(45.0),
-            Element::radius(-270.0),
-            Element::invline(45.0), Element::combining(1), Element::invline(45.0),
-            Element::radius(270.0),
-            Element::line(45.0), Element::combining(0), Element::line(45.0),
-            Element::radius(-45.0),
+            OlanElement::radius(45),
+            OlanElement::line(45), OlanElement::combining(-1), OlanElement::line(45),
+            OlanElement::radius(-270),
+            OlanElement::invline(45), OlanElement::combining(1), OlanElement::invline(45),
+            OlanElement::radius(270),
+            OlanElement::line(45), OlanElement::combining(0), OlanElement::line(45),
+            OlanElement::radius(-45),
             ],
         "igg" => vec![
-            Element::radius(-45.0),
-            Element::line(-45.0), Element::combining(-1), Element::line(-45.0),
-            Element::radius(270.0),
-            Element::invline(-45.0), Element::combining(1), Element::invline(-45.0),
-            Element::radius(-270.0),
-            Element::line(-45.0), Element::combining(0), Element::line(-45.0),
-            Element::radius(45.0),
+            OlanElement::radius(-45),
+            OlanElement::line(-45), OlanElement::combining(-1), OlanElement::line(-45),
+            OlanElement::radius(270),
+            OlanElement::invline(-45), OlanElement::combining(1), OlanElement::invline(-45),
+            OlanElement::radius(-270),
+            OlanElement::line(-45), OlanElement::combining(0), OlanElement::line(-45),
+            OlanElement::radius(45),
             ],
         _ => { unreachable!(); }
         }
     }
 
-pub fn get_elements_for_extra(figure_pair: Pair<Rule>) -> Vec<Element> {
+pub fn get_elements_for_extra(figure_pair: Pair<Rule>) -> Vec<OlanElement> {
     match figure_pair.as_str() {
         "mm" => vec![
-            Element::radius(180.0),
-            Element::radius(-180.0),
+            OlanElement::radius(180),
+            OlanElement::radius(-180),
             ],
         "zb" => vec![
-            Element::radius(135.0),
-            Element::invline(45.0), Element::combining(-1), Element::invline(45.0),
-            Element::radius(180.0),
-            Element::line(-45.0), Element::combining(0), Element::line(-45.0),
-            Element::radius(45.0),
+            OlanElement::radius(135),
+            OlanElement::invline(45), OlanElement::combining(-1), OlanElement::invline(45),
+            OlanElement::radius(180),
+            OlanElement::line(-45), OlanElement::combining(0), OlanElement::line(-45),
+            OlanElement::radius(45),
             ],
         "rzb" => vec![
-            Element::radius(135.0),
-            Element::invline(45.0), Element::combining(-1), Element::invline(45.0),
-            Element::radius(-180.0),
-            Element::line(-45.0), Element::combining(0), Element::line(-45.0),
-            Element::radius(45.0),
+            OlanElement::radius(135),
+            OlanElement::invline(45), OlanElement::combining(-1), OlanElement::invline(45),
+            OlanElement::radius(-180),
+            OlanElement::line(-45), OlanElement::combining(0), OlanElement::line(-45),
+            OlanElement::radius(45),
             ],
         "bz" => vec![
-            Element::radius(45.0),
-            Element::line(45.0), Element::combining(-1), Element::line(45.0),
-            Element::radius(180.0),
-            Element::invline(-45.0), Element::combining(0), Element::invline(-45.0),
-            Element::radius(135.0),
+            OlanElement::radius(45),
+            OlanElement::line(45), OlanElement::combining(-1), OlanElement::line(45),
+            OlanElement::radius(180),
+            OlanElement::invline(-45), OlanElement::combining(0), OlanElement::invline(-45),
+            OlanElement::radius(135),
             ],
         "rbz" => vec![
-            Element::radius(45.0),
-            Element::line(45.0), Element::combining(-1), Element::line(45.0),
-            Element::radius(-180.0),
-            Element::invline(-45.0), Element::combining(0), Element::invline(-45.0),
-            Element::radius(135.0),
+            OlanElement::radius(45),
+            OlanElement::line(45), OlanElement::combining(-1), OlanElement::line(45),
+            OlanElement::radius(-180),
+            OlanElement::invline(-45), OlanElement::combining(0), OlanElement::invline(-45),
+            OlanElement::radius(135),
             ],
         "zy" => vec![
-            Element::radius(135.0),
-            Element::invline(45.0), Element::combining(-1), Element::invline(45.0),
-            Element::radius(-225.0),
-            Element::invline(-90.0), Element::combining(0), Element::invline(-90.0),
-            Element::radius(90.0),
+            OlanElement::radius(135),
+            OlanElement::invline(45), OlanElement::combining(-1), OlanElement::invline(45),
+            OlanElement::radius(-225),
+            OlanElement::invline(-90), OlanElement::combining(0), OlanElement::invline(-90),
+            OlanElement::radius(90),
             ],
         _ => { unreachable!(); }
         }
     }
 
-pub fn get_elements_for_rolling_turn(figure_pair: Pair<Rule>) -> Vec<Element> {
+pub fn get_elements_for_rolling_turn(figure_pair: Pair<Rule>) -> Vec<OlanElement> {
     let mut inner_pairs = figure_pair.into_inner();
     let mut current_pair = inner_pairs.next().unwrap();
 
     // Default turn angle is 90, check for multipliers
-    let mut turn_angle = 90.0;
+    let mut turn_angle = 90;
     if current_pair.as_rule() == Rule::turn_angle {
-        turn_angle = 90.0 * current_pair.as_str().parse::<f32>().unwrap();
+        turn_angle = 90 * current_pair.as_str().parse::<i32>().unwrap();
         current_pair = inner_pairs.next().unwrap();
         }
     
@@ -503,88 +517,61 @@ pub fn get_elements_for_rolling_turn(figure_pair: Pair<Rule>) -> Vec<Element> {
         _ => { unreachable!(); }
         };
     
-    let mut turn_rolls = 0.0;
+    // Total roll through the turn (degrees)
+    let mut turn_roll = 0;
     if let Some(current_pair) = inner_pairs.next() {
         match current_pair.as_str() {
             "1" | "2" | "3" | "4" => {
-                turn_rolls = current_pair.as_str().parse::<f32>().unwrap();
+                turn_roll = 360 * current_pair.as_str().parse::<i32>().unwrap();
                 }
             "15" => {
-                turn_rolls = 1.5;
+                turn_roll = 540;
                 }
             _ => { unreachable!(); }
             }
         }
     
-    if turn_rolls == 0.0 {
+    let turn = |angle: i32, roll: i32| OlanElement { angle: Degrees(angle), argument: roll, .. OlanElement::new(ElementType::Turn) };
+
+    if turn_roll == 0 {
         // We don't care about type as we're not rolling
-        return vec![
-            Element{
-                angle: turn_angle,
-                .. Element::new(ElementType::Turn)
-                }
-            ]
+        return vec![ turn(turn_angle, 0) ];
         }
 
     if turn_type == TurnType::J || turn_type == TurnType::JO {
         return match turn_type {
-            TurnType::J  => vec![
-                Element{
-                    angle: turn_angle,
-                    argument: 360.0 * turn_rolls,
-                    .. Element::new(ElementType::Turn)
-                    }
-                ],
-            TurnType::JO => vec![
-                Element{
-                    angle: turn_angle,
-                    argument: -360.0 * turn_rolls,
-                    .. Element::new(ElementType::Turn)
-                    }
-                ],
+            TurnType::J  => vec![ turn(turn_angle, turn_roll) ],
+            TurnType::JO => vec![ turn(turn_angle, -turn_roll) ],
             _ => { unreachable!(); }
             }
         }
     
     let initial_direction = match turn_type {
-        TurnType::JIO =>  1.0,
-        TurnType::JOI => -1.0,
+        TurnType::JIO =>  1,
+        TurnType::JOI => -1,
         _ => { unreachable!(); }
         };
-    let turn_angle_per_roll = turn_angle / turn_rolls;
-    if turn_rolls == 1.5 {
+    // Angle turned between two points of the roll, in whole degrees that add up to the whole turn
+    let turned = |from_roll: i32, to_roll: i32| turn_angle * to_roll / turn_roll - turn_angle * from_roll / turn_roll;
+    if turn_roll == 540 {
         // Finicky special case...
         return vec![
-            Element{
-                angle: turn_angle_per_roll,
-                argument: initial_direction * 360.0,
-                .. Element::new(ElementType::Turn)
-                },
-            Element{
-                angle: turn_angle_per_roll * 0.5,
-                argument: initial_direction * -180.0,
-                .. Element::new(ElementType::Turn)
-                }
+            turn(turned(0, 360), initial_direction * 360),
+            turn(turned(360, 540), initial_direction * -180),
             ]
         }
     
     // Integer number of rolls
     let mut direction = initial_direction;
-    let mut elements = Vec::<Element>::new();
-    for _ in 1..(turn_rolls as i8) {
-        elements.push(
-            Element {
-                angle: turn_angle_per_roll,
-                argument: direction * 360.0,
-                .. Element::new(ElementType::Turn)
-                }
-            );
+    let mut elements = Vec::<OlanElement>::new();
+    for roll in 1..(turn_roll / 360) {
+        elements.push( turn(turned(360 * (roll - 1), 360 * roll), direction * 360) );
         direction = -direction; 
         }
     
     return elements;
     }
 
-pub fn get_elements_for_non_aresti(figure_pair: Pair<Rule>) -> Vec<Element> {
-    Vec::<Element>::new()
+pub fn get_elements_for_non_aresti(figure_pair: Pair<Rule>) -> Vec<OlanElement> {
+    Vec::<OlanElement>::new()
     }
\ No newline at end of file
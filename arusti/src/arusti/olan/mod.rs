@@ -1,4 +1,8 @@
 pub mod parser;
 mod figure_defs;
+mod element;
+pub mod diagnostics;
 
-pub use parser::parse_sequence;
\ No newline at end of file
+pub use parser::{parse_sequence,try_parse_sequence};
+pub use figure_defs::FIGURE_KEYS;
+pub use diagnostics::{diagnose,Diagnostic,DiagnosticKind};
@@ -6,11 +6,15 @@ use pest::Parser;
 #[grammar = "arusti/olan/olan.pest"]
 struct OLANParser;
 
-use crate::types::{ElementType, Element, Figure, Sequence};
+use crate::types::{ElementType, Element, FlatElement, Figure, LayoutCommand, Sequence};
+use crate::olan::element::{Degrees, OlanElement};
 
+use std::ops::Range;
+
+use pest::error::InputLocation;
 use pest::iterators::Pair;
 
-fn get_element_for_roll_element(roll_element_pair: Pair<Rule>, reverse_roll: bool) -> Element {
+fn get_element_for_roll_element(roll_element_pair: Pair<Rule>, reverse_roll: bool) -> OlanElement {
     let roll_angle : i8;
     let mut roll_divisions : i8 = 1;
     let mut elem_type : ElementType = ElementType::Roll;
@@ -63,49 +67,23 @@ fn get_element_for_roll_element(roll_element_pair: Pair<Rule>, reverse_roll: boo
             }
         }
 
-    let computed_roll : f32 = match roll_divisions {
+    // Hesitation rolls are a number of points of the roll, otherwise a number of quarters apart from 1, 4 and 9
+    let computed_roll : i32 = match roll_divisions {
         1 => match roll_angle {
-            1 => 360.0,
-            2 => 180.0,
-            3 => 270.0,
-            4 => 90.0,
-            5 => 450.0,
-            6 => 540.0,
-            7 => 630.0,
-            9 => 720.0,
-            _ => { unreachable!(); }
-            },
-        2 => match roll_angle {
-            2 => 360.0,
-            3 => 540.0,
-            4 => 720.0,
-            _ => { unreachable!(); }
-            },
-        4 => match roll_angle {
-            2 => 180.0,
-            3 => 270.0,
-            4 => 360.0,
-            5 => 450.0,
-            6 => 540.0,
-            7 => 630.0,
-            8 => 720.0,
+            1 => 360,
+            4 => 90,
+            9 => 720,
+            2 | 3 | 5..=7 => 90 * roll_angle as i32,
             _ => { unreachable!(); }
             },
-        8 => match roll_angle {
-            2 => 90.0,
-            4 => 180.0,
-            6 => 270.0,
-            8 => 360.0,
-            _ => { unreachable!(); }
-            }
-        _ => { unreachable!(); }
+        _ => 360 * roll_angle as i32 / roll_divisions as i32,
         };
 
-    Element {
+    OlanElement {
         elem_type: elem_type,
         inverted: inverted,
-        angle: if reverse_roll { -computed_roll } else { computed_roll },
-        argument: roll_divisions as f32
+        angle: Degrees( if reverse_roll { -computed_roll } else { computed_roll } ),
+        argument: roll_divisions as i32
         }
     }
 
@@ -113,12 +91,12 @@ fn is_direction_swap(roll_separator: Pair<Rule>) -> bool {
     roll_separator.as_str().to_string().contains(",")
     }
 
-fn get_elements_for_roll_set(roll_set_pair: Pair<Rule>) -> Vec<Element> {
+fn get_elements_for_roll_set(roll_set_pair: Pair<Rule>) -> Vec<OlanElement> {
     let mut inner_pairs = roll_set_pair.into_inner();
 
     let mut reverse_state = false;
 
-    let mut elements = Vec::<Element>::new();
+    let mut elements = Vec::<OlanElement>::new();
 
     loop {
         if let Some(current_pair) = inner_pairs.next() {
@@ -146,7 +124,7 @@ fn get_elements_for_roll_set(roll_set_pair: Pair<Rule>) -> Vec<Element> {
 
 use crate::olan::figure_defs;
 
-fn get_elements_for_main_figure(figure_pair: Pair<Rule>) -> Vec<Element> {
+fn get_elements_for_main_figure(figure_pair: Pair<Rule>) -> Vec<OlanElement> {
     let current_pair = figure_pair.into_inner().next().unwrap();
 
     match current_pair.as_rule() {
@@ -164,10 +142,10 @@ fn get_elements_for_main_figure(figure_pair: Pair<Rule>) -> Vec<Element> {
         }
     }
 
-fn find_combining_with_arg(elements: &Vec<Element>, argument: i8) -> Option<usize> {
+fn find_combining_with_arg(elements: &Vec<OlanElement>, argument: i8) -> Option<usize> {
     let result = elements.iter().enumerate().find(
         |(_,elem)| {
-            (elem.elem_type == ElementType::Combining) && ( elem.argument == ( argument as f32 ) )
+            (elem.elem_type == ElementType::Combining) && ( elem.argument == ( argument as i32 ) )
             }
         );
     match result {
@@ -176,7 +154,7 @@ fn find_combining_with_arg(elements: &Vec<Element>, argument: i8) -> Option<usiz
         }
     }
 
-fn insert_combining_rolls(elements: &mut Vec<Element>, position: i8, roll_set_opt: Option<Vec<Element>>) -> Option<Vec<Element>> {
+fn insert_combining_rolls(elements: &mut Vec<OlanElement>, position: i8, roll_set_opt: Option<Vec<OlanElement>>) -> Option<Vec<OlanElement>> {
     // Xfif(Y)(Z)W
     // X => -1
     // Y => 1
@@ -201,16 +179,16 @@ fn insert_combining_rolls(elements: &mut Vec<Element>, position: i8, roll_set_op
         }
     }
 
-fn get_elements_for_named_figure(figure_pair: Pair<Rule>) -> Vec<Element> {
+fn get_elements_for_named_figure(figure_pair: Pair<Rule>) -> Vec<OlanElement> {
     let mut inner_pairs = figure_pair.into_inner();
     let mut current_pair = inner_pairs.next().unwrap();
 
-    let mut elements = Vec::<Element>::new();
+    let mut elements = Vec::<OlanElement>::new();
 
-    let mut entry_roll_set_opt  : Option<Vec<Element>> = None;
-    let mut inner_roll_set_opt  : Option<Vec<Element>> = None;
-    let mut inner_roll_set2_opt : Option<Vec<Element>> = None;
-    let mut exit_roll_set_opt   : Option<Vec<Element>> = None;
+    let mut entry_roll_set_opt  : Option<Vec<OlanElement>> = None;
+    let mut inner_roll_set_opt  : Option<Vec<OlanElement>> = None;
+    let mut inner_roll_set2_opt : Option<Vec<OlanElement>> = None;
+    let mut exit_roll_set_opt   : Option<Vec<OlanElement>> = None;
 
     if current_pair.as_rule() == Rule::roll_set {
         entry_roll_set_opt = Some( get_elements_for_roll_set(current_pair) );
@@ -266,7 +244,7 @@ fn get_elements_for_named_figure(figure_pair: Pair<Rule>) -> Vec<Element> {
     }
 
 
-fn get_elements_for_rolling_figure(figure_pair: Pair<Rule>) -> Vec<Element> {
+fn get_elements_for_rolling_figure(figure_pair: Pair<Rule>) -> Vec<OlanElement> {
     let mut inner_pairs = figure_pair.into_inner();
     
     if let Some(roll_set_pair) = inner_pairs.next() {
@@ -274,7 +252,7 @@ fn get_elements_for_rolling_figure(figure_pair: Pair<Rule>) -> Vec<Element> {
         }
     else {
         // Rolling figure is actually just a line
-        Vec::<Element>::new()
+        Vec::<OlanElement>::new()
         }
 
     }
@@ -283,7 +261,7 @@ fn is_transition_inverted(line_extension: &Pair<Rule>) -> bool {
     line_extension.as_str().to_string().contains("-")
     }
 
-fn invert_figure_elements(elements: &mut Vec<Element>, from_idx: usize) {
+fn invert_figure_elements(elements: &mut [OlanElement], from_idx: usize) {
     // Push -> pull
     // Line -> inverted line (for non-verticals)
     for (i,mut elem) in elements.iter_mut().enumerate() {
@@ -292,7 +270,7 @@ fn invert_figure_elements(elements: &mut Vec<Element>, from_idx: usize) {
             }
         match elem.elem_type {
             ElementType::Radius => {
-                if elem.argument >= 0.0 {
+                if elem.argument >= 0 {
                     elem.angle = -elem.angle;
                     }
                 }
@@ -300,7 +278,7 @@ fn invert_figure_elements(elements: &mut Vec<Element>, from_idx: usize) {
                 elem.inverted = !elem.inverted;
                 }
             ElementType::Line => {
-                if !( (elem.angle == 90.0) || (elem.angle == -90.0) ) {
+                if !elem.angle.is_vertical() {
                     elem.inverted = !elem.inverted;
                     }
                 }
@@ -309,88 +287,30 @@ fn invert_figure_elements(elements: &mut Vec<Element>, from_idx: usize) {
         }
     }
 
-fn parse_figure(olan_figure: Pair<Rule>) -> Figure {
-    let mut figure = Figure::new();
-
-    let mut inner_pairs = olan_figure.into_inner();
-
-    let mut current_pair = inner_pairs.next().unwrap();
-
-    // If has initial inter_line_extension, check for inverted entry
-    let mut entry_is_inverted = false;
-    if current_pair.as_rule() == Rule::inter_line_extension {
-        entry_is_inverted = is_transition_inverted(&current_pair);
-        // Advance current_pair
-        current_pair = inner_pairs.next().unwrap();
-        }
-
-    // Add entry line
-    if entry_is_inverted {
-        figure.push( Element::invline(0.0) );
-        }
-    else {
-        figure.push( Element::line(0.0) );
-        }
-    
-    let mut figure_elements = match current_pair.as_rule() {
-        Rule::named_figure   => get_elements_for_named_figure(current_pair),
-        Rule::rolling_figure => get_elements_for_rolling_figure(current_pair),
-        Rule::rolling_turn   => figure_defs::get_elements_for_rolling_turn(current_pair),
-        _ => unreachable!()
-        };
-
-    // If has trailing inter_line_extension, check for inverted exit
-    let mut exit_is_inverted = false;
-    if let Some(current_pair) = inner_pairs.next() {
-        // Will be inter_line_extension by parser rules
-        exit_is_inverted = is_transition_inverted(&current_pair);
-        }
-
-
-    log::debug!("PIV: {:#?}",figure_elements);
+/// Apply the inversions to the library elements of a figure, between its entry and exit lines:
+///  radii after rolls that leave the aircraft inverted become pushes, lines are marked inverted where the aircraft is,
+///  and everything is inverted for an inverted entry. With a written exit, a figure ending the other way up has its
+///  last invertible radius and everything after it flipped. Returns whether the figure ends inverted and any exit correction
+pub(crate) fn apply_inversions(figure_elements: &mut [OlanElement], entry_is_inverted: bool, exit_is_inverted: Option<bool>) -> (bool, Option<ExitCorrection>) {
     // Apply inversions due to in-figure rolls
     let mut currently_inverted = false;
     let mut inverted_by_roll = false;
-    let mut current_pitch = 0.0;
-    for mut element in figure_elements.iter_mut() {
+    let mut current_pitch = Degrees(0);
+    for element in figure_elements.iter_mut() {
         match element.elem_type {
             ElementType::Radius => {
-                if inverted_by_roll == true && element.argument >= 0.0 {
+                if inverted_by_roll && element.argument >= 0 {
                     element.angle = -element.angle
                     }
-                current_pitch = (current_pitch + element.angle + 360.0) % 360.0;
-                if 90.0 < current_pitch && current_pitch < 270.0 {
-                    currently_inverted = true;
-                    }
-                else {
-                    currently_inverted = false;
-                    }
+                current_pitch = (current_pitch + element.angle).normalised();
+                currently_inverted = current_pitch.is_inverted();
                 }
             ElementType::Roll | ElementType::Flick => {
-                match current_pitch {
-                    90.0 | 270.0 => {},
-                    0.0 | 180.0 => {
-                        if (element.angle % 360.0) != 0.0 {
-                            inverted_by_roll = !inverted_by_roll;
-                            currently_inverted = !currently_inverted;
-                            current_pitch = (current_pitch + 180.0 + 360.0) % 360.0;
-                            }
-                        },
-                    45.0 | 225.0 => {
-                        if (element.angle % 360.0) != 0.0 {
-                            inverted_by_roll = !inverted_by_roll;
-                            currently_inverted = !currently_inverted;
-                            current_pitch = (current_pitch + 90.0 + 360.0) % 360.0;
-                            }
-                        }
-                    135.0 | 315.0 => {
-                        if (element.angle % 360.0) != 0.0 {
-                            inverted_by_roll = !inverted_by_roll;
-                            currently_inverted = !currently_inverted;
-                            current_pitch = (current_pitch - 90.0 + 360.0) % 360.0;
-                            }
-                        }
-                    _ => { unreachable!(); }
+                // Rolling upside down on any other line mirrors it about the vertical, e.g. a 45° up line becomes 135°
+                if !current_pitch.is_vertical() && element.angle.ends_inverted() {
+                    inverted_by_roll = !inverted_by_roll;
+                    currently_inverted = !currently_inverted;
+                    current_pitch = (Degrees(180) - current_pitch).normalised();
                     }
                 }
             ElementType::Line => {
@@ -398,11 +318,11 @@ fn parse_figure(olan_figure: Pair<Rule>) -> Figure {
                 }
             ElementType::Stall => {
                 // Indicate pitch reversal through stalling figure
-                current_pitch = (current_pitch + 180.0 + 360.0) % 360.0;
+                current_pitch = (current_pitch + Degrees(180)).normalised();
                 }
             _ => {}
             }
-        log::debug!("Pitch: {}", current_pitch);
+        log::debug!("Pitch: {:?}", current_pitch);
         }
 
     log::debug!("PFR: {:#?}",figure_elements);
@@ -411,12 +331,16 @@ fn parse_figure(olan_figure: Pair<Rule>) -> Figure {
         //log::debug!("Entry is inverted");
         // Figure library assumes no inversion
         // Invert all figure elements
-        invert_figure_elements(&mut figure_elements,0);
+        invert_figure_elements(figure_elements,0);
         currently_inverted = !currently_inverted;
         }
 
     log::debug!("IFR: {:#?}",figure_elements);
     log::debug!("Current_inversion {:?}",currently_inverted);
+    let exit_is_inverted = match exit_is_inverted {
+        Some(exit_is_inverted) => exit_is_inverted,
+        None => return (currently_inverted, None),
+        };
     if currently_inverted != exit_is_inverted {
         log::debug!("Mismatched exit!");
         // Mismatched exit
@@ -425,7 +349,7 @@ fn parse_figure(olan_figure: Pair<Rule>) -> Figure {
         for (i,element) in figure_elements.iter_mut().rev().enumerate() {
             match element.elem_type {
                 ElementType::Radius => {
-                    if element.argument >= 0.0 {
+                    if element.argument >= 0 {
                         rev_idx_invert_from = i;
                         break;
                         }
@@ -433,51 +357,294 @@ fn parse_figure(olan_figure: Pair<Rule>) -> Figure {
                 _ => {}
                 }
             }
-        log::debug!("Inverting from {}",rev_idx_invert_from);
-        let invert_from = figure_elements.len() - 1 - rev_idx_invert_from;
-        invert_figure_elements(&mut figure_elements,invert_from);
+        if rev_idx_invert_from < figure_elements.len() {
+            log::debug!("Inverting from {}",rev_idx_invert_from);
+            let invert_from = figure_elements.len() - 1 - rev_idx_invert_from;
+            invert_figure_elements(figure_elements,invert_from);
+            return (exit_is_inverted, Some(ExitCorrection::RadiusInverted(figure_elements[invert_from].angle.to_f32())));
+            }
+        else {
+            log::debug!("No invertible radius, exit left as written");
+            return (currently_inverted, Some(ExitCorrection::LeftAsWritten));
+            }
         }
+    (currently_inverted, None)
+    }
 
-    figure.append(figure_elements);
+/// Undo `apply_inversions` without an exit correction, giving the library elements of a figure with its rolls
+fn remove_inversions(figure_elements: &mut [OlanElement], entry_is_inverted: bool) {
+    if entry_is_inverted {
+        invert_figure_elements(figure_elements,0);
+        }
+    let mut inverted_by_roll = false;
+    let mut current_pitch = Degrees(0);
+    for element in figure_elements.iter_mut() {
+        match element.elem_type {
+            ElementType::Radius => {
+                current_pitch = (current_pitch + element.angle).normalised();
+                if inverted_by_roll && element.argument >= 0 {
+                    element.angle = -element.angle
+                    }
+                }
+            ElementType::Roll | ElementType::Flick if !current_pitch.is_vertical() && element.angle.ends_inverted() => {
+                inverted_by_roll = !inverted_by_roll;
+                current_pitch = (Degrees(180) - current_pitch).normalised();
+                }
+            ElementType::Line => {
+                element.inverted = false;
+                }
+            ElementType::Stall => {
+                current_pitch = (current_pitch + Degrees(180)).normalised();
+                }
+            _ => {}
+            }
+        }
+    }
+
+/// Flat elements of a figure between its entry and exit lines in library form: as written for an upright entry, without the
+///  radii flipped by rolls. Exit corrections are kept as part of the figure
+pub(crate) fn library_elements(figure_elements: &[FlatElement], entry_is_inverted: bool) -> Vec<FlatElement> {
+    let mut elements: Vec<OlanElement> = figure_elements.iter().map(OlanElement::from_flat).collect();
+    remove_inversions(&mut elements, entry_is_inverted);
+    elements.into_iter().map(OlanElement::to_flat).collect()
+    }
+
+/// Flat elements in library form with the inversions of the rolls and entry applied, as the parser would without an exit
+///  line written. Returns whether the figure ends inverted
+pub(crate) fn inverted_elements(library_elements: &[FlatElement], entry_is_inverted: bool) -> (Vec<FlatElement>, bool) {
+    let mut elements: Vec<OlanElement> = library_elements.iter().map(OlanElement::from_flat).collect();
+    let (exit_is_inverted, _) = apply_inversions(&mut elements, entry_is_inverted, None);
+    (elements.into_iter().map(OlanElement::to_flat).collect(), exit_is_inverted)
+    }
+
+/// Correction the parser made to the exit of a figure whose elements end the other way up to its exit line
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub(crate) enum ExitCorrection {
+    /// The last invertible radius, and everything after it, was flipped. Holds the new radius angle (degrees)
+    RadiusInverted(f32),
+    /// No radius to flip, so the exit line was left as written
+    LeftAsWritten,
+    }
+
+/// Where a figure is written in the OLAN and any correction made to it
+#[derive(Debug)]
+#[derive(Clone)]
+pub(crate) struct FigureSource {
+    pub span: Range<usize>,
+    /// Figure letters, e.g. `io`, for figures from the library
+    pub key: Option<Range<usize>>,
+    pub exit_correction: Option<ExitCorrection>,
+    /// Whether the entry and exit lines are written inverted
+    pub entry_inverted: bool,
+    pub exit_inverted: bool,
+    }
+
+fn parse_figure(olan_figure: Pair<Rule>) -> (Figure, FigureSource) {
+    let mut source = FigureSource {
+        span: olan_figure.as_span().start()..olan_figure.as_span().end(),
+        key: None,
+        exit_correction: None,
+        entry_inverted: false,
+        exit_inverted: false,
+        };
+    let mut figure = Vec::<OlanElement>::new();
+
+    let mut inner_pairs = olan_figure.into_inner();
+
+    let mut current_pair = inner_pairs.next().unwrap();
+
+    // If has initial inter_line_extension, check for inverted entry
+    let mut entry_is_inverted = false;
+    if current_pair.as_rule() == Rule::inter_line_extension {
+        entry_is_inverted = is_transition_inverted(&current_pair);
+        // Advance current_pair
+        current_pair = inner_pairs.next().unwrap();
+        }
+
+    // Add entry line
+    if entry_is_inverted {
+        figure.push( OlanElement::invline(0) );
+        }
+    else {
+        figure.push( OlanElement::line(0) );
+        }
+    
+    if current_pair.as_rule() == Rule::named_figure {
+        source.key = current_pair.clone().into_inner()
+            .find(|pair| pair.as_rule() == Rule::main_figure)
+            .map(|pair| pair.as_span().start()..pair.as_span().end());
+        }
+
+    let mut figure_elements = match current_pair.as_rule() {
+        Rule::named_figure   => get_elements_for_named_figure(current_pair),
+        Rule::rolling_figure => get_elements_for_rolling_figure(current_pair),
+        Rule::rolling_turn   => figure_defs::get_elements_for_rolling_turn(current_pair),
+        _ => unreachable!()
+        };
+
+    // If has trailing inter_line_extension, check for inverted exit
+    let mut exit_is_inverted = false;
+    if let Some(current_pair) = inner_pairs.next() {
+        // Will be inter_line_extension by parser rules
+        exit_is_inverted = is_transition_inverted(&current_pair);
+        }
+
+
+    log::debug!("PIV: {:#?}",figure_elements);
+    let (_, exit_correction) = apply_inversions(&mut figure_elements, entry_is_inverted, Some(exit_is_inverted));
+    source.exit_correction = exit_correction;
+
+    figure.append(&mut figure_elements);
 
     // Add exit line
     if exit_is_inverted {
-        figure.push( Element::invline(0.0) );
+        figure.push( OlanElement::invline(0) );
         }
     else {
-        figure.push( Element::line(0.0) );
+        figure.push( OlanElement::line(0) );
         }
 
-    return figure;
+    // Exact angles end here
+    let figure: Vec<FlatElement> = figure.into_iter().map(OlanElement::to_flat).collect();
+    source.entry_inverted = entry_is_inverted;
+    source.exit_inverted = exit_is_inverted;
+    return (Figure::from_flat(&figure), source);
+    }
+
+fn get_xy_offset(offset_pair: Pair<Rule>) -> Result<(i32,i32), String> {
+    let offset = offset_pair.into_inner().next().unwrap().as_str();
+    // The parser guarantees a comma separated pair of integers
+    let (x, y) = offset.split_once(',').unwrap();
+    let parse = |value: &str| value.parse::<i32>().map_err(|_| format!("layout offset out of range: {}", offset));
+    Ok((parse(x)?, parse(y)?))
+    }
+
+fn get_layout_command(layout_pair: Pair<Rule>) -> Result<LayoutCommand, String> {
+    let text = layout_pair.as_str();
+    match layout_pair.into_inner().next() {
+        Some(command_pair) => match command_pair.as_rule() {
+            Rule::move_by => {
+                let (x, y) = get_xy_offset(command_pair)?;
+                Ok(LayoutCommand::MoveBy(x, y))
+                }
+            Rule::curve_to => {
+                let (x, y) = get_xy_offset(command_pair)?;
+                Ok(LayoutCommand::CurveTo(x, y))
+                }
+            Rule::move_forward => {
+                let steps = text.trim_end_matches('>');
+                if steps.is_empty() {
+                    Ok(LayoutCommand::MoveForward(1))
+                    }
+                else {
+                    steps.parse().map(LayoutCommand::MoveForward).map_err(|_| format!("layout step count out of range: {}", text))
+                    }
+                }
+            _ => unreachable!()
+            },
+        None => match text {
+            "eu" => Ok(LayoutCommand::EntryUpwind),
+            "ed" => Ok(LayoutCommand::EntryDownwind),
+            "ej" => Ok(LayoutCommand::EntryTowardsJudges),
+            "eja" => Ok(LayoutCommand::EntryAwayFromJudges),
+            "/" => Ok(LayoutCommand::SwitchYAxis),
+            "//" => Ok(LayoutCommand::SwitchXAxis),
+            _ => unreachable!()
+            },
+        }
     }
 
 pub fn parse_sequence(olan_string: String) -> Sequence {
-    let mut sequence = Sequence::new();
+    try_parse_sequence(olan_string).expect("Failed to parse sequence")
+    }
+
+/// Parse an OLAN string, returning a description of the syntax error if it does not match the grammar
+///  or the grammar stops matching before the end of the string
+pub fn try_parse_sequence(olan_string: String) -> Result<Sequence, String> {
+    let parsed = parse_with_sources(&olan_string).map_err(|error| error.message)?;
+    let rest = olan_string[parsed.end..].trim_end();
+    let unparsed = rest.trim_start();
+    if !unparsed.is_empty() {
+        let position = parsed.end + rest.len() - unparsed.len();
+        return Err(format!("unexpected \"{}\" at position {}", unparsed, position));
+        }
+    Ok(parsed.sequence)
+    }
+
+/// Failure to parse an OLAN string
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub(crate) struct ParseError {
+    /// Byte offset of the error
+    pub position: usize,
+    pub message: String,
+    /// Short form of the message, without the location
+    pub summary: String,
+    }
+
+/// A parsed OLAN string with where each figure was written
+#[derive(Debug)]
+pub(crate) struct ParsedOlan {
+    pub sequence: Sequence,
+    pub sources: Vec<FigureSource>,
+    /// Byte offset of the end of the parsed OLAN. The grammar stops at the first part it cannot match
+    pub end: usize,
+    }
 
-    let olan_sequence = OLANParser::parse(Rule::sequence, &olan_string)
-        .expect("Failed to parse sequence")
+/// Parse an OLAN string, keeping where each figure was written
+pub(crate) fn parse_with_sources(olan_string: &str) -> Result<ParsedOlan, ParseError> {
+    let mut sequence = Sequence::new();
+    let mut sources = Vec::new();
+
+    let olan_sequence = OLANParser::parse(Rule::sequence, olan_string)
+        .map_err(|error| {
+            let position = match error.location {
+                InputLocation::Pos(position) => position,
+                InputLocation::Span((start, _)) => start,
+                };
+            ParseError { position, message: error.to_string(), summary: error.variant.message().to_string() }
+            })?
         .next().unwrap();
+    let end = olan_sequence.as_span().end();
     
     let inner_rules = olan_sequence.into_inner();
 
+    // Layout commands apply to the next figure. Any after the last figure have nothing to apply to
+    let mut layout = Vec::<LayoutCommand>::new();
     for olan_sequence_part in inner_rules {
-        match olan_sequence_part.into_inner().next() {
-            Some(x) => {
-                sequence.push( parse_figure(x) );
+        for current_pair in olan_sequence_part.into_inner() {
+            match current_pair.as_rule() {
+                Rule::layout_command => {
+                    let position = current_pair.as_span().start();
+                    let command = get_layout_command(current_pair)
+                        .map_err(|message| ParseError { position, summary: message.clone(), message })?;
+                    layout.push(command);
+                    }
+                Rule::figure => {
+                    let (mut figure, source) = parse_figure(current_pair);
+                    figure.layout = std::mem::take(&mut layout);
+                    sequence.push(figure);
+                    sources.push(source);
+                    }
+                _ => {
+                    unreachable!();
+                    }
                 }
-            _ => {}
             }
         }
     
     // Remove remaining doubled lines
     for figure in &mut sequence.figures {
-        figure.elements.dedup_by(|a,b| a.elem_type == ElementType::Line && a == b);
+        figure.elements.dedup_by(|a,b| matches!(a, Element::Line { .. }) && a == b);
         }
         
     // With sequence parsed, need to check for:
     // - Inverted flight continuity
     // - Spin entry trimming
 
-    return sequence;
-
+    Ok(ParsedOlan { sequence, sources, end })
     }
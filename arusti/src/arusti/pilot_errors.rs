@@ -0,0 +1,102 @@
+//! Pilot errors that can be injected into a generated flight
+//!
+//! Judging deducts one point for every 5° of deviation. Marks are given in half points, so
+//! downgrades are rounded down to the nearest half point and capped at the full 10 points.
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub enum PilotError {
+    /// Line drifts off heading by the given yaw angle (degrees) over its length
+    OffHeading(f32),
+    /// Line drifts off its intended pitch angle (degrees) over its length, e.g. off the vertical
+    OffPitch(f32),
+    /// Roll is over (+ve) or under (-ve) rotated by the given angle (degrees)
+    RollRotation(f32),
+    /// Each hesitation stops short by the given angle (degrees). The final segment makes up the difference
+    HesitationShort(f32),
+    /// Second half of a radius is flown with the radius scaled by the given factor
+    UnevenRadius(f32),
+    /// The given number of hesitation points are not flown
+    MissingPoints(u8),
+    }
+
+impl PilotError {
+    /// Downgrade (in points) that a judge should award for this error
+    pub fn downgrade(&self) -> f32 {
+        match *self {
+            PilotError::OffHeading(angle)
+            | PilotError::OffPitch(angle)
+            | PilotError::RollRotation(angle)
+            | PilotError::HesitationShort(angle) => angle_downgrade(angle),
+            // One point per 10% mismatch between the two halves of the radius
+            PilotError::UnevenRadius(scale) => clamp_downgrade( ((scale - 1.0).abs() * 20.0).floor() / 2.0 ),
+            // Flying the wrong number of points zeroes the figure
+            PilotError::MissingPoints(count) => if count > 0 { 10.0 } else { 0.0 },
+            }
+        }
+    }
+
+/// One point per 5° deviation, rounded down to the nearest half point
+pub fn angle_downgrade(angle: f32) -> f32 {
+    clamp_downgrade( (angle.abs() / 5.0 * 2.0).floor() / 2.0 )
+    }
+
+fn clamp_downgrade(downgrade: f32) -> f32 {
+    downgrade.min(10.0)
+    }
+
+/// A pilot error to apply to a single element of the sequence
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub struct ErrorSpec {
+    pub figure: usize,
+    pub element: usize,
+    pub error: PilotError,
+    }
+
+/// Set of errors to inject while generating a flight
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Default)]
+pub struct ErrorModel {
+    pub errors: Vec<ErrorSpec>,
+    }
+
+impl ErrorModel {
+    pub fn new() -> ErrorModel {
+        ErrorModel {
+            errors: Vec::new()
+            }
+        }
+
+    pub fn push(&mut self, figure: usize, element: usize, error: PilotError) {
+        self.errors.push(ErrorSpec { figure, element, error });
+        }
+
+    /// Errors to apply to the given element
+    pub fn errors_for(&self, figure: usize, element: usize) -> Vec<PilotError> {
+        self.errors.iter()
+            .filter(|spec| spec.figure == figure && spec.element == element)
+            .map(|spec| spec.error)
+            .collect()
+        }
+    }
+
+/// Record of an error that was flown, along with the downgrade it should earn
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub struct InjectedError {
+    pub figure: usize,
+    pub element: usize,
+    pub error: PilotError,
+    pub downgrade: f32,
+    /// Index of the first DataPoint generated for the element, counted from the generator's first DataPoint
+    pub start_index: usize,
+    /// Index one past the last DataPoint generated for the element
+    pub end_index: usize,
+    }
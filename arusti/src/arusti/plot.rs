@@ -0,0 +1,383 @@
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+extern crate nalgebra as na;
+use na::{Vector2, Vector3};
+
+use crate::box_frame::BoxFrame;
+use crate::data_generation::DataPoint;
+use crate::segmentation::FigureSegment;
+use crate::types::ElementType;
+
+/// Space around the drawing in each view (px)
+const MARGIN: f32 = 30.0;
+/// Position of the title in each view (px)
+const TITLE_OFFSET: f32 = 18.0;
+/// Points along each edge of the box, which curve in the judge's view
+const EDGE_SAMPLES: usize = 50;
+const TRACK_WIDTH: f32 = 2.0;
+const BOX_WIDTH: f32 = 1.0;
+const MARKER_WIDTH: f32 = 1.0;
+const DOT_RADIUS: f32 = 4.0;
+/// Space given to each character of the legend (px)
+const CHARACTER_WIDTH: f32 = 7.0;
+
+/// Colour of flight outside any figure
+const UNSEGMENTED_COLOUR: Colour = [190, 190, 190];
+const BOX_COLOUR: Colour = [120, 120, 120];
+const MARKER_COLOUR: Colour = [0, 0, 0];
+
+/// Red, green and blue
+pub type Colour = [u8; 3];
+
+/// Colour of the track while flying an element
+pub fn element_colour(elem_type: ElementType) -> Colour {
+    match elem_type {
+        ElementType::Line => [60, 60, 60],
+        ElementType::Radius => [31, 119, 180],
+        ElementType::Turn => [44, 160, 44],
+        ElementType::Roll => [214, 39, 40],
+        ElementType::Flick => [255, 127, 14],
+        ElementType::Spin => [148, 103, 189],
+        ElementType::Stall => [140, 86, 75],
+        ElementType::Combining => [23, 190, 207],
+        }
+    }
+
+/// View of the flight
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum Projection {
+    /// Looking along the Y axis from the judges' side, with A on the left
+    Side,
+    /// Looking down with the judges at the bottom
+    Top,
+    /// Azimuth and elevation from the judges' position
+    Judge,
+    }
+
+impl FromStr for Projection {
+    type Err = String;
+
+    fn from_str(projection: &str) -> Result<Projection, String> {
+        match projection.to_lowercase().as_str() {
+            "side" => Ok(Projection::Side),
+            "top" => Ok(Projection::Top),
+            "judge" => Ok(Projection::Judge),
+            _ => Err(format!("Unknown projection: {}", projection)),
+            }
+        }
+    }
+
+impl Projection {
+    fn title(&self) -> &'static str {
+        match self {
+            Projection::Side => "Side",
+            Projection::Top => "Top",
+            Projection::Judge => "Judge",
+            }
+        }
+
+    /// Project box coordinates onto the view with x to the right and y up, in m or degrees for the judge's view
+    fn project(&self, box_frame: &BoxFrame, position: &Vector3<f32>) -> Vector2<f32> {
+        match self {
+            Projection::Side => Vector2::new(position[0], position[2]),
+            Projection::Top => Vector2::new(position[0], position[1]),
+            Projection::Judge => {
+                let offset = position - box_frame.to_box(&box_frame.judge_position());
+                Vector2::new(offset[0].atan2(offset[1]).to_degrees(), offset[2].atan2(offset[0].hypot(offset[1])).to_degrees())
+                },
+            }
+        }
+    }
+
+/// Image format of a plot
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum PlotFormat {
+    Svg,
+    /// Without text. Requires the `png` feature
+    Png,
+    }
+
+impl FromStr for PlotFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<PlotFormat, String> {
+        match format.to_lowercase().as_str() {
+            "svg" => Ok(PlotFormat::Svg),
+            "png" => Ok(PlotFormat::Png),
+            _ => Err(format!("Unknown plot format: {}", format)),
+            }
+        }
+    }
+
+impl PlotFormat {
+    /// Guess the format from the extension of a file
+    pub fn from_path(path: &Path) -> Option<PlotFormat> {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| extension.parse().ok())
+        }
+    }
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct PlotOptions {
+    /// Views to draw, from top to bottom
+    pub projections: Vec<Projection>,
+    pub box_frame: BoxFrame,
+    /// Draw the outline of the box
+    pub show_box: bool,
+    /// Size of each view (px)
+    pub width: u32,
+    pub height: u32,
+    /// Time between wingtip markers (s). Zero for none
+    pub wingtip_interval: f32,
+    /// Span of the wingtip markers, larger than the aircraft so they show at the scale of the box (m)
+    pub wingtip_span: f32,
+    }
+
+impl Default for PlotOptions {
+    fn default() -> PlotOptions {
+        PlotOptions {
+            projections: vec![Projection::Side, Projection::Top, Projection::Judge],
+            box_frame: BoxFrame::default(),
+            show_box: true,
+            width: 800,
+            height: 400,
+            wingtip_interval: 1.0,
+            wingtip_span: 20.0,
+            }
+        }
+    }
+
+impl PlotOptions {
+    /// Size of the whole image (px)
+    pub fn image_size(&self) -> (u32, u32) {
+        (self.width, self.height * self.projections.len() as u32)
+        }
+    }
+
+/// Part of a plot, in pixels from the top left of the image
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum Mark {
+    /// Part of the track, an edge of the box or a wingtip marker
+    Line { points: Vec<Vector2<f32>>, colour: Colour, width: f32 },
+    /// Start or end of a figure
+    Dot { centre: Vector2<f32>, radius: f32, colour: Colour },
+    /// Titles, figure numbers and the legend. Only drawn in SVG
+    Text { position: Vector2<f32>, text: String, colour: Colour },
+    }
+
+/// Edges of the box in box coordinates
+fn box_edges(box_frame: &BoxFrame) -> Vec<Vec<Vector3<f32>>> {
+    let corner = |i: usize| Vector3::new(
+        if i & 1 == 0 { -box_frame.length / 2.0 } else { box_frame.length / 2.0 },
+        if i & 2 == 0 { -box_frame.width / 2.0 } else { box_frame.width / 2.0 },
+        if i & 4 == 0 { box_frame.bottom } else { box_frame.top },
+        );
+    let mut edges = Vec::new();
+    for start in 0..8 {
+        for axis in [1, 2, 4] {
+            if start & axis == 0 {
+                let (from, to) = (corner(start), corner(start | axis));
+                edges.push((0..=EDGE_SAMPLES).map(|i| from.lerp(&to, i as f32 / EDGE_SAMPLES as f32)).collect());
+                }
+            }
+        }
+    edges
+    }
+
+/// Wingtip to wingtip line and a tick towards the canopy at intervals along the flight, in box coordinates
+fn wingtip_markers(points: &[DataPoint], options: &PlotOptions) -> Vec<Vec<Vector3<f32>>> {
+    let mut markers = Vec::new();
+    if options.wingtip_interval <= 0.0 || points.is_empty() {
+        return markers;
+        }
+    let box_frame = &options.box_frame;
+    let half_span = options.wingtip_span / 2.0;
+    let mut next_time = points[0].time;
+    for point in points {
+        if point.time < next_time {
+            continue;
+            }
+        while next_time <= point.time {
+            next_time += options.wingtip_interval;
+            }
+        let wing = point.attitude * Vector3::new(0.0, half_span, 0.0);
+        let canopy = point.attitude * Vector3::new(0.0, 0.0, -half_span / 2.0);
+        markers.push(vec![box_frame.to_box(&(point.position - wing)), box_frame.to_box(&(point.position + wing))]);
+        markers.push(vec![box_frame.to_box(&point.position), box_frame.to_box(&(point.position + canopy))]);
+        }
+    markers
+    }
+
+/// Draw the views of a flight with the track coloured by the element being flown
+pub fn plot(points: &[DataPoint], figures: &[FigureSegment], options: &PlotOptions) -> Vec<Mark> {
+    let box_frame = &options.box_frame;
+    let track: Vec<Vector3<f32>> = points.iter().map(|point| box_frame.to_box(&point.position)).collect();
+    let mut colours = vec![UNSEGMENTED_COLOUR; points.len()];
+    for segment in figures.iter().flat_map(|figure| &figure.elements) {
+        for colour in colours.iter_mut().take(segment.end).skip(segment.start) {
+            *colour = element_colour(segment.elem_type);
+            }
+        }
+    let edges = if options.show_box { box_edges(box_frame) } else { Vec::new() };
+    let markers = wingtip_markers(points, options);
+
+    let mut marks = Vec::new();
+    let (width, height) = (options.width as f32, options.height as f32);
+    for (view, projection) in options.projections.iter().enumerate() {
+        let project = |position: &Vector3<f32>| projection.project(box_frame, position);
+
+        // Fit everything drawn into the view, keeping the aspect ratio
+        let (min, max) = track.iter().chain(edges.iter().flatten()).chain(markers.iter().flatten())
+            .map(project)
+            .fold((Vector2::repeat(f32::INFINITY), Vector2::repeat(f32::NEG_INFINITY)), |(min, max), point| (min.inf(&point), max.sup(&point)));
+        let size = (max - min).sup(&Vector2::repeat(1.0));
+        let scale = ((width - 2.0 * MARGIN) / size[0]).min((height - 2.0 * MARGIN) / size[1]);
+        let middle = (min + max) / 2.0;
+        let centre = Vector2::new(width / 2.0, height * (view as f32 + 0.5));
+        let to_pixels = |position: &Vector3<f32>| {
+            let offset = (project(position) - middle) * scale;
+            centre + Vector2::new(offset[0], -offset[1])
+            };
+
+        for edge in &edges {
+            marks.push(Mark::Line { points: edge.iter().map(to_pixels).collect(), colour: BOX_COLOUR, width: BOX_WIDTH });
+            }
+
+        // One line for each run of points with the same colour, joined to the next run
+        let mut start = 0;
+        for end in 1..=track.len() {
+            if end == track.len() || colours[end] != colours[start] {
+                let last = end.min(track.len() - 1);
+                marks.push(Mark::Line { points: track[start..=last].iter().map(to_pixels).collect(), colour: colours[start], width: TRACK_WIDTH });
+                start = end;
+                }
+            }
+
+        for marker in &markers {
+            marks.push(Mark::Line { points: marker.iter().map(to_pixels).collect(), colour: MARKER_COLOUR, width: MARKER_WIDTH });
+            }
+
+        for (i, figure) in figures.iter().enumerate() {
+            let start = to_pixels(&track[figure.start]);
+            marks.push(Mark::Dot { centre: start, radius: DOT_RADIUS, colour: MARKER_COLOUR });
+            marks.push(Mark::Dot { centre: to_pixels(&track[figure.end - 1]), radius: DOT_RADIUS / 2.0, colour: MARKER_COLOUR });
+            marks.push(Mark::Text { position: start + Vector2::new(DOT_RADIUS, -DOT_RADIUS), text: (i + 1).to_string(), colour: MARKER_COLOUR });
+            }
+
+        let top = height * view as f32;
+        marks.push(Mark::Text { position: Vector2::new(MARGIN / 2.0, top + TITLE_OFFSET), text: projection.title().to_string(), colour: MARKER_COLOUR });
+        }
+
+    // Legend of element colours across the top right
+    let elem_types = [ElementType::Line, ElementType::Radius, ElementType::Turn, ElementType::Roll, ElementType::Flick, ElementType::Spin, ElementType::Stall];
+    let mut x = width - MARGIN / 2.0 - elem_types.iter().map(|elem_type| (format!("{:?}", elem_type).len() + 1) as f32 * CHARACTER_WIDTH).sum::<f32>();
+    for elem_type in elem_types {
+        let text = format!("{:?}", elem_type);
+        let advance = (text.len() + 1) as f32 * CHARACTER_WIDTH;
+        marks.push(Mark::Text { position: Vector2::new(x, TITLE_OFFSET), text, colour: element_colour(elem_type) });
+        x += advance;
+        }
+    marks
+    }
+
+fn format_colour(colour: &Colour) -> String {
+    format!("#{:02x}{:02x}{:02x}", colour[0], colour[1], colour[2])
+    }
+
+/// Write the views of a flight as SVG
+pub fn write_svg<W: Write>(points: &[DataPoint], figures: &[FigureSegment], options: &PlotOptions, mut writer: W) -> io::Result<()> {
+    let (width, height) = options.image_size();
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#, width, height, width, height)?;
+    writeln!(writer, r#"<rect width="100%" height="100%" fill="white"/>"#)?;
+    writeln!(writer, r#"<g fill="none" stroke-linecap="round" stroke-linejoin="round" font-family="sans-serif" font-size="12">"#)?;
+    for mark in plot(points, figures, options) {
+        match mark {
+            Mark::Line { points, colour, width } => {
+                let points: Vec<String> = points.iter().map(|point| format!("{:.1},{:.1}", point[0], point[1])).collect();
+                writeln!(writer, r#"<polyline points="{}" stroke="{}" stroke-width="{}"/>"#, points.join(" "), format_colour(&colour), width)?;
+                },
+            Mark::Dot { centre, radius, colour } => {
+                writeln!(writer, r#"<circle cx="{:.1}" cy="{:.1}" r="{}" fill="{}"/>"#, centre[0], centre[1], radius, format_colour(&colour))?;
+                },
+            Mark::Text { position, text, colour } => {
+                writeln!(writer, r#"<text x="{:.1}" y="{:.1}" fill="{}">{}</text>"#, position[0], position[1], format_colour(&colour), text)?;
+                },
+            }
+        }
+    writeln!(writer, "</g>")?;
+    writeln!(writer, "</svg>")?;
+    Ok(())
+    }
+
+/// Draw the marks onto a white RGB image, skipping text
+#[cfg(feature = "png")]
+fn rasterise(marks: &[Mark], width: usize, height: usize) -> Vec<u8> {
+    let mut pixels = vec![255; width * height * 3];
+    let mut fill_circle = |centre: Vector2<f32>, radius: f32, colour: &Colour| {
+        let radius = radius.max(0.75);
+        let x_range = ((centre[0] - radius).floor().max(0.0) as usize)..((centre[0] + radius).ceil().max(0.0) as usize).min(width);
+        let y_range = ((centre[1] - radius).floor().max(0.0) as usize)..((centre[1] + radius).ceil().max(0.0) as usize).min(height);
+        for y in y_range {
+            for x in x_range.clone() {
+                if (Vector2::new(x as f32 + 0.5, y as f32 + 0.5) - centre).norm() <= radius {
+                    let i = (y * width + x) * 3;
+                    pixels[i..i + 3].copy_from_slice(colour);
+                    }
+                }
+            }
+        };
+    for mark in marks {
+        match mark {
+            Mark::Line { points, colour, width } => {
+                for pair in points.windows(2) {
+                    let steps = ((pair[1] - pair[0]).norm() * 2.0).ceil().max(1.0) as usize;
+                    for step in 0..=steps {
+                        fill_circle(pair[0].lerp(&pair[1], step as f32 / steps as f32), width / 2.0, colour);
+                        }
+                    }
+                },
+            Mark::Dot { centre, radius, colour } => fill_circle(*centre, *radius, colour),
+            Mark::Text { .. } => {},
+            }
+        }
+    pixels
+    }
+
+/// Write the views of a flight as PNG, without text
+#[cfg(feature = "png")]
+pub fn write_png<W: Write>(points: &[DataPoint], figures: &[FigureSegment], options: &PlotOptions, writer: W) -> io::Result<()> {
+    let (width, height) = options.image_size();
+    let pixels = rasterise(&plot(points, figures, options), width as usize, height as usize);
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut png_writer = encoder.write_header().map_err(io::Error::other)?;
+    png_writer.write_image_data(&pixels).map_err(io::Error::other)?;
+    png_writer.finish().map_err(io::Error::other)
+    }
+
+#[cfg(not(feature = "png"))]
+pub fn write_png<W: Write>(_points: &[DataPoint], _figures: &[FigureSegment], _options: &PlotOptions, _writer: W) -> io::Result<()> {
+    Err(io::Error::other("PNG output requires the `png` feature"))
+    }
+
+pub fn write_plot<W: Write>(format: PlotFormat, points: &[DataPoint], figures: &[FigureSegment], options: &PlotOptions, writer: W) -> io::Result<()> {
+    match format {
+        PlotFormat::Svg => write_svg(points, figures, options, writer),
+        PlotFormat::Png => write_png(points, figures, options, writer),
+        }
+    }
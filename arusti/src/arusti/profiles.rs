@@ -0,0 +1,189 @@
+use std::fs;
+use std::path::Path;
+
+extern crate nalgebra as na;
+use na::Vector3;
+
+use serde::{Deserialize, Serialize};
+
+use crate::data_generation::{DataPointGeneratorBuilder, PerformanceOptions};
+
+/// Standard gravity (m/s^2)
+const GRAVITY: f32 = 9.80665;
+
+/// Profiles shipped with the crate as (name, TOML source)
+pub const BUILTIN_PROFILES: [(&str, &str); 5] = [
+    ("extra330", include_str!("../../profiles/extra330.toml")),
+    ("pitts_s2b", include_str!("../../profiles/pitts_s2b.toml")),
+    ("cap232", include_str!("../../profiles/cap232.toml")),
+    ("swift_s1", include_str!("../../profiles/swift_s1.toml")),
+    ("rc_sport", include_str!("../../profiles/rc_sport.toml")),
+    ];
+
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AircraftCategory {
+    Powered,
+    Glider,
+    RemoteControl,
+    }
+
+/// Airspeeds (m/s)
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Serialize, Deserialize)]
+pub struct Speeds {
+    /// Speed at which figures are flown
+    pub entry: f32,
+    /// Slowest speed the aircraft may be flown at, e.g. a --speed override
+    pub minimum: f32,
+    pub maximum: f32,
+    }
+
+/// Rate limits (deg/s)
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Serialize, Deserialize)]
+pub struct Rates {
+    pub roll: f32,
+    pub pitch: f32,
+    pub turn: f32,
+    pub spin: f32,
+    }
+
+/// Load factor limits (g)
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Serialize, Deserialize)]
+pub struct GLimits {
+    pub positive: f32,
+    pub negative: f32,
+    }
+
+/// How the aircraft enters a snap roll
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Serialize, Deserialize)]
+pub struct SnapEntry {
+    /// Autorotation rate once the snap has broken (deg/s)
+    pub rate: f32,
+    /// Pitch change at the break, relative to the flight path (degrees)
+    pub entry_pitch: f32,
+    /// Time taken to break into autorotation (s)
+    pub entry_time: f32,
+    }
+
+/// Description of an aircraft type for generating flight data
+///
+/// Profiles are read from TOML or JSON with the same structure, see the files in `profiles/`.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Serialize, Deserialize)]
+pub struct AircraftProfile {
+    pub name: String,
+    pub category: AircraftCategory,
+    /// Pause at each hesitation (s)
+    pub hesitation_time: f32,
+    pub speeds: Speeds,
+    pub rates: Rates,
+    pub g_limits: GLimits,
+    pub snap: SnapEntry,
+    }
+
+impl AircraftProfile {
+    pub fn from_toml(source: &str) -> Result<AircraftProfile, String> {
+        let profile: AircraftProfile = toml::from_str(source).map_err(|error| error.to_string())?;
+        profile.validate()?;
+        Ok(profile)
+        }
+
+    pub fn from_json(source: &str) -> Result<AircraftProfile, String> {
+        let profile: AircraftProfile = serde_json::from_str(source).map_err(|error| error.to_string())?;
+        profile.validate()?;
+        Ok(profile)
+        }
+
+    /// Read a profile from a file, as JSON if it has a .json extension and TOML otherwise
+    pub fn load(path: &Path) -> Result<AircraftProfile, String> {
+        let source = fs::read_to_string(path).map_err(|error| format!("cannot read {}: {}", path.display(), error))?;
+        let is_json = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+        let profile = if is_json { AircraftProfile::from_json(&source) } else { AircraftProfile::from_toml(&source) };
+        profile.map_err(|error| format!("{}: {}", path.display(), error))
+        }
+
+    /// Look up one of the BUILTIN_PROFILES by name
+    pub fn builtin(name: &str) -> Option<AircraftProfile> {
+        BUILTIN_PROFILES.iter()
+            .find(|(builtin_name, _)| *builtin_name == name)
+            .map(|(_, source)| AircraftProfile::from_toml(source).expect("Built-in profile is invalid"))
+        }
+
+    pub fn builtin_names() -> Vec<&'static str> {
+        BUILTIN_PROFILES.iter().map(|(name, _)| *name).collect()
+        }
+
+    fn validate(&self) -> Result<(), String> {
+        let rates = [
+            ("rates.roll", self.rates.roll),
+            ("rates.pitch", self.rates.pitch),
+            ("rates.turn", self.rates.turn),
+            ("rates.spin", self.rates.spin),
+            ("snap.rate", self.snap.rate),
+            ("speeds.entry", self.speeds.entry),
+            ];
+        if let Some((field, _)) = rates.iter().find(|(_, value)| value.is_nan() || *value <= 0.0) {
+            return Err(format!("{} must be positive", field));
+            }
+        self.check_speed(self.speeds.entry).map_err(|error| format!("speeds.entry: {}", error))?;
+        if self.g_limits.positive <= 1.0 || self.g_limits.negative >= -1.0 {
+            return Err("g_limits.positive must be above 1 and g_limits.negative below -1".to_string());
+            }
+        if self.hesitation_time < 0.0 || self.snap.entry_time < 0.0 {
+            return Err("times must not be negative".to_string());
+            }
+        Ok(())
+        }
+
+    /// Check that a speed is within the speed range of the aircraft (m/s)
+    pub fn check_speed(&self, speed: f32) -> Result<(), String> {
+        if speed < self.speeds.minimum || speed > self.speeds.maximum {
+            return Err(format!("{} m/s is outside {} to {} m/s", speed, self.speeds.minimum, self.speeds.maximum));
+            }
+        Ok(())
+        }
+
+    /// Pitch rate for looping at the entry speed, limited by the rate limit and the g limits.
+    ///  Pulls and pushes are flown at the same rate, with gravity adding to the load at the bottom of a loop
+    pub fn pitch_rate(&self) -> f32 {
+        let g_limited = ((self.g_limits.positive - 1.0).min(-self.g_limits.negative - 1.0) * GRAVITY / self.speeds.entry).to_degrees();
+        self.rates.pitch.min(g_limited)
+        }
+
+    pub fn performance_options(&self) -> PerformanceOptions {
+        PerformanceOptions {
+            roll_rate: self.rates.roll,
+            snap_rate: self.snap.rate,
+            spin_rate: self.rates.spin,
+            pitch_rate: self.pitch_rate(),
+            turn_rate: self.rates.turn,
+            hesitation_time: self.hesitation_time,
+            snap_entry_pitch: self.snap.entry_pitch,
+            snap_entry_time: self.snap.entry_time,
+            }
+        }
+
+    /// Builder for a generator flying this aircraft at its entry speed
+    pub fn generator_builder(&self) -> DataPointGeneratorBuilder {
+        DataPointGeneratorBuilder::new(self.performance_options())
+            .velocity(Vector3::x() * self.speeds.entry)
+        }
+    }
@@ -0,0 +1,343 @@
+use std::cmp::Ordering;
+use std::sync::OnceLock;
+
+use crate::types::Element;
+use crate::data_generation::DataPoint;
+use crate::fitting::{self, ElementFit};
+use crate::olan::{self, FIGURE_KEYS};
+use crate::segmentation::{self, SegmentationOptions};
+
+/// Angle error that costs as much as a missing element (degrees)
+const ANGLE_SCALE: f32 = 45.0;
+/// Cost of a line that was flown but not expected, or expected but not flown
+const LINE_GAP_COST: f32 = 0.25;
+/// Cost of a roll flown with the wrong number of hesitations
+const DIVISIONS_COST: f32 = 0.5;
+/// Cost of flying a line the wrong way up, or a snap in place of a roll
+const MISMATCH_COST: f32 = 1.0;
+
+/// OLAN figure that may have been flown
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Candidate {
+    /// OLAN for the figure, including the inverted entry and exit markers
+    pub olan: String,
+    /// Elements of the parsed OLAN, with consecutive radii and lines merged
+    pub elements: Vec<Element>,
+    /// Mismatch between the flown and parsed elements, 0 being an exact match
+    pub cost: f32,
+    /// Likelihood relative to the other candidates for the figure. Likelihoods sum to 1
+    pub likelihood: f32,
+    }
+
+/// Drop combining markers and merge radii pulled the same way, and lines at the same angle
+pub(crate) fn normalise(elements: &[Element]) -> Vec<Element> {
+    let mut merged: Vec<Element> = Vec::new();
+    for element in elements {
+        match (merged.last_mut(), *element) {
+            (_, Element::Combining(_)) => {}
+            (Some(Element::Radius { angle: last, .. }), Element::Radius { angle, .. }) if *last * angle > 0.0 => *last += angle,
+            (Some(Element::Line { pitch: last, .. }), Element::Line { pitch, .. }) if (*last - pitch).abs() < 10.0 => {}
+            _ => merged.push(*element),
+            }
+        }
+    merged
+    }
+
+/// Cost of matching a flown element to an expected one, or None if they cannot match
+fn element_cost(expected: &Element, flown: &Element) -> Option<f32> {
+    match (*expected, *flown) {
+        (Element::Line { pitch: expected_pitch, inverted: expected_inverted }, Element::Line { pitch, inverted }) => {
+            // Vertical lines have no upright or inverted sense
+            let inversion = if expected_pitch.abs() < 60.0 && expected_inverted != inverted { MISMATCH_COST } else { 0.0 };
+            Some((expected_pitch - pitch).abs() / ANGLE_SCALE + inversion)
+            }
+        (Element::Radius { angle: expected_angle, .. }, Element::Radius { angle, .. }) => Some((expected_angle - angle).abs() / ANGLE_SCALE),
+        (Element::Turn { angle: expected_angle, .. }, Element::Turn { angle, .. }) => {
+            // OLAN does not give the turn direction, only whether the roll is towards the inside
+            Some(((expected_angle.abs() - angle.abs()).abs() + (expected.turn_roll() - flown.turn_roll()).abs()) / ANGLE_SCALE)
+            }
+        _ if expected.is_rolling() && flown.is_rolling() => {
+            // OLAN does not give the direction of the first roll
+            let mut cost = (expected.rolling_angle().abs() - flown.rolling_angle().abs()).abs() / ANGLE_SCALE;
+            match (*expected, *flown) {
+                (Element::Roll { divisions: expected_divisions, .. }, Element::Roll { divisions, .. }) if expected_divisions != divisions => {
+                    cost += DIVISIONS_COST;
+                    },
+                _ if expected.elem_type() != flown.elem_type() => cost += MISMATCH_COST,
+                _ => {}
+                }
+            Some(cost)
+            }
+        _ => None,
+        }
+    }
+
+/// Cost of an element that appears in only one of the chains
+fn gap_cost(element: &Element) -> f32 {
+    let angle = match *element {
+        Element::Line { .. } => return LINE_GAP_COST,
+        Element::Radius { angle, .. } | Element::Turn { angle, .. } => angle,
+        Element::Stall { yaw, .. } => yaw,
+        Element::Combining(_) => 0.0,
+        _ => element.rolling_angle(),
+        };
+    1.0 + angle.abs() / ANGLE_SCALE
+    }
+
+/// Pairs of expected and flown element indices. Elements that appear in only one chain are paired with None
+pub type Alignment = Vec<(Option<usize>, Option<usize>)>;
+
+/// Best alignment of the flown elements with the expected ones, with its cost
+pub fn align(expected: &[Element], flown: &[Element]) -> (f32, Alignment) {
+    // Edit distance, with substitutions costed by the element mismatch
+    let mut costs = vec![vec![0.0; flown.len() + 1]; expected.len() + 1];
+    for (j, flown_element) in flown.iter().enumerate() {
+        costs[0][j + 1] = costs[0][j] + gap_cost(flown_element);
+        }
+    for (i, expected_element) in expected.iter().enumerate() {
+        costs[i + 1][0] = costs[i][0] + gap_cost(expected_element);
+        for (j, flown_element) in flown.iter().enumerate() {
+            let mut cost = (costs[i][j + 1] + gap_cost(expected_element)).min(costs[i + 1][j] + gap_cost(flown_element));
+            if let Some(substitution) = element_cost(expected_element, flown_element) {
+                cost = cost.min(costs[i][j] + substitution);
+                }
+            costs[i + 1][j + 1] = cost;
+            }
+        }
+
+    // Trace the cheapest path back from the end of both chains
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (expected.len(), flown.len());
+    while i > 0 || j > 0 {
+        let substituted = i > 0 && j > 0 && element_cost(&expected[i - 1], &flown[j - 1])
+            .is_some_and(|substitution| (costs[i - 1][j - 1] + substitution - costs[i][j]).abs() < 1e-4);
+        if substituted {
+            pairs.push((Some(i - 1), Some(j - 1)));
+            i -= 1;
+            j -= 1;
+            }
+        else if i > 0 && (j == 0 || (costs[i - 1][j] + gap_cost(&expected[i - 1]) - costs[i][j]).abs() < 1e-4) {
+            pairs.push((Some(i - 1), None));
+            i -= 1;
+            }
+        else {
+            pairs.push((None, Some(j - 1)));
+            j -= 1;
+            }
+        }
+    pairs.reverse();
+    (costs[expected.len()][flown.len()], pairs)
+    }
+
+/// Cost of the best alignment of the flown elements with the expected ones
+pub fn chain_cost(expected: &[Element], flown: &[Element]) -> f32 {
+    align(expected, flown).0
+    }
+
+/// OLAN for a single roll, snap or spin, or None if it has no notation
+fn roll_notation(element: &Element) -> Option<String> {
+    let eighths = (element.rolling_angle().abs() / 45.0).round() as i32;
+    let divisions = match *element {
+        Element::Roll { divisions, .. } => divisions as i32,
+        _ => 1,
+        };
+    if [2, 4, 8].contains(&divisions) && eighths * divisions % 8 == 0 {
+        let digit = eighths * divisions / 8;
+        let valid = match divisions {
+            2 => (2..=4).contains(&digit),
+            4 => (2..=8).contains(&digit),
+            _ => digit % 2 == 0 && (2..=8).contains(&digit),
+            };
+        if valid {
+            return Some(format!("{}{}", digit, divisions));
+            }
+        }
+
+    if eighths % 2 != 0 {
+        return None;
+        }
+    let digit = match eighths / 2 {
+        1 => 4,
+        2 => 2,
+        3 => 3,
+        4 => 1,
+        5 => 5,
+        6 => 6,
+        7 => 7,
+        8 => 9,
+        _ => return None,
+        };
+    Some(match element {
+        Element::Flick { .. } => format!("{}f", digit),
+        Element::Spin { .. } => format!("{}s", digit),
+        _ => digit.to_string(),
+        })
+    }
+
+/// OLAN for consecutive rotations on a line. A comma separates rolls in opposite directions
+pub(crate) fn roll_set_notation(rolls: &[Element]) -> Option<String> {
+    let mut notation = String::new();
+    for (i, roll) in rolls.iter().enumerate() {
+        if i > 0 {
+            notation.push(if roll.rolling_angle() * rolls[i - 1].rolling_angle() < 0.0 { ',' } else { ';' });
+            }
+        notation.push_str(&roll_notation(roll)?);
+        }
+    Some(notation)
+    }
+
+/// Rotations grouped by the line they are flown on
+fn roll_groups(elements: &[Element]) -> Vec<Vec<Element>> {
+    let mut groups = Vec::new();
+    let mut current = Vec::new();
+    for element in elements {
+        if element.is_rolling() {
+            current.push(*element);
+            }
+        else if !matches!(element, Element::Line { .. }) && !current.is_empty() {
+            groups.push(std::mem::take(&mut current));
+            }
+        }
+    if !current.is_empty() {
+        groups.push(current);
+        }
+    groups
+    }
+
+/// Ways of placing the roll sets, in order, into the before, two inner and after positions of a figure
+fn roll_placements(roll_sets: &[String]) -> Vec<[Option<&String>; 4]> {
+    let mut placements = Vec::new();
+    if roll_sets.len() > 4 {
+        return placements;
+        }
+    for mask in 0..16u32 {
+        if mask.count_ones() as usize != roll_sets.len() {
+            continue;
+            }
+        let mut placement = [None; 4];
+        let mut sets = roll_sets.iter();
+        for (slot, position) in placement.iter_mut().enumerate() {
+            if mask & (1 << slot) != 0 {
+                *position = sets.next();
+                }
+            }
+        placements.push(placement);
+        }
+    placements
+    }
+
+/// OLAN for rolling turns matching a flown turn
+fn turn_notations(angle: f32, rolls: f32) -> Vec<String> {
+    let quarters = (angle.abs() / 90.0).round().clamp(1.0, 4.0) as i32;
+    let half_rolls = (rolls * 2.0).round() as i32;
+    match half_rolls {
+        0 => vec![format!("{}j", quarters)],
+        3 => vec![format!("{}j15", quarters), format!("{}jo15", quarters)],
+        n if n % 2 == 0 && n <= 8 => vec![format!("{}j{}", quarters, n / 2), format!("{}jo{}", quarters, n / 2)],
+        _ => Vec::new(),
+        }
+    }
+
+/// Figure keys that the grammar can reach. Some keys parse as a line, so cannot be flown
+fn reachable_keys() -> &'static [&'static str] {
+    static KEYS: OnceLock<Vec<&'static str>> = OnceLock::new();
+    KEYS.get_or_init(|| FIGURE_KEYS.iter()
+        .filter(|key| olan::try_parse_sequence(key.to_string()).is_ok_and(|sequence| sequence.figures.iter().any(|figure| figure.elements.len() > 2)))
+        .copied()
+        .collect())
+    }
+
+/// OLAN strings, without entry or exit markers, for figures that could match the flown elements
+fn candidate_figures(flown: &[Element]) -> Vec<String> {
+    let mut figures = Vec::new();
+    let groups = roll_groups(flown);
+    let roll_sets: Option<Vec<String>> = groups.iter().map(|group| roll_set_notation(group)).collect();
+    let roll_sets = roll_sets.unwrap_or_default();
+
+    for key in reachable_keys() {
+        for placement in roll_placements(&roll_sets) {
+            let mut figure = String::new();
+            if let Some(before) = placement[0] {
+                figure.push_str(before);
+                }
+            figure.push_str(key);
+            for inner in placement[1..3].iter().flatten() {
+                figure.push_str(&format!("({})", inner));
+                }
+            if let Some(after) = placement[3] {
+                figure.push_str(after);
+                }
+            figures.push(figure);
+            }
+        }
+
+    // All of the rolls as a single rolling figure
+    let rolls: Vec<Element> = groups.concat();
+    if let Some(roll_set) = roll_set_notation(&rolls).filter(|roll_set| !roll_set.is_empty()) {
+        figures.push(roll_set);
+        }
+
+    for element in flown {
+        if let Element::Turn { angle, rolls, .. } = *element {
+            figures.append(&mut turn_notations(angle, rolls));
+            }
+        }
+    figures
+    }
+
+/// Rank the figures that could have been flown, from most to least likely
+pub fn recognise_figure(fits: &[ElementFit]) -> Vec<Candidate> {
+    let flown = normalise(&fits.iter().map(|fit| fit.element).collect::<Vec<Element>>());
+    let (first, last) = match (flown.first(), flown.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Vec::new(),
+        };
+    let entry = if first.inverted() { "-" } else { "" };
+    let exit = if last.inverted() { "-" } else { "" };
+
+    let mut candidates: Vec<Candidate> = candidate_figures(&flown).into_iter()
+        .filter_map(|figure| {
+            let olan = format!("{}{}{}", entry, figure, exit);
+            let mut sequence = olan::try_parse_sequence(olan.clone()).ok()?;
+            if sequence.figures.len() != 1 {
+                return None;
+                }
+            let elements = normalise(&sequence.figures.remove(0).elements);
+            let cost = chain_cost(&elements, &flown);
+            Some(Candidate { olan, elements, cost, likelihood: 0.0 })
+            })
+        .collect();
+
+    // Prefer the simpler OLAN when candidates match equally well
+    candidates.sort_by(|a, b| a.cost.partial_cmp(&b.cost).unwrap_or(Ordering::Equal).then(a.olan.len().cmp(&b.olan.len())).then(a.olan.cmp(&b.olan)));
+    candidates.dedup_by(|a, b| a.olan == b.olan);
+
+    let best_cost = candidates.first().map_or(0.0, |candidate| candidate.cost);
+    let total: f32 = candidates.iter().map(|candidate| (best_cost - candidate.cost).exp()).sum();
+    for candidate in &mut candidates {
+        candidate.likelihood = (best_cost - candidate.cost).exp() / total;
+        }
+    candidates
+    }
+
+/// Segment and fit a flight, ranking the figures that could have been flown for each figure found
+pub fn recognise_flight(points: &[DataPoint], options: &SegmentationOptions) -> Vec<Vec<Candidate>> {
+    segmentation::segment_figures(points, options).iter()
+        .map(|figure| {
+            let fits: Vec<ElementFit> = figure.elements.iter()
+                .filter_map(|segment| fitting::fit_segment(points, segment, options))
+                .collect();
+            recognise_figure(&fits)
+            })
+        .collect()
+    }
+
+/// Best guess at the OLAN for a flight, from the candidates for each figure
+pub fn best_guess(figures: &[Vec<Candidate>]) -> String {
+    figures.iter()
+        .filter_map(|candidates| candidates.first())
+        .map(|candidate| candidate.olan.as_str())
+        .collect::<Vec<&str>>()
+        .join(" ")
+    }
@@ -0,0 +1,288 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{Element, Sequence};
+use crate::seq_file::{CatalogueFigure, SeqFile};
+
+/// Rule sets shipped with the crate as (name, TOML source)
+pub const BUILTIN_RULES: [(&str, &str); 2] = [
+    ("civa", include_str!("../../rules/civa.toml")),
+    ("baea", include_str!("../../rules/baea.toml")),
+    ];
+
+/// Kind of rolling element
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RollType {
+    /// Continuous roll
+    Aileron,
+    /// Roll with hesitations
+    Hesitation,
+    Flick,
+    Spin,
+    }
+
+impl fmt::Display for RollType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RollType::Aileron => write!(f, "aileron roll"),
+            RollType::Hesitation => write!(f, "hesitation roll"),
+            RollType::Flick => write!(f, "flick roll"),
+            RollType::Spin => write!(f, "spin"),
+            }
+        }
+    }
+
+/// Limit on how many figures use a catalogue family
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Serialize, Deserialize)]
+pub struct FamilyLimit {
+    /// Catalogue number prefix, e.g. "9.11" for all positive spins
+    pub family: String,
+    pub max: usize,
+    }
+
+/// Rules for one programme of one category
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Default)]
+#[derive(PartialEq)]
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProgramRules {
+    pub category: String,
+    pub program: String,
+    pub min_figures: Option<usize>,
+    pub max_figures: Option<usize>,
+    /// Limits on the total K of the sequence
+    pub min_k: Option<f32>,
+    pub max_k: Option<f32>,
+    /// Limit on the K of any one figure
+    pub max_figure_k: Option<f32>,
+    /// Catalogue number prefixes that may be used. Empty allows any
+    pub allowed_families: Vec<String>,
+    /// Catalogue number prefixes that may not be used
+    pub forbidden_families: Vec<String>,
+    /// Number of times the same base figure may be flown
+    pub max_repeats: Option<usize>,
+    pub family_limits: Vec<FamilyLimit>,
+    /// Roll types the sequence must include
+    pub required_rolls: Vec<RollType>,
+    /// Number of rolling elements allowed in one figure
+    pub max_rolls_per_figure: Option<usize>,
+    }
+
+/// A named collection of programme rules, e.g. those of CIVA or a national body
+///
+/// Rule sets are read from TOML or JSON with the same structure as the files in `rules/`.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Serialize, Deserialize)]
+pub struct RuleSet {
+    pub name: String,
+    pub programs: Vec<ProgramRules>,
+    }
+
+/// A broken rule
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum ViolationKind {
+    TooFewFigures { count: usize, min: usize },
+    TooManyFigures { count: usize, max: usize },
+    TotalKTooLow { k: f32, min: f32 },
+    TotalKTooHigh { k: f32, max: f32 },
+    FigureKTooHigh { k: f32, max: f32 },
+    /// Catalogue number outside the allowed families, or in a forbidden one
+    FamilyNotAllowed(String),
+    /// Base figure already flown the maximum number of times
+    Repeated { aresti: String, max: usize },
+    /// Family already used the maximum number of times
+    FamilyLimit { family: String, max: usize },
+    MissingRollType(RollType),
+    TooManyRolls { count: usize, max: usize },
+    /// The parsed sequence and its catalogue data have different numbers of figures
+    CatalogueMismatch { figures: usize, catalogue: usize },
+    }
+
+/// A broken rule, with the index of the figure that breaks it for rules on single figures
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct Violation {
+    pub figure: Option<usize>,
+    pub kind: ViolationKind,
+    }
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(figure) = self.figure {
+            write!(f, "Figure {}: ", figure + 1)?;
+            }
+        match &self.kind {
+            ViolationKind::TooFewFigures { count, min } => write!(f, "{} figures, at least {} required", count, min),
+            ViolationKind::TooManyFigures { count, max } => write!(f, "{} figures, at most {} allowed", count, max),
+            ViolationKind::TotalKTooLow { k, min } => write!(f, "total K {} is below the minimum of {}", k, min),
+            ViolationKind::TotalKTooHigh { k, max } => write!(f, "total K {} is above the maximum of {}", k, max),
+            ViolationKind::FigureKTooHigh { k, max } => write!(f, "K {} is above the maximum of {}", k, max),
+            ViolationKind::FamilyNotAllowed(aresti) => write!(f, "{} is not allowed", aresti),
+            ViolationKind::Repeated { aresti, max } => write!(f, "{} is flown more than {} times", aresti, max),
+            ViolationKind::FamilyLimit { family, max } => write!(f, "family {} is used more than {} times", family, max),
+            ViolationKind::MissingRollType(roll_type) => write!(f, "no {} in the sequence", roll_type),
+            ViolationKind::TooManyRolls { count, max } => write!(f, "{} rolling elements, at most {} allowed", count, max),
+            ViolationKind::CatalogueMismatch { figures, catalogue } => write!(f, "{} figures in the sequence but {} in the catalogue data", figures, catalogue),
+            }
+        }
+    }
+
+/// Whether a catalogue number is in a family, comparing whole components so "9.1" does not match "9.10.1.1"
+fn in_family(aresti: &str, family: &str) -> bool {
+    let mut components = aresti.split('.');
+    family.split('.').all(|family_component| components.next() == Some(family_component))
+    }
+
+/// Roll type of a rolling element of a parsed figure
+fn roll_type(element: &Element) -> Option<RollType> {
+    match element {
+        Element::Roll { divisions, .. } if *divisions > 1 => Some(RollType::Hesitation),
+        Element::Roll { .. } => Some(RollType::Aileron),
+        Element::Flick { .. } => Some(RollType::Flick),
+        Element::Spin { .. } => Some(RollType::Spin),
+        _ => None,
+        }
+    }
+
+impl RuleSet {
+    pub fn from_toml(source: &str) -> Result<RuleSet, String> {
+        toml::from_str(source).map_err(|error| error.to_string())
+        }
+
+    pub fn from_json(source: &str) -> Result<RuleSet, String> {
+        serde_json::from_str(source).map_err(|error| error.to_string())
+        }
+
+    /// Read a rule set from a file, as JSON if it has a .json extension and TOML otherwise
+    pub fn load(path: &Path) -> Result<RuleSet, String> {
+        let source = fs::read_to_string(path).map_err(|error| format!("cannot read {}: {}", path.display(), error))?;
+        let is_json = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+        let rules = if is_json { RuleSet::from_json(&source) } else { RuleSet::from_toml(&source) };
+        rules.map_err(|error| format!("{}: {}", path.display(), error))
+        }
+
+    /// Look up one of the BUILTIN_RULES by name, ignoring case
+    pub fn builtin(name: &str) -> Option<RuleSet> {
+        BUILTIN_RULES.iter()
+            .find(|(builtin_name, _)| builtin_name.eq_ignore_ascii_case(name))
+            .map(|(_, source)| RuleSet::from_toml(source).expect("Built-in rule set is invalid"))
+        }
+
+    pub fn builtin_names() -> Vec<&'static str> {
+        BUILTIN_RULES.iter().map(|(name, _)| *name).collect()
+        }
+
+    /// Rules for a category and programme, ignoring case
+    pub fn program(&self, category: &str, program: &str) -> Option<&ProgramRules> {
+        self.programs.iter()
+            .find(|rules| rules.category.eq_ignore_ascii_case(category) && rules.program.eq_ignore_ascii_case(program))
+        }
+
+    /// Check the sequence of a `.seq` file against the rules for its category and programme
+    pub fn check_seq_file(&self, seq_file: &SeqFile, sequence: &Sequence) -> Result<Vec<Violation>, String> {
+        let category = seq_file.category.as_deref().ok_or_else(|| "no category in file".to_string())?;
+        let program = seq_file.program.as_deref().ok_or_else(|| "no program in file".to_string())?;
+        let rules = self.program(category, program)
+            .ok_or_else(|| format!("no {} rules for {} {}", self.name, category, program))?;
+        Ok(rules.check(sequence, &seq_file.figures))
+        }
+    }
+
+impl ProgramRules {
+    /// Find the rules broken by a sequence. The catalogue data gives the Aresti numbers and K of each figure;
+    ///  without it only the figure count and rolling elements are checked
+    pub fn check(&self, sequence: &Sequence, catalogue: &[CatalogueFigure]) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        let mut violation = |figure: Option<usize>, kind: ViolationKind| violations.push(Violation { figure, kind });
+
+        let count = sequence.figures.len();
+        if let Some(min) = self.min_figures.filter(|min| count < *min) {
+            violation(None, ViolationKind::TooFewFigures { count, min });
+            }
+        if let Some(max) = self.max_figures.filter(|max| count > *max) {
+            violation(None, ViolationKind::TooManyFigures { count, max });
+            }
+
+        // Rolling elements from the parsed figures
+        let mut roll_types = Vec::new();
+        for (i, figure) in sequence.figures.iter().enumerate() {
+            let rolls: Vec<RollType> = figure.elements.iter().filter_map(roll_type).collect();
+            if let Some(max) = self.max_rolls_per_figure.filter(|max| rolls.len() > *max) {
+                violation(Some(i), ViolationKind::TooManyRolls { count: rolls.len(), max });
+                }
+            roll_types.extend(rolls);
+            }
+        for required in &self.required_rolls {
+            if !roll_types.contains(required) {
+                violation(None, ViolationKind::MissingRollType(*required));
+                }
+            }
+
+        if catalogue.is_empty() {
+            return violations;
+            }
+        if catalogue.len() != count {
+            violation(None, ViolationKind::CatalogueMismatch { figures: count, catalogue: catalogue.len() });
+            }
+
+        let total_k: f32 = catalogue.iter().map(|figure| figure.k).sum();
+        if let Some(min) = self.min_k.filter(|min| total_k < *min) {
+            violation(None, ViolationKind::TotalKTooLow { k: total_k, min });
+            }
+        if let Some(max) = self.max_k.filter(|max| total_k > *max) {
+            violation(None, ViolationKind::TotalKTooHigh { k: total_k, max });
+            }
+
+        let mut base_figures: Vec<&str> = Vec::new();
+        let mut family_counts = vec![0; self.family_limits.len()];
+        for (i, figure) in catalogue.iter().enumerate() {
+            if let Some(max) = self.max_figure_k.filter(|max| figure.k > *max) {
+                violation(Some(i), ViolationKind::FigureKTooHigh { k: figure.k, max });
+                }
+            for element in &figure.elements {
+                let allowed = self.allowed_families.is_empty() || self.allowed_families.iter().any(|family| in_family(&element.aresti, family));
+                let forbidden = self.forbidden_families.iter().any(|family| in_family(&element.aresti, family));
+                if !allowed || forbidden {
+                    violation(Some(i), ViolationKind::FamilyNotAllowed(element.aresti.clone()));
+                    }
+                }
+
+            // Each family counts once per figure
+            for (limit, family_count) in self.family_limits.iter().zip(family_counts.iter_mut()) {
+                if figure.elements.iter().any(|element| in_family(&element.aresti, &limit.family)) {
+                    *family_count += 1;
+                    if *family_count > limit.max {
+                        violation(Some(i), ViolationKind::FamilyLimit { family: limit.family.clone(), max: limit.max });
+                        }
+                    }
+                }
+
+            if let Some(base) = figure.elements.iter().find(|element| !in_family(&element.aresti, "9")) {
+                base_figures.push(&base.aresti);
+                let repeats = base_figures.iter().filter(|aresti| **aresti == base.aresti).count();
+                if let Some(max) = self.max_repeats.filter(|max| repeats > *max) {
+                    violation(Some(i), ViolationKind::Repeated { aresti: base.aresti.clone(), max });
+                    }
+                }
+            }
+        violations
+        }
+    }
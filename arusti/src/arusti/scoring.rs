@@ -0,0 +1,210 @@
+extern crate nalgebra as na;
+use na::Vector3;
+
+use std::fmt;
+
+use crate::types::{Element, ElementType, Sequence};
+use crate::data_generation::DataPoint;
+use crate::box_frame::{BoxFrame, BoxEvent, BoxEventKind};
+use crate::fitting;
+use crate::judging::{self, FigureMark, JudgingOptions};
+use crate::seq_file::SeqFile;
+
+/// Options for scoring a whole sequence
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct ScoringOptions {
+    pub judging: JudgingOptions,
+    pub box_frame: BoxFrame,
+    /// Figure that should be centred in the box. Defaults to the middle figure
+    pub centre_figure: Option<usize>,
+    /// Distance off position that costs one positioning point (m)
+    pub position_tolerance: f32,
+    /// Variation in radius, as a fraction of the mean, that costs one harmony point
+    pub size_tolerance: f32,
+    /// Penalty for each box out
+    pub box_out_penalty: f32,
+    /// Penalty for each infringement of the lowest altitude
+    pub low_altitude_penalty: f32,
+    }
+
+impl Default for ScoringOptions {
+    fn default() -> ScoringOptions {
+        ScoringOptions {
+            judging: JudgingOptions::default(),
+            box_frame: BoxFrame::default(),
+            centre_figure: None,
+            position_tolerance: 50.0,
+            size_tolerance: 0.05,
+            box_out_penalty: 10.0,
+            low_altitude_penalty: 20.0,
+            }
+        }
+    }
+
+/// Weights given to the marks of a sequence
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Default)]
+#[derive(PartialEq)]
+pub struct Coefficients {
+    /// K factor of each figure. Figures without one have a K of 1
+    pub figure_k: Vec<f32>,
+    pub positioning: f32,
+    pub harmony: f32,
+    }
+
+impl From<&SeqFile> for Coefficients {
+    fn from(seq_file: &SeqFile) -> Coefficients {
+        Coefficients {
+            figure_k: seq_file.figure_k(),
+            positioning: seq_file.positioning,
+            harmony: seq_file.harmony,
+            }
+        }
+    }
+
+impl Coefficients {
+    pub fn figure_k(&self, figure: usize) -> f32 {
+        self.figure_k.get(figure).cloned().unwrap_or(1.0)
+        }
+    }
+
+/// A presentation fault, with the distance or variation it was measured as
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum PresentationFault {
+    /// Centre figure flown off the centre of the box along the X axis (m)
+    OffCentre(f32),
+    /// Sequence flown off the centre of the box along the X axis (m)
+    Unbalanced(f32),
+    /// Sequence flown away from the centre line of the box (m)
+    OffCentreLine(f32),
+    /// Variation of the radii flown, as a fraction of their mean
+    UnevenSize(f32),
+    }
+
+impl fmt::Display for PresentationFault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PresentationFault::OffCentre(distance) => write!(f, "centre figure {:.0} m off centre", distance),
+            PresentationFault::Unbalanced(distance) => write!(f, "sequence {:.0} m off centre", distance),
+            PresentationFault::OffCentreLine(distance) => write!(f, "sequence {:.0} m off the centre line", distance),
+            PresentationFault::UnevenSize(variation) => write!(f, "radii vary by {:.0}%", variation * 100.0),
+            }
+        }
+    }
+
+/// Positioning or harmony mark, from 0 to 10
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct PresentationMark {
+    pub mark: f32,
+    /// Faults with the points deducted for each
+    pub deductions: Vec<(PresentationFault, f32)>,
+    }
+
+impl PresentationMark {
+    fn new(faults: Vec<(PresentationFault, f32)>) -> PresentationMark {
+        let deductions: Vec<(PresentationFault, f32)> = faults.into_iter().filter(|(_, points)| *points > 0.0).collect();
+        PresentationMark {
+            mark: (10.0 - deductions.iter().map(|(_, points)| points).sum::<f32>()).max(0.0),
+            deductions,
+            }
+        }
+    }
+
+/// Scores for a whole sequence
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct SequenceScore {
+    pub figures: Vec<FigureMark>,
+    pub positioning: PresentationMark,
+    pub harmony: PresentationMark,
+    pub events: Vec<BoxEvent>,
+    pub penalties: f32,
+    /// Marks weighted by the coefficients, less penalties
+    pub total: f32,
+    /// Total for a perfect flight
+    pub maximum: f32,
+    }
+
+/// One point per tolerance, rounded down to the nearest half point
+fn tolerance_deduction(value: f32, tolerance: f32) -> f32 {
+    ((value.abs() / tolerance * 2.0).floor() / 2.0).min(10.0)
+    }
+
+/// Mark the positioning of the flown figures in the box
+pub fn positioning_mark(points: &[DataPoint], figures: &[FigureMark], options: &ScoringOptions) -> PresentationMark {
+    let positions: Vec<Vector3<f32>> = points.iter().map(|point| options.box_frame.to_box(&point.position)).collect();
+    // Middle of the extent along the main axis
+    let middle = |positions: &[Vector3<f32>]| {
+        let (min, max) = positions.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), position| (min.min(position[0]), max.max(position[0])));
+        (min + max) / 2.0
+        };
+    if positions.is_empty() {
+        return PresentationMark::new(Vec::new());
+        }
+
+    let mut faults = Vec::new();
+    let centre_figure = options.centre_figure.unwrap_or(figures.len() / 2);
+    if let Some(flown) = figures.get(centre_figure).and_then(|mark| mark.flown.as_ref()) {
+        let offset = middle(&positions[flown.start..flown.end]);
+        faults.push((PresentationFault::OffCentre(offset.abs()), tolerance_deduction(offset, options.position_tolerance)));
+        }
+    let offset = middle(&positions);
+    faults.push((PresentationFault::Unbalanced(offset.abs()), tolerance_deduction(offset, options.position_tolerance)));
+    let offset = positions.iter().map(|position| position[1]).sum::<f32>() / positions.len() as f32;
+    faults.push((PresentationFault::OffCentreLine(offset.abs()), tolerance_deduction(offset, options.position_tolerance)));
+    PresentationMark::new(faults)
+    }
+
+/// Mark the harmony of the sequence from how consistently sized its radii are
+pub fn harmony_mark(points: &[DataPoint], figures: &[FigureMark], options: &ScoringOptions) -> PresentationMark {
+    let segmentation = &options.judging.segmentation;
+    let sizes: Vec<f32> = figures.iter()
+        .filter_map(|mark| mark.flown.as_ref())
+        .flat_map(|flown| flown.elements.iter())
+        .filter(|segment| segment.elem_type == ElementType::Radius)
+        .filter_map(|segment| fitting::fit_segment(points, segment, segmentation))
+        .filter(|fit| matches!(fit.element, Element::Radius { angle, .. } if angle.abs() >= 90.0))
+        .map(|fit| fit.size)
+        .collect();
+    if sizes.len() < 2 {
+        return PresentationMark::new(Vec::new());
+        }
+
+    let mean = sizes.iter().sum::<f32>() / sizes.len() as f32;
+    let deviation = (sizes.iter().map(|size| (size - mean).powi(2)).sum::<f32>() / sizes.len() as f32).sqrt();
+    let variation = deviation / mean;
+    PresentationMark::new(vec![(PresentationFault::UnevenSize(variation), tolerance_deduction(variation, options.size_tolerance))])
+    }
+
+/// Judge each figure, mark the positioning and harmony, and total them weighted by the coefficients
+pub fn score_sequence(points: &[DataPoint], sequence: &Sequence, coefficients: &Coefficients, options: &ScoringOptions) -> SequenceScore {
+    let figures = judging::judge_flight(points, sequence, &options.judging);
+    let positioning = positioning_mark(points, &figures, options);
+    let harmony = harmony_mark(points, &figures, options);
+    let events = options.box_frame.box_events(points);
+    let penalties = events.iter()
+        .map(|event| match event.kind() {
+            BoxEventKind::BoxOut => options.box_out_penalty,
+            BoxEventKind::LowAltitude => options.low_altitude_penalty,
+            })
+        .fold(0.0, |total, penalty| total + penalty);
+
+    let figure_total: f32 = figures.iter().enumerate().map(|(i, mark)| mark.mark * coefficients.figure_k(i)).sum();
+    let figure_maximum: f32 = (0..figures.len()).map(|i| 10.0 * coefficients.figure_k(i)).sum();
+    SequenceScore {
+        total: figure_total + positioning.mark * coefficients.positioning + harmony.mark * coefficients.harmony - penalties,
+        maximum: figure_maximum + 10.0 * (coefficients.positioning + coefficients.harmony),
+        figures,
+        positioning,
+        harmony,
+        events,
+        penalties,
+        }
+    }
@@ -0,0 +1,393 @@
+extern crate nalgebra as na;
+use na::{Vector3, UnitQuaternion};
+
+use crate::types::ElementType;
+use crate::data_generation::DataPoint;
+
+/// Thresholds used to split a flight into figures and elements
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct SegmentationOptions {
+    /// Time over which attitudes are differenced for angular rates, smoothing sensor noise (s)
+    pub rate_window: f32,
+    /// Time over which positions are differenced for velocity, smoothing sensor noise (s)
+    pub velocity_window: f32,
+    /// Body roll rate above which the aircraft is rolling (deg/s)
+    pub roll_threshold: f32,
+    /// Pitch and yaw rate accompanying a roll above which the aircraft is autorotating (deg/s)
+    pub autorotation_threshold: f32,
+    /// Rate of change of heading above which level flight is a turn (deg/s)
+    pub turn_threshold: f32,
+    /// Angular rate above which the flight path is curving (deg/s)
+    pub pitch_threshold: f32,
+    /// Shortest element. Shorter runs are merged into their neighbours (s)
+    pub min_element_time: f32,
+    /// Longest pause between rolls that is treated as a hesitation in one roll (s)
+    pub max_hesitation_time: f32,
+    /// Shortest horizontal line that separates two figures (s)
+    pub min_separator_time: f32,
+    /// Largest flight path angle of a horizontal line (degrees)
+    pub level_tolerance: f32,
+    }
+
+impl Default for SegmentationOptions {
+    fn default() -> SegmentationOptions {
+        SegmentationOptions {
+            rate_window: 0.2,
+            velocity_window: 0.5,
+            roll_threshold: 30.0,
+            autorotation_threshold: 60.0,
+            turn_threshold: 10.0,
+            pitch_threshold: 10.0,
+            min_element_time: 0.2,
+            max_hesitation_time: 0.5,
+            min_separator_time: 1.5,
+            level_tolerance: 15.0,
+            }
+        }
+    }
+
+/// Motion of the aircraft at a sample, estimated by differencing its neighbours
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub struct Kinematics {
+    /// Velocity in the earth frame (m/s)
+    pub velocity: Vector3<f32>,
+    /// Rates about the body axes (deg/s)
+    pub body_rates: Vector3<f32>,
+    /// Rates about the earth axes (deg/s)
+    pub earth_rates: Vector3<f32>,
+    /// Angle of the velocity above the horizon (degrees)
+    pub flight_path_angle: f32,
+    /// Whether the body z axis points upwards
+    pub inverted: bool,
+    }
+
+/// A run of samples flying a single element, from start up to but excluding end
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub struct Segment {
+    /// One of Line, Radius, Turn, Roll, Flick or Spin
+    pub elem_type: ElementType,
+    pub start: usize,
+    pub end: usize,
+    /// Whether the aircraft is inverted at the start of the segment
+    pub inverted: bool,
+    }
+
+impl Segment {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+        }
+
+    pub fn is_empty(&self) -> bool {
+        self.end == self.start
+        }
+    }
+
+/// A run of samples flying one figure, split into its elements
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct FigureSegment {
+    pub start: usize,
+    pub end: usize,
+    pub elements: Vec<Segment>,
+    }
+
+/// Mean time between the points, or None if there are fewer than two or the time does not advance (s)
+fn sample_time(points: &[DataPoint]) -> Option<f32> {
+    if points.len() < 2 {
+        return None;
+        }
+    let sample_time = (points[points.len()-1].time - points[0].time) / (points.len() - 1) as f32;
+    if sample_time.is_finite() && sample_time > 0.0 { Some(sample_time) } else { None }
+    }
+
+/// Estimate the motion at each of the points
+pub fn kinematics(points: &[DataPoint], options: &SegmentationOptions) -> Vec<Kinematics> {
+    let sample_time = match sample_time(points) {
+        Some(sample_time) => sample_time,
+        // No motion can be estimated without time between the points
+        None => return points.iter().map(|point| Kinematics {
+            velocity: Vector3::zeros(),
+            body_rates: Vector3::zeros(),
+            earth_rates: Vector3::zeros(),
+            flight_path_angle: 0.0,
+            inverted: is_inverted(&point.attitude),
+            }).collect(),
+        };
+    // Neighbours either side of a sample spanning window
+    let neighbours = |i: usize, window: f32| {
+        let half_width = ((0.5 * window / sample_time).round() as usize).max(1);
+        (&points[i.saturating_sub(half_width)], &points[i.saturating_add(half_width).min(points.len() - 1)])
+        };
+
+    (0..points.len()).map(|i| {
+        let (before, after) = neighbours(i, options.velocity_window);
+        let velocity = (after.position - before.position) / (after.time - before.time);
+
+        let (before, after) = neighbours(i, options.rate_window);
+        let dt = after.time - before.time;
+        let body_rotation = before.attitude.inverse() * after.attitude;
+        let earth_rotation = after.attitude * before.attitude.inverse();
+        let horizontal_speed = (velocity[0].powi(2) + velocity[1].powi(2)).sqrt();
+
+        Kinematics {
+            velocity,
+            body_rates: body_rotation.scaled_axis().map(|r| r.to_degrees()) / dt,
+            earth_rates: earth_rotation.scaled_axis().map(|r| r.to_degrees()) / dt,
+            flight_path_angle: (-velocity[2]).atan2(horizontal_speed).to_degrees(),
+            inverted: is_inverted(&points[i].attitude),
+            }
+        }).collect()
+    }
+
+pub(crate) fn is_inverted(attitude: &UnitQuaternion<f32>) -> bool {
+    (attitude * Vector3::<f32>::z())[2] < 0.0
+    }
+
+/// Element being flown at a sample, with the direction of any rotation about the body x axis
+fn classify(state: &Kinematics, options: &SegmentationOptions) -> (ElementType, bool) {
+    let roll_rate = state.body_rates[0];
+    let cross_rate = (state.body_rates[1].powi(2) + state.body_rates[2].powi(2)).sqrt();
+    let heading_rate = state.earth_rates[2].abs();
+    // Rotation of the flight path in the horizontal plane, as opposed to rolling about it
+    let direction = state.velocity.try_normalize(1e-3).unwrap_or_else(Vector3::x);
+    let path_rates = state.earth_rates - direction * state.earth_rates.dot(&direction);
+    let climb_rate = (path_rates[0].powi(2) + path_rates[1].powi(2)).sqrt();
+    let level = state.flight_path_angle.abs() < options.level_tolerance;
+    let positive = roll_rate >= 0.0;
+
+    if roll_rate.abs() > options.roll_threshold && cross_rate > options.autorotation_threshold {
+        if state.flight_path_angle < -45.0 {
+            (ElementType::Spin, positive)
+            }
+        else {
+            (ElementType::Flick, positive)
+            }
+        }
+    else if level && heading_rate > options.turn_threshold && heading_rate > climb_rate {
+        (ElementType::Turn, true)
+        }
+    else if roll_rate.abs() > options.roll_threshold {
+        (ElementType::Roll, positive)
+        }
+    else if state.body_rates.norm() > options.pitch_threshold {
+        (ElementType::Radius, true)
+        }
+    else {
+        (ElementType::Line, true)
+        }
+    }
+
+/// Merge the segment at index into the one before it, or the one after if it is first
+fn absorb(segments: &mut Vec<Segment>, index: usize) {
+    let removed = segments.remove(index);
+    if index > 0 {
+        segments[index - 1].end = removed.end;
+        }
+    else if let Some(next) = segments.first_mut() {
+        next.start = removed.start;
+        next.inverted = removed.inverted;
+        }
+    }
+
+/// Join neighbouring segments flying the same element
+fn join_matching(segments: &mut Vec<Segment>, directions: &mut Vec<bool>) {
+    let mut i = 1;
+    while i < segments.len() {
+        if segments[i].elem_type == segments[i-1].elem_type && directions[i] == directions[i-1] {
+            segments[i-1].end = segments[i].end;
+            segments.remove(i);
+            directions.remove(i);
+            }
+        else {
+            i += 1;
+            }
+        }
+    }
+
+/// Split a flight into the elements flown
+pub fn segment_elements(points: &[DataPoint], options: &SegmentationOptions) -> Vec<Segment> {
+    let states = kinematics(points, options);
+    if states.is_empty() {
+        return Vec::new();
+        }
+    let sample_time = match sample_time(points) {
+        Some(sample_time) => sample_time,
+        None if points.len() == 1 => 1.0,
+        // Points all at the same time, e.g. a log with a stuck clock
+        None => return Vec::new(),
+        };
+
+    // Runs of samples classified alike
+    let mut segments = Vec::<Segment>::new();
+    let mut directions = Vec::<bool>::new();
+    for (i, state) in states.iter().enumerate() {
+        let (elem_type, direction) = classify(state, options);
+        match segments.last_mut() {
+            Some(last) if last.elem_type == elem_type && *directions.last().unwrap() == direction => last.end = i + 1,
+            _ => {
+                segments.push(Segment { elem_type, start: i, end: i + 1, inverted: state.inverted });
+                directions.push(direction);
+                }
+            }
+        }
+
+    // Drop runs too short to be an element, e.g. transients between elements
+    let min_samples = (options.min_element_time / sample_time).ceil() as usize;
+    while segments.len() > 1 {
+        let shortest = (0..segments.len()).min_by_key(|&i| segments[i].len()).unwrap();
+        if segments[shortest].len() >= min_samples {
+            break;
+            }
+        absorb(&mut segments, shortest);
+        directions.remove(shortest);
+        join_matching(&mut segments, &mut directions);
+        }
+
+    // Hesitations are pauses within a single roll
+    let max_hesitation_samples = (options.max_hesitation_time / sample_time).round() as usize;
+    let mut i = 1;
+    while i + 1 < segments.len() {
+        let is_hesitation = segments[i].elem_type == ElementType::Line
+            && segments[i].len() <= max_hesitation_samples
+            && segments[i-1].elem_type == ElementType::Roll
+            && segments[i+1].elem_type == ElementType::Roll
+            && directions[i-1] == directions[i+1];
+        if is_hesitation {
+            segments[i-1].end = segments[i+1].end;
+            segments.drain(i..i+2);
+            directions.drain(i..i+2);
+            }
+        else {
+            i += 1;
+            }
+        }
+
+    // Entry and exit rolls of a turn are part of the turn
+    let mut i = 0;
+    while i < segments.len() {
+        if segments[i].elem_type == ElementType::Turn {
+            if i + 1 < segments.len() && segments[i+1].elem_type == ElementType::Roll {
+                segments[i].end = segments[i+1].end;
+                segments.remove(i + 1);
+                directions.remove(i + 1);
+                }
+            if i > 0 && segments[i-1].elem_type == ElementType::Roll {
+                segments[i-1].elem_type = ElementType::Turn;
+                segments[i-1].end = segments[i].end;
+                segments.remove(i);
+                directions.remove(i);
+                i -= 1;
+                }
+            }
+        i += 1;
+        }
+    join_matching(&mut segments, &mut directions);
+
+    let half_width = ((0.5 * options.rate_window / sample_time).round() as usize).max(1);
+    refine_boundaries(&mut segments, &states, half_width);
+
+    // An autorotation builds up from the line before it, so it starts where the line stops being still.
+    //  A roll too short to be an element just before it is the start of the build up
+    let mut i = 1;
+    while i < segments.len() {
+        let is_autorotation = matches!(segments[i].elem_type, ElementType::Flick | ElementType::Spin);
+        if is_autorotation && i > 1 && segments[i-1].elem_type == ElementType::Roll && segments[i-1].len() < min_samples {
+            segments[i].start = segments[i-1].start;
+            segments[i].inverted = segments[i-1].inverted;
+            segments.remove(i - 1);
+            i -= 1;
+            }
+        if is_autorotation && segments[i-1].elem_type == ElementType::Line {
+            let mut start = segments[i].start;
+            while start > segments[i-1].start + 1 && states[start - 1].body_rates.norm() > options.pitch_threshold {
+                start -= 1;
+                }
+            segments[i-1].end = start;
+            segments[i].start = start;
+            }
+        i += 1;
+        }
+    segments
+    }
+
+/// Move each boundary to where the angular rate is midway between the elements either side.
+///  The rate window spreads a step in rate into a ramp centred on the true boundary
+fn refine_boundaries(segments: &mut [Segment], states: &[Kinematics], half_width: usize) {
+    let rates: Vec<f32> = states.iter().map(|state| state.body_rates.norm()).collect();
+    let median = |range: std::ops::Range<usize>| {
+        let mut values = rates[range].to_vec();
+        values.sort_by(f32::total_cmp);
+        values[values.len() / 2]
+        };
+
+    for i in 1..segments.len() {
+        let (before, after) = (segments[i-1], segments[i]);
+        let boundary = after.start;
+        // Rates just clear of the ramp either side
+        let before_end = boundary.saturating_sub(2 * half_width).max(before.start + 1);
+        let level_before = median(before_end.saturating_sub(half_width).max(before.start)..before_end);
+        let after_start = (boundary + 2 * half_width).min(after.end - 1);
+        let level_after = median(after_start..(after_start + half_width).min(after.end));
+        let midway = 0.5 * (level_before + level_after);
+
+        let search_start = boundary.saturating_sub(2 * half_width).max(before.start + 1);
+        let search_end = (boundary + 2 * half_width).min(after.end - 1);
+        if let Some(mut refined) = (search_start..=search_end).find(|&j| (rates[j] - midway) * (level_after - level_before) >= 0.0) {
+            // Take whichever sample either side of the crossing is nearer midway
+            if refined > search_start && (rates[refined - 1] - midway).abs() <= (rates[refined] - midway).abs() {
+                refined -= 1;
+                }
+            segments[i-1].end = refined;
+            segments[i].start = refined;
+            segments[i].inverted = states[refined].inverted;
+            }
+        }
+    }
+
+/// Split a flight into figures at the horizontal lines between them, upright or inverted
+pub fn segment_figures(points: &[DataPoint], options: &SegmentationOptions) -> Vec<FigureSegment> {
+    let elements = segment_elements(points, options);
+    if elements.is_empty() {
+        return Vec::new();
+        }
+    let states = kinematics(points, options);
+    let min_separator_samples = points.iter()
+        .position(|point| point.time - points[0].time >= options.min_separator_time)
+        .unwrap_or(points.len());
+
+    let mut figures = vec![FigureSegment { start: 0, end: points.len(), elements: Vec::new() }];
+    for element in elements {
+        let is_separator = element.elem_type == ElementType::Line
+            && element.len() >= min_separator_samples
+            && element.start > 0
+            && element.end < points.len()
+            && {
+                let mean_angle = states[element.start..element.end].iter()
+                    .map(|state| state.flight_path_angle).sum::<f32>() / element.len() as f32;
+                mean_angle.abs() < options.level_tolerance
+                };
+
+        if is_separator {
+            let split = element.start + element.len() / 2;
+            let figure = figures.last_mut().unwrap();
+            figure.end = split;
+            figure.elements.push(Segment { end: split, ..element });
+            figures.push(FigureSegment {
+                start: split,
+                end: points.len(),
+                elements: vec![Segment { start: split, inverted: states[split].inverted, ..element }],
+                });
+            }
+        else {
+            figures.last_mut().unwrap().elements.push(element);
+            }
+        }
+    figures
+    }
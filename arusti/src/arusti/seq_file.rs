@@ -0,0 +1,99 @@
+use std::fs;
+use std::path::Path;
+
+/// A catalogue element of a figure, e.g. the base figure or one of its rolls
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct CatalogueElement {
+    /// Aresti catalogue number, e.g. "9.4.3.2"
+    pub aresti: String,
+    pub k: f32,
+    }
+
+/// Catalogue entries and total K of a figure
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct CatalogueFigure {
+    pub elements: Vec<CatalogueElement>,
+    pub k: f32,
+    }
+
+/// Contents of an OpenAero `.seq` file used for judging
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct SeqFile {
+    /// OLAN for the sequence
+    pub sequence_text: String,
+    pub category: Option<String>,
+    pub program: Option<String>,
+    pub rules: Option<String>,
+    /// Coefficient applied to the positioning mark
+    pub positioning: f32,
+    /// Coefficient applied to the harmony mark
+    pub harmony: f32,
+    /// Catalogue entries of each figure, in order
+    pub figures: Vec<CatalogueFigure>,
+    }
+
+fn child_text<'a>(node: roxmltree::Node<'a, 'a>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|child| child.tag_name().name() == name)
+        .and_then(|child| child.text())
+        .map(|text| text.trim())
+    }
+
+fn parse_number(text: Option<&str>, name: &str) -> Result<f32, String> {
+    match text {
+        Some(text) => text.parse().map_err(|_| format!("invalid {}: {}", name, text)),
+        None => Ok(0.0),
+        }
+    }
+
+impl SeqFile {
+    /// Parse the XML of a `.seq` file
+    pub fn parse(contents: &str) -> Result<SeqFile, String> {
+        let doc = roxmltree::Document::parse(contents).map_err(|error| error.to_string())?;
+        let root = doc.root_element();
+        let sequence_text = child_text(root, "sequence_text")
+            .ok_or_else(|| "no sequence_text in file".to_string())?;
+
+        let figures = root.children()
+            .filter(|node| node.tag_name().name() == "figures")
+            .flat_map(|figures| figures.children())
+            .filter(|node| node.tag_name().name() == "figure")
+            .map(|figure| {
+                let elements = figure.children()
+                    .filter(|node| node.tag_name().name() == "element")
+                    .map(|element| Ok(CatalogueElement {
+                        aresti: child_text(element, "aresti").unwrap_or_default().to_string(),
+                        k: parse_number(child_text(element, "k"), "k")?,
+                        }))
+                    .collect::<Result<Vec<CatalogueElement>, String>>()?;
+                Ok(CatalogueFigure { elements, k: parse_number(child_text(figure, "figk"), "figk")? })
+                })
+            .collect::<Result<Vec<CatalogueFigure>, String>>()?;
+
+        Ok(SeqFile {
+            sequence_text: sequence_text.to_string(),
+            category: child_text(root, "category").map(str::to_string),
+            program: child_text(root, "program").map(str::to_string),
+            rules: child_text(root, "rules").map(str::to_string),
+            positioning: parse_number(child_text(root, "positioning"), "positioning")?,
+            harmony: parse_number(child_text(root, "harmony"), "harmony")?,
+            figures,
+            })
+        }
+
+    /// K factor of each figure, in order
+    pub fn figure_k(&self) -> Vec<f32> {
+        self.figures.iter().map(|figure| figure.k).collect()
+        }
+
+    pub fn load(path: &Path) -> Result<SeqFile, String> {
+        let contents = fs::read_to_string(path).map_err(|error| format!("cannot read {}: {}", path.display(), error))?;
+        SeqFile::parse(&contents).map_err(|error| format!("{}: {}", path.display(), error))
+        }
+    }
@@ -24,20 +24,24 @@ pub enum ElementType {
     Combining
     }
 
+use std::fmt;
+
+/// An element in the flat form, where the meaning of the angle and argument depends on the type.
+///  The OLAN parser builds figures in this form and pyarusti exposes it. Everything else uses `Element`
 #[derive(Debug)]
 #[derive(Clone)]
 #[derive(Copy)]
 #[derive(PartialEq)]
-pub struct Element {
+pub struct FlatElement {
     pub elem_type: ElementType,
     pub inverted: bool,
     pub angle: f32,
     pub argument: f32,
     }
 
-impl Element {
-    pub fn new(elem_type: ElementType) -> Element {
-        Element {
+impl FlatElement {
+    pub fn new(elem_type: ElementType) -> FlatElement {
+        FlatElement {
             elem_type: elem_type,
             inverted: false,
             angle: 0.0,
@@ -45,53 +49,325 @@ impl Element {
             }
         }
     
-    pub fn line(angle: f32) -> Element {
-        Element {
+    pub fn line(angle: f32) -> FlatElement {
+        FlatElement {
             angle: angle,
-            .. Element::new(ElementType::Line)
+            .. FlatElement::new(ElementType::Line)
             }
         }
     
-    pub fn invline(angle: f32) -> Element {
-        Element {
+    pub fn invline(angle: f32) -> FlatElement {
+        FlatElement {
             inverted: true,
-            .. Element::line(angle)
+            .. FlatElement::line(angle)
             }
         }
     
-    pub fn radius(angle: f32) -> Element {
-        Element {
+    pub fn radius(angle: f32) -> FlatElement {
+        FlatElement {
             angle: angle,
-            .. Element::new(ElementType::Radius)
+            .. FlatElement::new(ElementType::Radius)
             }
         }
     
-    pub fn stall(yaw: f32, pitch: f32) -> Element {
-        Element {
+    pub fn stall(yaw: f32, pitch: f32) -> FlatElement {
+        FlatElement {
             angle: yaw,
             argument: pitch,
-            .. Element::new(ElementType::Stall)
+            .. FlatElement::new(ElementType::Stall)
             }
         }
 
-    pub fn combining(argument: i8) -> Element {
-        Element {
+    pub fn combining(argument: i8) -> FlatElement {
+        FlatElement {
             argument: argument as f32,
-            .. Element::new(ElementType::Combining)
+            .. FlatElement::new(ElementType::Combining)
             }
         }
     
     }
 
+impl fmt::Display for FlatElement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.elem_type {
+            ElementType::Roll => write!(f, "Roll {}/{}", self.angle, self.argument)?,
+            ElementType::Turn => write!(f, "Turn {} roll {}", self.angle, self.argument)?,
+            ElementType::Stall => write!(f, "Stall {} pitch {}", self.angle, self.argument)?,
+            ElementType::Combining => write!(f, "Combining {}", self.argument)?,
+            _ => write!(f, "{:?} {}", self.elem_type, self.angle)?,
+            };
+        if self.inverted {
+            write!(f, " inverted")?;
+            }
+        Ok(())
+        }
+    }
+
+/// Direction of a roll, flick or spin, seen from behind
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum RollDirection {
+    /// Positive angles in the flat form
+    Right,
+    Left,
+    }
+
+impl RollDirection {
+    pub(crate) fn from_angle(angle: f32) -> RollDirection {
+        if angle < 0.0 { RollDirection::Left } else { RollDirection::Right }
+        }
+
+    /// 1 for right and -1 for left
+    pub fn sign(&self) -> f32 {
+        match self {
+            RollDirection::Right => 1.0,
+            RollDirection::Left => -1.0,
+            }
+        }
+    }
+
+/// Side of a rolling turn the aircraft rolls towards
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum TurnRollDirection {
+    /// Positive arguments in the flat form
+    Inside,
+    Outside,
+    }
+
+/// Size class of a radius, the argument in the flat form
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum RadiusSize {
+    /// Any size
+    Free,
+    /// Matched to the other radii of the figure with the same class
+    Matched(u8),
+    /// Matched by class, and kept as written when the figure is inverted
+    Fixed(u8),
+    }
+
+/// Point in a figure where the rolls written around it are inserted
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum CombiningPoint {
+    /// Rolls written before the figure, -1 in the flat form
+    Entry,
+    /// Rolls written after the figure, 0 in the flat form
+    Exit,
+    /// Rolls written in the first or second brackets, 1 or 2 in the flat form
+    Inner(u8),
+    }
+
+/// An element of a figure
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum Element {
+    /// Line at a pitch above the horizon (degrees)
+    Line { pitch: f32, inverted: bool },
+    /// Pull (+ve) or push (-ve) through an angle (degrees)
+    Radius { angle: f32, size: RadiusSize },
+    /// Turn through an angle (degrees), rolling through a number of rolls on the way
+    Turn { angle: f32, rolls: f32, roll_direction: TurnRollDirection, inverted: bool },
+    /// Roll through an angle (degrees) with the hesitation points per 360° as divisions, 1 for a continuous roll.
+    ///  Linked rolls follow the previous roll in the same direction
+    Roll { angle: f32, direction: RollDirection, divisions: u8, linked: bool },
+    Flick { angle: f32, direction: RollDirection, negative: bool, linked: bool },
+    Spin { angle: f32, direction: RollDirection, negative: bool, linked: bool },
+    /// Yaw through an angle as in a hammerhead, then pitch through an angle as in a tailslide (degrees)
+    Stall { yaw: f32, pitch: f32 },
+    Combining(CombiningPoint),
+    }
+
+impl Element {
+    pub fn line(pitch: f32) -> Element {
+        Element::Line { pitch, inverted: false }
+        }
+
+    pub fn invline(pitch: f32) -> Element {
+        Element::Line { pitch, inverted: true }
+        }
+
+    pub fn radius(angle: f32) -> Element {
+        Element::Radius { angle, size: RadiusSize::Free }
+        }
+
+    /// Roll through an angle, to the left if it is negative
+    pub fn roll(angle: f32, divisions: u8) -> Element {
+        Element::Roll { angle: angle.abs(), direction: RollDirection::from_angle(angle), divisions, linked: false }
+        }
+
+    /// Divisions of a roll through an angle flown in a number of parts, the inverse of `roll_parts`
+    pub fn hesitation_divisions(angle: f32, parts: u8) -> u8 {
+        if parts > 1 {
+            (parts as f32 * 360.0 / angle.abs()).round() as u8
+            }
+        else {
+            1
+            }
+        }
+
+    pub fn stall(yaw: f32, pitch: f32) -> Element {
+        Element::Stall { yaw, pitch }
+        }
+
+    pub fn elem_type(&self) -> ElementType {
+        match self {
+            Element::Line { .. } => ElementType::Line,
+            Element::Radius { .. } => ElementType::Radius,
+            Element::Turn { .. } => ElementType::Turn,
+            Element::Roll { .. } => ElementType::Roll,
+            Element::Flick { .. } => ElementType::Flick,
+            Element::Spin { .. } => ElementType::Spin,
+            Element::Stall { .. } => ElementType::Stall,
+            Element::Combining(_) => ElementType::Combining,
+            }
+        }
+
+    /// Whether the element is flown inverted: inverted lines and turns, and negative flicks and spins
+    pub fn inverted(&self) -> bool {
+        match *self {
+            Element::Line { inverted, .. } | Element::Turn { inverted, .. } => inverted,
+            Element::Flick { negative, .. } | Element::Spin { negative, .. } => negative,
+            _ => false,
+            }
+        }
+
+    /// Convert from the flat form. Rolls, flicks and spins are linked when they follow another in the same direction
+    pub fn from_flat(element: &FlatElement, previous: Option<&FlatElement>) -> Element {
+        let direction = RollDirection::from_angle(element.angle);
+        let linked = previous.is_some_and(|previous| {
+            matches!(previous.elem_type, ElementType::Roll | ElementType::Flick | ElementType::Spin)
+                && RollDirection::from_angle(previous.angle) == direction
+            });
+        match element.elem_type {
+            ElementType::Line => Element::Line { pitch: element.angle, inverted: element.inverted },
+            ElementType::Radius => {
+                let class = element.argument.abs().round() as u8;
+                let size = match class {
+                    0 => RadiusSize::Free,
+                    _ if element.argument < 0.0 => RadiusSize::Fixed(class),
+                    _ => RadiusSize::Matched(class),
+                    };
+                Element::Radius { angle: element.angle, size }
+                },
+            ElementType::Turn => Element::Turn {
+                angle: element.angle,
+                rolls: element.argument.abs() / 360.0,
+                roll_direction: if element.argument < 0.0 { TurnRollDirection::Outside } else { TurnRollDirection::Inside },
+                inverted: element.inverted,
+                },
+            ElementType::Roll => Element::Roll {
+                angle: element.angle.abs(),
+                direction,
+                divisions: element.argument.round().max(1.0) as u8,
+                linked,
+                },
+            ElementType::Flick => Element::Flick { angle: element.angle.abs(), direction, negative: element.inverted, linked },
+            ElementType::Spin => Element::Spin { angle: element.angle.abs(), direction, negative: element.inverted, linked },
+            ElementType::Stall => Element::Stall { yaw: element.angle, pitch: element.argument },
+            ElementType::Combining => Element::Combining(match element.argument as i8 {
+                -1 => CombiningPoint::Entry,
+                0 => CombiningPoint::Exit,
+                inner => CombiningPoint::Inner(inner.max(1) as u8),
+                }),
+            }
+        }
+
+    /// Convert to the flat form, which has no linked flag
+    pub fn to_flat(&self) -> FlatElement {
+        let new = FlatElement::new(self.elem_type());
+        match *self {
+            Element::Line { pitch, inverted } => FlatElement { angle: pitch, inverted, .. new },
+            Element::Radius { angle, size } => {
+                let argument = match size {
+                    RadiusSize::Free => 0.0,
+                    RadiusSize::Matched(class) => class as f32,
+                    RadiusSize::Fixed(class) => -(class as f32),
+                    };
+                FlatElement { angle, argument, .. new }
+                },
+            Element::Turn { angle, rolls, roll_direction, inverted } => {
+                let sign = if roll_direction == TurnRollDirection::Outside { -1.0 } else { 1.0 };
+                FlatElement { angle, argument: sign * rolls * 360.0, inverted, .. new }
+                },
+            Element::Roll { angle, direction, divisions, .. } => FlatElement { angle: direction.sign() * angle, argument: divisions as f32, .. new },
+            Element::Flick { angle, direction, negative, .. } | Element::Spin { angle, direction, negative, .. } => {
+                FlatElement { angle: direction.sign() * angle, argument: 1.0, inverted: negative, .. new }
+                },
+            Element::Stall { yaw, pitch } => FlatElement { angle: yaw, argument: pitch, .. new },
+            Element::Combining(point) => {
+                let argument = match point {
+                    CombiningPoint::Entry => -1.0,
+                    CombiningPoint::Exit => 0.0,
+                    CombiningPoint::Inner(inner) => inner as f32,
+                    };
+                FlatElement { argument, .. new }
+                },
+            }
+        }
+    }
+
+impl From<&Element> for FlatElement {
+    fn from(element: &Element) -> FlatElement {
+        element.to_flat()
+        }
+    }
+
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_flat())
+        }
+    }
+
+/// Drawing instruction written before a figure in OLAN. These only affect how the sequence is drawn
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub enum LayoutCommand {
+    /// `eu`: enter the figure flying upwind, drawn left to right on form B
+    EntryUpwind,
+    /// `ed`: enter the figure flying downwind, drawn right to left on form B
+    EntryDownwind,
+    /// `ej`: enter the figure on the Y axis, flying towards the judges
+    EntryTowardsJudges,
+    /// `eja`: enter the figure on the Y axis, flying away from the judges
+    EntryAwayFromJudges,
+    /// `/`: switch the direction Y axis flight is drawn in, for the rest of the sequence
+    SwitchYAxis,
+    /// `//`: switch the X axis direction the figure is entered in
+    SwitchXAxis,
+    /// `n>`: lengthen the gap before the figure by n steps
+    MoveForward(u32),
+    /// `[x,y]`: move the start of the figure x right and y down, without a connector
+    MoveBy(i32,i32),
+    /// `(x,y)`: move the start of the figure x right and y down, drawing a curved connector
+    CurveTo(i32,i32),
+    }
+
 #[derive(Debug)]
 pub struct Figure {
     pub elements: Vec<Element>,
+    /// Layout commands written before the figure
+    pub layout: Vec<LayoutCommand>,
     }
 
 impl Figure {
     pub fn new() -> Figure {
         Figure {
-            elements: Vec::new()
+            elements: Vec::new(),
+            layout: Vec::new(),
             }
         }
     
@@ -102,6 +378,19 @@ impl Figure {
     pub fn push(self: &mut Figure, new_element: Element) {
         self.elements.push(new_element);
         }
+
+    /// Figure with the typed form of flat elements
+    pub fn from_flat(elements: &[FlatElement]) -> Figure {
+        let elements = elements.iter().enumerate()
+            .map(|(i, element)| Element::from_flat(element, i.checked_sub(1).map(|previous| &elements[previous])))
+            .collect();
+        Figure { elements, .. Figure::new() }
+        }
+
+    /// The elements in the flat form, as used by the OLAN parser and pyarusti
+    pub fn to_flat(&self) -> Vec<FlatElement> {
+        self.elements.iter().map(Element::to_flat).collect()
+        }
     }
 
 impl<'a> IntoIterator for &'a Figure {
@@ -134,6 +423,19 @@ impl Sequence {
         self.figures.push(new_figure);
         }
 
+    /// Indices of figures whose entry line does not continue the inversion state of the previous figure's exit line
+    pub fn continuity_breaks(&self) -> Vec<usize> {
+        let mut breaks = Vec::new();
+        for (i,(previous,current)) in self.figures.iter().zip(self.figures.iter().skip(1)).enumerate() {
+            let exit_inverted = previous.elements.last().map(|e| e.inverted());
+            let entry_inverted = current.elements.first().map(|e| e.inverted());
+            if exit_inverted.is_some() && entry_inverted.is_some() && exit_inverted != entry_inverted {
+                breaks.push(i + 1);
+                }
+            }
+        breaks
+        }
+
     }
 
 impl<'a> IntoIterator for &'a Sequence {
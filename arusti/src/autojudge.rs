@@ -1,78 +1,202 @@
-extern crate nalgebra as na;
-use na::{Vector3, UnitQuaternion};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
 
-use core::f32::consts::PI;
+use clap::Parser;
 
 use arusti;
+use arusti::{fitting, recognition, scoring, segmentation};
+use arusti::{BoxFrame, Category, Coefficients, Element, GeoOrigin, ImportFormat, ImportOptions, JudgingOptions, PlotFormat, PlotOptions, ScoringOptions, SegmentationOptions, SeqFile, Vector3};
+use arusti::import::ColumnMapping;
 
-struct FlightDataPoint {
-    position: Vector3<f32>,
-    attitude: UnitQuaternion<f32>
+/// Split flight data into figures, fit the elements flown and judge them against the filed sequence
+#[derive(Parser)]
+#[command(name = "autojudge", version)]
+struct Cli {
+    /// Flight data, e.g. a CSV file from datagen or a flight log
+    #[arg(short, long)]
+    file: PathBuf,
+    /// Format of the flight data: dataflash, ulog, nmea, gpx, igc or csv. Guessed from the file extension by default
+    #[arg(long)]
+    format: Option<ImportFormat>,
+    /// Origin of the flight data frame for logs with latitude and longitude, as latitude,longitude,altitude (degrees, m).
+    ///  Defaults to the first fix
+    #[arg(long, value_parser = parse_origin, allow_hyphen_values = true)]
+    origin: Option<GeoOrigin>,
+    /// Columns to read from CSV, as key=column pairs, e.g. "time=t,lat=Lat,lon=Lng,alt=Alt". Defaults to the datagen columns
+    #[arg(long)]
+    columns: Option<ColumnMapping>,
+    /// OLAN for the filed sequence. When given, each figure is marked against it
+    #[arg(short, long, conflicts_with = "seq")]
+    olan: Option<String>,
+    /// OpenAero .seq file for the filed sequence, giving the K factors and presentation coefficients
+    #[arg(short, long)]
+    seq: Option<PathBuf>,
+    /// Category, which sets the height of the box: unlimited, advanced, intermediate, sports or primary
+    #[arg(long, default_value = "unlimited")]
+    category: Category,
+    /// Centre of the box at ground level in the flight data frame, as north,east,down (m)
+    #[arg(long, value_parser = parse_vector, default_value = "0,0,0", allow_hyphen_values = true)]
+    box_centre: Vector3<f32>,
+    /// Heading of the box X axis from A to B (degrees clockwise from north)
+    #[arg(long, default_value_t = 0.0)]
+    box_heading: f32,
+    /// Plot the flight in side, top and judge's views with the box, figures and elements to an SVG or PNG file
+    #[arg(long)]
+    plot: Option<PathBuf>,
     }
 
-fn generate_loop(start_point : Vector3<f32>, radius : f32, num_samples : i32) -> Vec<FlightDataPoint> {
-    let mut flight_data_points : Vec<FlightDataPoint> = Vec::new();
-
-    let origin = start_point + Vector3::<f32>::new(0.0,0.0,radius);
-
-    for i in 0..num_samples {
-        let theta = (i as f32) * 2.0*PI/ (num_samples as f32);
-        
-        let position = origin + Vector3::<f32>::new(
-            radius * theta.sin(),
-            0.0,
-            -radius * theta.cos()
-            );
-        
-        let attitude = UnitQuaternion::from_euler_angles( 0.0, theta, 0.0 );
-        
-        flight_data_points.push( FlightDataPoint {
-            position: position,
-            attitude: attitude 
-            });
+fn parse_vector(value: &str) -> Result<Vector3<f32>, String> {
+    let components = value.split(',')
+        .map(|component| component.trim().parse::<f32>().map_err(|error| error.to_string()))
+        .collect::<Result<Vec<f32>, String>>()?;
+    if components.len() != 3 {
+        return Err("expected three comma separated values".to_string());
         }
-    return flight_data_points;
+    Ok(Vector3::new(components[0], components[1], components[2]))
     }
-/*
-fn fit_line(points: Vec<FlightDataPoint>) -> arusti::Element {
-    // Arbitrary line in 3D space defined by a and b
-    // a = mean of points.position
-    let position_sum : Vector3<f32> = points.iter().map(|p| p.position).sum();
-    let count = points.len();
-    let a = position_sum / (count as f32);
-
-    // b = (points[0].position - points[end].position).norm()
-    let b = (points[0].position - points.last().unwrap().position).norm();
-    // angle between b and xy plane -> line angle
-    // roll should be either ~0 or ~180 for whole line
 
-    return arusti::Element {
-        elemType: arusti::ElementType::Line,
-        inverted: false,
-        angle: 0.0,
-        points: 0,
-        combining: Vec::<arusti::Element>::new()
+fn parse_origin(value: &str) -> Result<GeoOrigin, String> {
+    let components = value.split(',')
+        .map(|component| component.trim().parse::<f64>().map_err(|error| error.to_string()))
+        .collect::<Result<Vec<f64>, String>>()?;
+    if components.len() != 3 {
+        return Err("expected latitude,longitude,altitude".to_string());
         }
+    Ok(GeoOrigin::new(components[0], components[1], components[2]))
+    }
 
+/// The filed sequence and its coefficients, if given
+fn read_filed_sequence(cli: &Cli) -> Result<Option<(arusti::Sequence, Coefficients)>, String> {
+    if let Some(olan) = &cli.olan {
+        return Ok(Some((arusti::olan::try_parse_sequence(olan.clone())?, Coefficients::default())));
+        }
+    match &cli.seq {
+        Some(path) => {
+            let seq_file = SeqFile::load(path)?;
+            let sequence = arusti::olan::try_parse_sequence(seq_file.sequence_text.clone())?;
+            Ok(Some((sequence, Coefficients::from(&seq_file))))
+            }
+        None => Ok(None),
+        }
     }
-*/
 
-fn spilt_into_figures() {
-    /*
-    Split into time-based lengths
-    For each length score for Line 0 & Line 0 inverted
-    if score > threshold:
-        split()
-    */
+/// The element with its angles in whole degrees, without negative zero
+fn whole_degrees(element: Element) -> Element {
+    let round = |angle: f32| angle.round() + 0.0;
+    match element {
+        Element::Line { pitch, inverted } => Element::Line { pitch: round(pitch), inverted },
+        Element::Radius { angle, size } => Element::Radius { angle: round(angle), size },
+        Element::Turn { angle, rolls, roll_direction, inverted } => {
+            Element::Turn { angle: round(angle), rolls: round(rolls * 360.0) / 360.0, roll_direction, inverted }
+            },
+        Element::Roll { angle, direction, divisions, linked } => Element::Roll { angle: round(angle), direction, divisions, linked },
+        Element::Flick { angle, direction, negative, linked } => Element::Flick { angle: round(angle), direction, negative, linked },
+        Element::Spin { angle, direction, negative, linked } => Element::Spin { angle: round(angle), direction, negative, linked },
+        Element::Stall { yaw, pitch } => Element::Stall { yaw: round(yaw), pitch: round(pitch) },
+        Element::Combining(point) => Element::Combining(point),
+        }
     }
 
-fn main() {
-    
-/*
+fn run(cli: &Cli) -> Result<(), String> {
+    let file = fs::File::open(&cli.file).map_err(|error| format!("cannot read {}: {}", cli.file.display(), error))?;
+    let format = cli.format.or_else(|| ImportFormat::from_path(&cli.file)).unwrap_or(ImportFormat::Csv);
+    let import_options = ImportOptions {
+        origin: cli.origin,
+        columns: cli.columns.clone().unwrap_or_default(),
+        .. ImportOptions::default()
+        };
+    let points = arusti::import::read_points(format, file, &import_options).map_err(|error| format!("{}: {}", cli.file.display(), error))?;
+
+    /*
     Split flight data into figures
         Look for level flight lengths (possibly inverted)
     Split figure into elements
     Judge each element
     */
+    let options = SegmentationOptions::default();
+    let figures = segmentation::segment_figures(&points, &options);
+    let mut recognised = Vec::new();
+    for (i,figure) in figures.iter().enumerate() {
+        println!("Figure {} ({:.2} s to {:.2} s)", i + 1, points[figure.start].time, points[figure.end - 1].time);
+        let mut fits = Vec::new();
+        for segment in &figure.elements {
+            let time_range = format!("{:.2} s to {:.2} s", points[segment.start].time, points[segment.end - 1].time);
+            match fitting::fit_segment(&points, segment, &options) {
+                Some(fit) => {
+                    println!("    {} ({}, size {:.1} m, confidence {:.2})", whole_degrees(fit.element), time_range, fit.size, fit.confidence);
+                    fits.push(fit);
+                    }
+                None => println!("    {:?} ({})", segment.elem_type, time_range),
+                }
+            }
+
+        let candidates = recognition::recognise_figure(&fits);
+        let ranked: Vec<String> = candidates.iter()
+            .take(3)
+            .map(|candidate| format!("{} ({:.2})", candidate.olan, candidate.likelihood))
+            .collect();
+        println!("    Recognised as {}", ranked.join(", "));
+        recognised.push(candidates);
+        }
+    println!("OLAN: {}", recognition::best_guess(&recognised));
+
+    let box_frame = BoxFrame {
+        centre: cli.box_centre,
+        heading: cli.box_heading,
+        .. BoxFrame::new(cli.category)
+        };
+    if let Some(path) = &cli.plot {
+        let format = PlotFormat::from_path(path).ok_or_else(|| format!("cannot plot to {}, expected a .svg or .png file", path.display()))?;
+        let plot_options = PlotOptions { box_frame, .. PlotOptions::default() };
+        let file = fs::File::create(path).map_err(|error| format!("cannot create {}: {}", path.display(), error))?;
+        arusti::plot::write_plot(format, &points, &figures, &plot_options, io::BufWriter::new(file))
+            .map_err(|error| format!("cannot write plot: {}", error))?;
+        }
 
+    if let Some((sequence, coefficients)) = read_filed_sequence(cli)? {
+        let scoring_options = ScoringOptions {
+            judging: JudgingOptions { segmentation: options, .. JudgingOptions::default() },
+            box_frame,
+            .. ScoringOptions::default()
+            };
+        let score = scoring::score_sequence(&points, &sequence, &coefficients, &scoring_options);
+        for (i,mark) in score.figures.iter().enumerate() {
+            println!("Figure {}: {:.1} (K {})", i + 1, mark.mark, coefficients.figure_k(i));
+            for downgrade in &mark.downgrades {
+                match downgrade.element {
+                    Some(element) => println!("    -{:.1} {} at {:.2} s ({})", downgrade.points, downgrade.fault, downgrade.time, element),
+                    None => println!("    -{:.1} {} at {:.2} s", downgrade.points, downgrade.fault, downgrade.time),
+                    }
+                }
+            }
+        let presentation = [
+            ("Positioning", &score.positioning, coefficients.positioning),
+            ("Harmony", &score.harmony, coefficients.harmony),
+            ];
+        for (name, mark, coefficient) in presentation.iter() {
+            println!("{}: {:.1} (K {})", name, mark.mark, coefficient);
+            for (fault, deduction) in &mark.deductions {
+                println!("    -{:.1} {}", deduction, fault);
+                }
+            }
+        for event in &score.events {
+            println!("{:?} {:.2} s to {:.2} s, {:.0} m outside the {}", event.kind(), points[event.start].time, points[event.end - 1].time, event.distance, event.edge);
+            }
+        println!("Penalties: {:.1}", score.penalties);
+        println!("Total: {:.1} of {:.1}", score.total, score.maximum);
+        }
+    Ok(())
+    }
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("autojudge: {}", error);
+            ExitCode::from(1)
+            }
+        }
     }
@@ -1,37 +1,418 @@
 use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand};
 
 use arusti;
+use arusti::{AircraftProfile, BoxFrame, Category, PlotFormat, PlotOptions, RuleSet, SeqFile, Sequence, Vector3};
+use arusti::box_frame::BoxDirection;
+
+/// Exit status for a sequence that is invalid or differs from another
+const EXIT_FAILED: u8 = 1;
+/// Exit status for an error such as an unreadable file or OLAN that does not parse. Usage errors exit with 2
+const EXIT_ERROR: u8 = 3;
+
+const EXIT_STATUS_HELP: &str = "Exit status:
+  0  success
+  1  validate or diagnose found the sequence invalid, or diff found differences
+  2  the command line is invalid
+  3  an error, e.g. an unreadable file or OLAN that does not parse";
+
+/// Parse OLAN sequences and generate simulated flight data for them
+#[derive(Parser)]
+#[command(name = "datagen", version, after_help = EXIT_STATUS_HELP)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    }
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the figures and elements of a sequence
+    Parse {
+        #[command(flatten)]
+        input: Input,
+        },
+    /// Generate flight data for a sequence
+    Generate(GenerateArgs),
+    /// Check a sequence for errors and against the rules of its category
+    Validate(ValidateArgs),
+    /// Explain the corrections the parser makes to a sequence and suggest fixes for invalid OLAN
+    Diagnose {
+        #[command(flatten)]
+        input: Input,
+        },
+    /// Draw the Aresti diagram of a sequence as SVG
+    Draw(DrawArgs),
+    /// List the figures added, removed, moved and changed between two sequences
+    Diff(DiffArgs),
+    /// List the built-in aircraft profiles
+    Profiles,
+    }
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+struct Input {
+    /// OpenAero .seq file, or text file containing an OLAN string
+    #[arg(short, long)]
+    file: Option<PathBuf>,
+    /// OLAN string
+    #[arg(short, long)]
+    olan: Option<String>,
+    }
+
+#[derive(Args)]
+struct GenerateArgs {
+    #[command(flatten)]
+    input: Input,
+
+    /// Built-in aircraft profile name, or path to a TOML or JSON profile
+    #[arg(long, default_value = "rc_sport")]
+    profile: String,
+    /// Airspeed (m/s). Overrides the profile
+    #[arg(long)]
+    speed: Option<f32>,
+    /// Roll rate (deg/s). Overrides the profile
+    #[arg(long)]
+    roll_rate: Option<f32>,
+    /// Snap roll rate (deg/s). Overrides the profile
+    #[arg(long)]
+    snap_rate: Option<f32>,
+    /// Spin rate (deg/s). Overrides the profile
+    #[arg(long)]
+    spin_rate: Option<f32>,
+    /// Looping pitch rate (deg/s). Overrides the profile
+    #[arg(long)]
+    pitch_rate: Option<f32>,
+    /// Turn rate (deg/s). Overrides the profile
+    #[arg(long)]
+    turn_rate: Option<f32>,
+    /// Pause at each hesitation (s). Overrides the profile
+    #[arg(long)]
+    hesitation_time: Option<f32>,
+
+    /// Wind in the earth frame as north,east,down (m/s)
+    #[arg(long, value_parser = parse_vector, default_value = "0,0,0", allow_hyphen_values = true)]
+    wind: Vector3<f32>,
+    /// Start at the A end of the box for this category, halfway between its bottom and top and flying towards B.
+    ///  Otherwise the flight starts at the origin heading north
+    #[arg(long)]
+    category: Option<Category>,
+    /// Heading of the box X axis from A to B (degrees clockwise from north)
+    #[arg(long, default_value_t = 0.0, requires = "category")]
+    box_heading: f32,
+    /// Samples per second (Hz)
+    #[arg(long, default_value_t = 50.0)]
+    sample_rate: f32,
+
+    /// Seed for the sensor noise
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Standard deviation of position noise (m)
+    #[arg(long, default_value_t = 0.0)]
+    position_noise: f32,
+    /// Standard deviation of attitude noise (degrees)
+    #[arg(long, default_value_t = 0.0)]
+    attitude_noise: f32,
+
+    /// Output format: csv, parquet, igc, kml or gltf
+    #[arg(long, default_value = "csv")]
+    format: arusti::ExportFormat,
+    /// Output file. Defaults to stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
+    /// Also plot the flight, with the box for --category, to an SVG or PNG file
+    #[arg(long)]
+    plot: Option<PathBuf>,
+    }
+
+#[derive(Args)]
+struct ValidateArgs {
+    #[command(flatten)]
+    input: Input,
+
+    /// Built-in rule set name, or path to a TOML or JSON rule set. Defaults to the rules of a .seq file
+    #[arg(long)]
+    rules: Option<String>,
+    /// Category in the rule set. Defaults to the category of a .seq file
+    #[arg(long)]
+    category: Option<String>,
+    /// Programme in the rule set, e.g. "Free Known". Defaults to the program of a .seq file
+    #[arg(long)]
+    program: Option<String>,
+    }
+
+#[derive(Args)]
+struct DrawArgs {
+    #[command(flatten)]
+    input: Input,
+
+    /// Form to draw: b for the judges' view or c for the pilot's
+    #[arg(long, default_value = "b")]
+    form: arusti::Form,
+    /// SVG pixels per diagram unit
+    #[arg(long, default_value_t = 3.0)]
+    scale: f32,
+    /// Leave out the figure numbers
+    #[arg(long)]
+    no_numbers: bool,
+    /// Output file. Defaults to stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
+    }
+
+#[derive(Args)]
+struct DiffArgs {
+    /// Old sequence: an OLAN string, or path to an OpenAero .seq file or text file containing an OLAN string
+    before: String,
+    /// New sequence, in the same form as the old
+    after: String,
+    /// Print the differences as JSON
+    #[arg(long)]
+    json: bool,
+    }
+
+fn parse_vector(value: &str) -> Result<Vector3<f32>, String> {
+    let components = value.split(',')
+        .map(|component| component.trim().parse::<f32>().map_err(|error| error.to_string()))
+        .collect::<Result<Vec<f32>, String>>()?;
+    if components.len() != 3 {
+        return Err("expected three comma separated values".to_string());
+        }
+    Ok(Vector3::new(components[0], components[1], components[2]))
+    }
+
+/// Read the OLAN string from the input, extracting it from the sequence_text of .seq files.
+///  Also returns the .seq file, if the input is one
+fn read_olan(input: &Input) -> Result<(String, Option<SeqFile>), String> {
+    if let Some(olan) = &input.olan {
+        return Ok((olan.clone(), None));
+        }
+
+    let path = input.file.as_ref().unwrap();
+    let contents = fs::read_to_string(path).map_err(|error| format!("cannot read {}: {}", path.display(), error))?;
+    if contents.trim_start().starts_with('<') {
+        let seq_file = SeqFile::parse(&contents)?;
+        Ok((seq_file.sequence_text.clone(), Some(seq_file)))
+        }
+    else {
+        Ok((contents.trim().to_string(), None))
+        }
+    }
+
+fn read_sequence(input: &Input) -> Result<(Sequence, Option<SeqFile>), String> {
+    let (sequence_str, seq_file) = read_olan(input)?;
+    let sequence = arusti::olan::try_parse_sequence(sequence_str)?;
+    log::debug!("{:#?}",sequence);
+    Ok((sequence, seq_file))
+    }
+
+fn parse(input: &Input) -> Result<(), String> {
+    let (sequence, _) = read_sequence(input)?;
+    for (i,figure) in sequence.figures.iter().enumerate() {
+        println!("Figure {}", i + 1);
+        for element in figure {
+            println!("    {}", element);
+            }
+        }
+    Ok(())
+    }
+
+/// Find a built-in profile by name, otherwise load it from a file
+fn read_profile(profile: &str) -> Result<AircraftProfile, String> {
+    match AircraftProfile::builtin(profile) {
+        Some(profile) => Ok(profile),
+        None if Path::new(profile).exists() => AircraftProfile::load(Path::new(profile)),
+        None => Err(format!("unknown profile {}, expected one of {}", profile, AircraftProfile::builtin_names().join(", "))),
+        }
+    }
 
-fn main() {
-    // Open file
-    let unparsed_file = fs::read_to_string("tests/resources/Example.seq").expect("cannot read file");
-    let doc = roxmltree::Document::parse(&unparsed_file).unwrap();
-    let elem = doc.descendants().find(|n| n.tag_name().name() == "sequence_text").unwrap();
-
-    // Parse OLAN
-    let sequence_str = elem.text().unwrap().to_string();
-    //let sequence_str = "/dq v .''s.''irp...'-~ ~----2j- [0,20] -'',24'' 2> c,24.... [0,22] ~+v-- 4> -id2 2> ''1''m2.' [0,20] ~~++++++2j2 f,2- -22a44".to_string();
-    //let sequence_str = "dq v irp 2j o".to_string();
-    let sequence = arusti::olan::parse_sequence(sequence_str);
-    eprintln!("{:#?}",sequence);
-    
-    let mut generator = arusti::DataPointGenerator::new(
-        arusti::Vector3::x() * 18.0,
-        arusti::Vector3::zeros(),
-        arusti::PerformanceOptions{
-            roll_rate: 180.0,
-            snap_rate: 360.0,
-            spin_rate: 90.0,
-            turn_rate: 22.5,
-            hesitation_time: 0.2,
-            });
-        
-    let points = generator.generate_points(&sequence);
-
-    for point in points {
-        println!("{},{},{}",point.position[0],point.position[1],point.position[2]);
+fn generate(args: &GenerateArgs) -> Result<(), String> {
+    let (sequence, _) = read_sequence(&args.input)?;
+
+    let profile = read_profile(&args.profile)?;
+    let mut performance = profile.performance_options();
+    performance.roll_rate = args.roll_rate.unwrap_or(performance.roll_rate);
+    performance.snap_rate = args.snap_rate.unwrap_or(performance.snap_rate);
+    performance.spin_rate = args.spin_rate.unwrap_or(performance.spin_rate);
+    performance.pitch_rate = args.pitch_rate.unwrap_or(performance.pitch_rate);
+    performance.turn_rate = args.turn_rate.unwrap_or(performance.turn_rate);
+    performance.hesitation_time = args.hesitation_time.unwrap_or(performance.hesitation_time);
+    if let Some(speed) = args.speed {
+        profile.check_speed(speed).map_err(|error| format!("--speed for {}: {}", profile.name, error))?;
+        }
+    let speed = args.speed.unwrap_or(profile.speeds.entry);
+
+    let mut builder = arusti::DataPointGenerator::builder(performance)
+        .velocity(Vector3::x() * speed)
+        .wind(args.wind)
+        .sample_rate(args.sample_rate);
+    let box_frame = args.category.map(|category| BoxFrame { heading: args.box_heading, .. BoxFrame::new(category) });
+    if let Some(box_frame) = &box_frame {
+        let entry = Vector3::new(-box_frame.length / 2.0, 0.0, (box_frame.bottom + box_frame.top) / 2.0);
+        builder = builder.box_entry(box_frame, entry, BoxDirection::TowardsB);
+        }
+    let mut generator = builder.build();
+
+    let mut noise = arusti::SensorNoise::new(args.seed, args.position_noise, args.attitude_noise);
+    let points: Vec<arusti::DataPoint> = generator.iter_points(&sequence)
+        .map(|mut point| { noise.apply(&mut point); point })
+        .collect();
+
+    if let Some(path) = &args.plot {
+        let format = PlotFormat::from_path(path).ok_or_else(|| format!("cannot plot to {}, expected a .svg or .png file", path.display()))?;
+        let figures = arusti::segmentation::segment_figures(&points, &arusti::SegmentationOptions::default());
+        let options = PlotOptions {
+            box_frame: box_frame.unwrap_or_default(),
+            show_box: box_frame.is_some(),
+            .. PlotOptions::default()
+            };
+        let file = fs::File::create(path).map_err(|error| format!("cannot create {}: {}", path.display(), error))?;
+        arusti::plot::write_plot(format, &points, &figures, &options, io::BufWriter::new(file))
+            .map_err(|error| format!("cannot write plot: {}", error))?;
+        }
+
+    let result = match &args.output {
+        Some(path) => {
+            let file = fs::File::create(path).map_err(|error| format!("cannot create {}: {}", path.display(), error))?;
+            arusti::export::write_points(args.format, &points, io::BufWriter::new(file))
+            }
+        None => arusti::export::write_points(args.format, &points, io::stdout()),
+        };
+    result.map_err(|error| format!("cannot write points: {}", error))
+    }
+
+/// Find a built-in rule set by name, otherwise load it from a file
+fn read_rules(rules: &str) -> Result<RuleSet, String> {
+    match RuleSet::builtin(rules) {
+        Some(rules) => Ok(rules),
+        None if Path::new(rules).exists() => RuleSet::load(Path::new(rules)),
+        None => Err(format!("unknown rules {}, expected one of {}", rules, RuleSet::builtin_names().join(", "))),
+        }
+    }
+
+/// Report problems with the sequence. Returns whether it is valid
+fn validate(args: &ValidateArgs) -> Result<bool, String> {
+    let (sequence, seq_file) = read_sequence(&args.input)?;
+    let breaks = sequence.continuity_breaks();
+    for figure_idx in &breaks {
+        println!("Figure {}: entry does not continue the inverted state of the previous exit", figure_idx + 1);
+        }
+
+    let from_file = |arg: &Option<String>, field: fn(&SeqFile) -> &Option<String>| {
+        arg.clone().or_else(|| seq_file.as_ref().and_then(|seq_file| field(seq_file).clone()))
+        };
+    let mut violations = Vec::new();
+    if let Some(rules) = from_file(&args.rules, |seq_file| &seq_file.rules) {
+        let rule_set = read_rules(&rules)?;
+        let category = from_file(&args.category, |seq_file| &seq_file.category).ok_or("no category to check the rules of")?;
+        let program = from_file(&args.program, |seq_file| &seq_file.program).ok_or("no program to check the rules of")?;
+        let program_rules = rule_set.program(&category, &program)
+            .ok_or_else(|| format!("no {} rules for {} {}", rule_set.name, category, program))?;
+        let catalogue = seq_file.as_ref().map(|seq_file| seq_file.figures.as_slice()).unwrap_or(&[]);
+        violations = program_rules.check(&sequence, catalogue);
+        for violation in &violations {
+            println!("{}", violation);
+            }
+        }
+
+    let valid = breaks.is_empty() && violations.is_empty();
+    if valid {
+        println!("Sequence of {} figures is valid", sequence.figures.len());
+        }
+    Ok(valid)
+    }
+
+fn diagnose(input: &Input) -> Result<bool, String> {
+    let (olan, _) = read_olan(input)?;
+    let diagnostics = arusti::olan::diagnose(&olan);
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
         }
+    let corrected = arusti::olan::diagnostics::corrected(&olan, &diagnostics);
+    if corrected != olan {
+        println!("Suggested OLAN: {}", corrected);
+        }
+    if diagnostics.is_empty() {
+        println!("No corrections needed");
+        }
+    Ok(diagnostics.is_empty())
+    }
+
+fn draw(args: &DrawArgs) -> Result<(), String> {
+    let (sequence, _) = read_sequence(&args.input)?;
+    let options = arusti::DiagramOptions {
+        form: args.form,
+        scale: args.scale,
+        numbers: !args.no_numbers,
+        };
+    let result = match &args.output {
+        Some(path) => {
+            let file = fs::File::create(path).map_err(|error| format!("cannot create {}: {}", path.display(), error))?;
+            arusti::diagram::write_svg(&sequence, &options, io::BufWriter::new(file))
+            }
+        None => arusti::diagram::write_svg(&sequence, &options, io::stdout()),
+        };
+    result.map_err(|error| format!("cannot write diagram: {}", error))
+    }
+
+/// Read a sequence given either as OLAN or as the path to a file containing it
+fn read_olan_or_file(sequence: &str) -> Result<Sequence, String> {
+    let input = if Path::new(sequence).is_file() {
+        Input { file: Some(PathBuf::from(sequence)), olan: None }
+        }
+    else {
+        Input { file: None, olan: Some(sequence.to_string()) }
+        };
+    read_sequence(&input).map(|(sequence, _)| sequence)
+    }
+
+fn diff(args: &DiffArgs) -> Result<bool, String> {
+    let before = read_olan_or_file(&args.before)?;
+    let after = read_olan_or_file(&args.after)?;
+    let diff = before.diff(&after);
+    if args.json {
+        println!("{}", diff.to_json());
+        }
+    else if diff.is_empty() {
+        println!("Sequences are the same");
+        }
+    else {
+        println!("{}", diff);
+        }
+    Ok(diff.is_empty())
+    }
+
+fn list_profiles() {
+    for name in AircraftProfile::builtin_names() {
+        let profile = AircraftProfile::builtin(name).unwrap();
+        println!("{:<12}{}", name, profile.name);
+        }
+    }
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match &cli.command {
+        Command::Parse { input } => parse(input).map(|_| true),
+        Command::Generate(args) => generate(args).map(|_| true),
+        Command::Validate(args) => validate(args),
+        Command::Diagnose { input } => diagnose(input),
+        Command::Draw(args) => draw(args).map(|_| true),
+        Command::Diff(args) => diff(args),
+        Command::Profiles => { list_profiles(); Ok(true) },
+        };
 
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(EXIT_FAILED),
+        Err(error) => {
+            eprintln!("datagen: {}", error);
+            ExitCode::from(EXIT_ERROR)
+            }
+        }
     }
 
 /*
@@ -44,7 +425,4 @@ l
 u
 x
 
-The grammar currently will not match trailing letters it does not
-recognize so letters appended to current figures should work
-
-*/
\ No newline at end of file
+*/
@@ -0,0 +1,92 @@
+use arusti::{BoxFrame,Category,DataPoint,DataPointGenerator,Vector3};
+use arusti::box_frame::{BoxDirection,BoxEdge,BoxEventKind};
+use arusti::olan::parse_sequence;
+
+mod common;
+
+fn assert_close(actual: &Vector3<f32>, expected: &Vector3<f32>) {
+    assert!((actual - expected).norm() < 1.0e-3, "{:?} != {:?}", actual, expected);
+    }
+
+#[test]
+fn categories() {
+    assert_eq!("Intermediate".parse::<Category>(), Ok(Category::Intermediate));
+    assert_eq!("sportsman".parse::<Category>(), Ok(Category::Sports));
+    assert!("glider".parse::<Category>().is_err());
+
+    let unlimited = BoxFrame::new(Category::Unlimited);
+    assert_eq!((unlimited.bottom, unlimited.top), (100.0, 1000.0));
+    assert_eq!(BoxFrame::default(), unlimited);
+    let categories = [Category::Unlimited, Category::Advanced, Category::Intermediate, Category::Sports, Category::Primary];
+    for pair in categories.windows(2) {
+        assert!(pair[0].altitudes().0 < pair[1].altitudes().0);
+        }
+    }
+
+#[test]
+fn conversions() {
+    let box_frame = BoxFrame {
+        centre: Vector3::new(100.0, -50.0, 20.0),
+        heading: 90.0,
+        .. BoxFrame::default()
+        };
+    // X axis runs east, Y axis away from the judges runs north, z is height above the box ground level
+    assert_close(&box_frame.to_box(&Vector3::new(100.0, 150.0, -280.0)), &Vector3::new(200.0, 0.0, 300.0));
+    assert_close(&box_frame.to_box(&Vector3::new(130.0, -50.0, 20.0)), &Vector3::new(0.0, 30.0, 0.0));
+
+    let position = Vector3::new(-320.0, 75.0, 410.0);
+    assert_close(&box_frame.to_box(&box_frame.to_earth(&position)), &position);
+
+    assert_eq!(box_frame.earth_heading(BoxDirection::TowardsB), 90.0);
+    assert_eq!(box_frame.earth_heading(BoxDirection::TowardsA), 270.0);
+    assert_eq!(box_frame.earth_heading(BoxDirection::TowardsJudges), 180.0);
+    assert_eq!(box_frame.earth_heading(BoxDirection::AwayFromJudges), 0.0);
+
+    // Judges 150 m south of the near side
+    assert_close(&box_frame.judge_position(), &Vector3::new(-550.0, -50.0, 20.0));
+    }
+
+#[test]
+fn contains() {
+    let box_frame = BoxFrame::new(Category::Advanced);
+    assert!(box_frame.contains(&Vector3::new(0.0, 0.0, -500.0)));
+    assert!(!box_frame.contains(&Vector3::new(0.0, 0.0, -150.0)));
+    assert!(!box_frame.contains(&Vector3::new(0.0, 0.0, -1200.0)));
+    assert!(!box_frame.contains(&Vector3::new(510.0, 0.0, -500.0)));
+    assert!(!box_frame.contains(&Vector3::new(0.0, -510.0, -500.0)));
+    }
+
+#[test]
+fn generated_box_events() {
+    let box_frame = BoxFrame {
+        heading: 45.0,
+        .. BoxFrame::new(Category::Intermediate)
+        };
+    let entry = Vector3::new(-400.0, 0.0, 320.0);
+    let mut generator = DataPointGenerator::builder(common::performance())
+        .velocity(Vector3::x() * 18.0)
+        .box_entry(&box_frame, entry, BoxDirection::TowardsB)
+        .build();
+    let points: Vec<DataPoint> = generator.generate_points(&parse_sequence("o".to_string()));
+    // The first sample is one step along the X axis
+    assert!((box_frame.to_box(&points[0].position) - entry).norm() < 0.5);
+
+    // The bottom of the loop is level with the entry, 20 m above the box
+    let events = box_frame.box_events(&points);
+    assert!(events.is_empty(), "{:?}", events);
+
+    let low = BoxFrame { bottom: 330.0, .. box_frame };
+    let events = low.box_events(&points);
+    assert!(!events.is_empty());
+    for event in &events {
+        assert_eq!(event.edge, BoxEdge::Bottom);
+        assert_eq!(event.kind(), BoxEventKind::LowAltitude);
+        assert!(event.distance > 9.0 && event.distance <= 10.0 + 1.0e-3, "{:?}", event);
+        }
+
+    let short = BoxFrame { length: 700.0, .. box_frame };
+    let events = short.box_events(&points);
+    assert_eq!(events[0].edge, BoxEdge::A);
+    assert_eq!(events[0].start, 0);
+    assert!(events[0].distance > 49.5, "{:?}", events);
+    }
@@ -0,0 +1,23 @@
+//! Fixtures shared by the integration tests. Not every test uses all of them
+#![allow(dead_code)]
+
+use arusti::{DataPointGenerator,PerformanceOptions,Vector3};
+
+/// Rates of a slow aircraft, round numbers so that element times are easy to check
+pub fn performance() -> PerformanceOptions {
+    PerformanceOptions {
+        roll_rate: 180.0,
+        snap_rate: 360.0,
+        spin_rate: 90.0,
+        pitch_rate: 45.0,
+        turn_rate: 22.5,
+        hesitation_time: 0.2,
+        snap_entry_pitch: 20.0,
+        snap_entry_time: 0.0,
+        }
+    }
+
+/// Generator flying at 18 m/s in still air
+pub fn generator() -> DataPointGenerator {
+    DataPointGenerator::new(Vector3::x() * 18.0, Vector3::zeros(), performance())
+    }
@@ -0,0 +1,199 @@
+use arusti::{DataPointGenerator,Vector3,UnitQuaternion};
+
+mod common;
+
+#[test]
+fn builder_initial_state() {
+    let sequence = arusti::olan::parse_sequence("1".to_string());
+
+    let mut generator = DataPointGenerator::builder(common::performance())
+        .velocity(Vector3::x() * 20.0)
+        .sample_rate(10.0)
+        .position(Vector3::new(-500.0, 150.0, -300.0))
+        .heading(90.0)
+        .start_time(100.0)
+        .build();
+    let points = generator.generate_points(&sequence);
+
+    // First sample is one period after the start
+    assert!((points[0].time - 100.1).abs() < 1e-3);
+    assert!((points[1].time - points[0].time - 0.1).abs() < 1e-3);
+
+    // Heading east, so first step moves along +y at constant height
+    let step = points[0].position - Vector3::new(-500.0, 150.0, -300.0);
+    assert!((step - Vector3::new(0.0, 2.0, 0.0)).norm() < 1e-3, "Unexpected first step {:?}", step);
+
+    let heading = UnitQuaternion::from_euler_angles(0.0, 0.0, 90.0_f32.to_radians());
+    assert!(points[0].attitude.angle_to(&heading) < 1e-3);
+    }
+
+#[test]
+fn builder_initial_attitude() {
+    let sequence = arusti::olan::parse_sequence("1".to_string());
+    let inverted_west = UnitQuaternion::from_euler_angles(180.0_f32.to_radians(), 0.0, 270.0_f32.to_radians());
+
+    let mut generator = DataPointGenerator::builder(common::performance())
+        .velocity(Vector3::x() * 20.0)
+        .attitude(inverted_west)
+        .build();
+    let points = generator.generate_points(&sequence);
+
+    assert!(points[0].position[1] < 0.0);
+    assert!(points[0].attitude.angle_to(&inverted_west) < 1e-3);
+    }
+
+#[test]
+fn vertical_quarter_roll() {
+    // Euler angles are singular on the vertical line, so the roll would be lost
+    let sequence = arusti::olan::parse_sequence("v4".to_string());
+
+    let mut generator = DataPointGenerator::new(Vector3::x() * 20.0, Vector3::zeros(), common::performance());
+    let points = generator.generate_points(&sequence);
+
+    // Peak pitch is vertical
+    let max_climb = points.iter()
+        .map(|p| -(p.attitude * Vector3::x())[2])
+        .fold(-1.0, f32::max);
+    assert!(max_climb > 0.99, "Never reached the vertical ({})", max_climb);
+
+    // Quarter roll on the vertical exits level, 90° off the entry heading
+    let exit_direction = points.last().unwrap().attitude * Vector3::x();
+    assert!(exit_direction[2].abs() < 0.1, "Exit is not level: {:?}", exit_direction);
+    assert!(exit_direction[1].abs() > 0.95, "Exit is not crosswind: {:?}", exit_direction);
+    }
+
+#[test]
+fn wind_drift() {
+    let sequence = arusti::olan::parse_sequence("o".to_string());
+    let wind = Vector3::new(0.0, 5.0, 0.0);
+
+    let still = DataPointGenerator::new(Vector3::x() * 20.0, Vector3::zeros(), common::performance()).generate_points(&sequence);
+    let windy = DataPointGenerator::new(Vector3::x() * 20.0, wind, common::performance()).generate_points(&sequence);
+
+    // The same flight through the air, carried downwind for its duration
+    let exit = windy.last().unwrap();
+    let drift = exit.position - still.last().unwrap().position;
+    assert!((drift - wind * exit.time).norm() < 1e-2, "Unexpected drift {:?}", drift);
+    assert!(exit.attitude.angle_to(&still.last().unwrap().attitude) < 1e-6);
+    }
+
+fn generate(olan: &str) -> Vec<arusti::DataPoint> {
+    let sequence = arusti::olan::parse_sequence(olan.to_string());
+    let mut generator = DataPointGenerator::new(Vector3::x() * 20.0, Vector3::zeros(), common::performance());
+    generator.generate_points(&sequence)
+    }
+
+#[test]
+fn loop_closure() {
+    let points = generate("o");
+    let exit = points.last().unwrap();
+
+    // Loop returns to entry height and attitude
+    assert!(exit.attitude.angle() < 1e-3, "Residual attitude {}", exit.attitude.angle().to_degrees());
+    assert!(exit.position[2].abs() < 1e-2, "Loop does not close: {:?}", exit.position);
+    assert!(exit.position[1].abs() < 1e-2, "Loop does not close: {:?}", exit.position);
+
+    // Entry and exit lines are each one second long
+    assert!((exit.position[0] - 40.0).abs() < 1e-2, "Loop does not close: {:?}", exit.position);
+    }
+
+#[test]
+fn roll_closure() {
+    let points = generate("1");
+    let exit = points.last().unwrap();
+
+    assert!(exit.attitude.angle() < 1e-3, "Residual bank {}", exit.attitude.angle().to_degrees());
+    assert!((exit.position - Vector3::new(exit.position[0], 0.0, 0.0)).norm() < 1e-3);
+    }
+
+#[test]
+fn turn_closure() {
+    let points = generate("2j");
+    let exit = points.last().unwrap();
+
+    let reversed = UnitQuaternion::from_euler_angles(0.0, 0.0, 180.0_f32.to_radians());
+    assert!(exit.attitude.angle_to(&reversed) < 1e-3, "Residual attitude {}", exit.attitude.angle_to(&reversed).to_degrees());
+    assert!(exit.position[2].abs() < 1e-3, "Height lost in turn: {:?}", exit.position);
+    }
+
+#[test]
+fn flick_and_spin_closure() {
+    // Autorotation ends in the attitude of a plain roll through the same angle
+    let inverted = UnitQuaternion::from_euler_angles(180.0_f32.to_radians(), 0.0, 0.0);
+    for olan in ["2f", "2if", "2s", "2is"].iter() {
+        let points = generate(olan);
+        let exit = points.last().unwrap();
+        assert!(exit.attitude.angle_to(&inverted) < 1e-3, "Residual attitude after {} {}", olan, exit.attitude.angle_to(&inverted).to_degrees());
+        }
+    }
+
+#[test]
+fn sample_clock() {
+    let points = generate("o 2j 1");
+    for (previous,current) in points.iter().zip(points.iter().skip(1)) {
+        assert!((current.time - previous.time - 0.02).abs() < 1e-3);
+        }
+    }
+
+#[test]
+fn streamed_points_match_generated() {
+    let sequence = arusti::olan::parse_sequence("o 2j 1".to_string());
+
+    let mut generator = DataPointGenerator::new(Vector3::x() * 20.0, Vector3::zeros(), common::performance());
+    let points = generator.generate_points(&sequence);
+
+    let mut generator = DataPointGenerator::new(Vector3::x() * 20.0, Vector3::zeros(), common::performance());
+    let mut count = 0;
+    for (streamed,generated) in generator.iter_points(&sequence).zip(points.iter()) {
+        assert_eq!(streamed.time, generated.time);
+        assert_eq!(streamed.position, generated.position);
+        assert_eq!(streamed.attitude, generated.attitude);
+        count += 1;
+        }
+    assert_eq!(count, points.len());
+    }
+
+#[test]
+fn streaming_continues_flight() {
+    let sequence = arusti::olan::parse_sequence("o".to_string());
+    let whole = generate("o");
+
+    let mut generator = DataPointGenerator::new(Vector3::x() * 20.0, Vector3::zeros(), common::performance());
+    let first = generator.iter_points(&sequence).take(10).count();
+    assert_eq!(first, 10);
+
+    // The rest of the figure that was started comes before the next flight of the sequence
+    let rest = generator.generate_points(&sequence);
+    assert_eq!(rest.len(), 2 * whole.len() - 10);
+    for (resumed, expected) in rest.iter().zip(whole.iter().skip(10)) {
+        assert_eq!(resumed.time, expected.time);
+        assert_eq!(resumed.position, expected.position);
+        }
+    }
+
+#[test]
+fn sensor_noise() {
+    let mut points = generate("o");
+    let perfect = generate("o");
+
+    let mut noise = arusti::SensorNoise::new(42, 1.0, 2.0);
+    for point in points.iter_mut() {
+        noise.apply(point);
+        }
+
+    let count = points.len() as f32;
+    let position_errors: Vec<Vector3<f32>> = points.iter().zip(perfect.iter()).map(|(n,p)| n.position - p.position).collect();
+    let mean = position_errors.iter().sum::<Vector3<f32>>() / count;
+    let variance = position_errors.iter().map(|e| e.norm_squared()).sum::<f32>() / (3.0 * count);
+    assert!(mean.norm() < 0.2, "Noise is biased: {:?}", mean);
+    assert!((variance.sqrt() - 1.0).abs() < 0.1, "Noise has wrong deviation: {}", variance.sqrt());
+
+    // Same seed gives the same noise
+    let mut repeat = generate("o");
+    let mut noise = arusti::SensorNoise::new(42, 1.0, 2.0);
+    for point in repeat.iter_mut() {
+        noise.apply(point);
+        }
+    assert_eq!(repeat[10].position, points[10].position);
+    assert_eq!(repeat[10].attitude, points[10].attitude);
+    }
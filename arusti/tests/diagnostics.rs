@@ -0,0 +1,66 @@
+use arusti::olan::{diagnose,DiagnosticKind};
+use arusti::olan::diagnostics::corrected;
+
+fn kinds(olan: &str) -> Vec<DiagnosticKind> {
+    diagnose(olan).into_iter().map(|diagnostic| diagnostic.kind).collect()
+    }
+
+#[test]
+fn exit_corrections() {
+    let diagnostics = diagnose("o 2a2 -o-");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].figure, Some(1));
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ExitInverted { radius: -180.0, inverted: false });
+    assert_eq!(diagnostics[0].suggestions, vec!["2a2-"]);
+    assert_eq!(diagnostics[0].to_string(), "Figure 2 `2a2`: exit line upright via push on last 180° radius; did you mean `2a2-`?");
+
+    // No radius to flip
+    assert_eq!(kinds("2"), vec![DiagnosticKind::ExitMismatch { inverted: false }]);
+    assert!(diagnose("o 2a2- -o-").is_empty());
+    }
+
+#[test]
+fn suggestions_keep_continuity() {
+    // Flying the outside loop keeps the upright entry after the first loop
+    let diagnostics = diagnose("o -o");
+    assert_eq!(diagnostics[0].suggestions, vec!["io"]);
+    assert_eq!(corrected("o -o", &diagnostics), "o io");
+
+    // A push out of the vertical line to meet the inverted entry of the next figure is explained without a suggestion
+    let diagnostics = diagnose("o v- -o-");
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0].suggestions.is_empty());
+    }
+
+#[test]
+fn notation_mistakes() {
+    let olan = "O 1/2 2x4";
+    let diagnostics = diagnose(olan);
+    assert!(diagnostics.iter().any(|diagnostic| diagnostic.kind == DiagnosticKind::Capitals));
+    assert!(diagnostics.iter().any(|diagnostic| diagnostic.kind == DiagnosticKind::RollNotation { written: "1/2".to_string(), olan: "2".to_string() }));
+    assert!(diagnostics.iter().any(|diagnostic| matches!(diagnostic.kind, DiagnosticKind::Syntax(_))));
+    assert_eq!(corrected(olan, &diagnostics), "o 2 24");
+
+    assert_eq!(corrected("c(2 o", &diagnose("c(2 o")), "c(2) o");
+    assert_eq!(corrected("rcc(2)4) (3,-4 o", &diagnose("rcc(2)4) (3,-4 o")), "rcc(2)4 (3,-4) o");
+    assert_eq!(corrected("d1/8", &diagnose("d1/8")), "d18");
+    }
+
+#[test]
+fn prefixes_and_unparsed_input() {
+    let diagnostics = diagnose("ih");
+    assert!(matches!(diagnostics[0].kind, DiagnosticKind::Syntax(_)));
+    assert_eq!(diagnostics[0].suggestions, vec!["h"]);
+
+    // The grammar stops at the first word it cannot match and would silently drop the rest
+    let diagnostics = diagnose("o 2ia2 o");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::Unparsed);
+    assert_eq!(diagnostics[0].text, "2ia2");
+    assert_eq!(diagnostics[0].suggestions, vec!["2a2"]);
+
+    // 8 alone is not a roll
+    assert!(arusti::olan::try_parse_sequence("8".to_string()).is_err());
+    assert_eq!(kinds("o8"), vec![DiagnosticKind::Unparsed]);
+    assert!(diagnose("/dq v irp 2j o").is_empty());
+    }
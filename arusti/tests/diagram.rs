@@ -0,0 +1,101 @@
+use arusti::{DiagramOptions,Form,SeqFile};
+use arusti::diagram::{draw,write_svg,Shape,Stroke};
+use arusti::olan::parse_sequence;
+
+fn shapes(olan: &str) -> Vec<Shape> {
+    draw(&parse_sequence(olan.to_string()), &DiagramOptions::default())
+    }
+
+fn dots(shapes: &[Shape]) -> Vec<(f32, f32)> {
+    shapes.iter()
+        .filter_map(|shape| match shape { Shape::Dot { centre, .. } => Some((centre[0], centre[1])), _ => None })
+        .collect()
+    }
+
+fn labels(shapes: &[Shape]) -> Vec<&str> {
+    shapes.iter()
+        .filter_map(|shape| match shape { Shape::Label { text, .. } => Some(text.as_str()), _ => None })
+        .collect()
+    }
+
+fn has_stroke(shapes: &[Shape], expected: Stroke) -> bool {
+    shapes.iter().any(|shape| matches!(shape, Shape::Path { stroke, .. } if *stroke == expected))
+    }
+
+#[test]
+fn figure_numbers() {
+    let seq_file = SeqFile::load(std::path::Path::new("tests/resources/Example.seq")).unwrap();
+    let shapes = draw(&parse_sequence(seq_file.sequence_text), &DiagramOptions::default());
+    assert_eq!(dots(&shapes).len(), 12);
+    let numbers: Vec<String> = (1..=12).map(|number| number.to_string()).collect();
+    assert!(numbers.iter().all(|number| labels(&shapes).contains(&number.as_str())));
+
+    let shapes = draw(&parse_sequence("o 1".to_string()), &DiagramOptions { numbers: false, .. DiagramOptions::default() });
+    assert!(labels(&shapes).is_empty());
+    }
+
+#[test]
+fn inverted_lines_are_dashed() {
+    assert!(!has_stroke(&shapes("o"), Stroke::Dashed));
+    assert!(has_stroke(&shapes("-o-"), Stroke::Dashed));
+    assert!(has_stroke(&shapes("2-"), Stroke::Dashed));
+    }
+
+#[test]
+fn roll_symbols() {
+    assert_eq!(labels(&shapes("24")), vec!["1", "2x4"]);
+    assert_eq!(labels(&shapes("4")), vec!["1", "1/4"]);
+    assert_eq!(labels(&shapes("2")), vec!["1"]);
+
+    // Positive flicks are filled, negative ones are not
+    let filled = |olan: &str| shapes(olan).iter()
+        .filter_map(|shape| match shape { Shape::Polygon { filled, .. } => Some(*filled), _ => None })
+        .collect::<Vec<bool>>();
+    assert_eq!(filled("1f"), vec![true]);
+    assert_eq!(filled("1if"), vec![false]);
+    assert_eq!(labels(&shapes("2f")), vec!["1", "1/2"]);
+    }
+
+#[test]
+fn layout_offsets() {
+    let plain = dots(&shapes("o o"));
+    let moved = dots(&shapes("o [10,20] o"));
+    assert_eq!(plain[0], moved[0]);
+    assert!((moved[1].0 - plain[1].0 - 10.0).abs() < 1.0e-3);
+    assert!((moved[1].1 - plain[1].1 + 20.0).abs() < 1.0e-3);
+
+    let forward = dots(&shapes("o 3> o"));
+    assert!((forward[1].0 - plain[1].0 - 30.0).abs() < 1.0e-3);
+
+    let curved = shapes("o (10,20) o");
+    assert_eq!(dots(&curved), moved);
+    assert!(has_stroke(&curved, Stroke::Dotted));
+
+    // Entering downwind draws the figure right to left
+    let downwind = dots(&shapes("ed 1 1"));
+    assert!(downwind[1].0 < downwind[0].0);
+    }
+
+#[test]
+fn form_c_mirrors_form_b() {
+    let sequence = parse_sequence("o 2j 24- -o-".to_string());
+    let form_b = draw(&sequence, &DiagramOptions::default());
+    let form_c = draw(&sequence, &DiagramOptions { form: Form::C, .. DiagramOptions::default() });
+    let b_dots = dots(&form_b);
+    let c_dots = dots(&form_c);
+    assert_eq!(b_dots.len(), c_dots.len());
+    for (b, c) in b_dots.iter().zip(c_dots.iter()) {
+        assert_eq!((b.0, b.1), (-c.0, c.1));
+        }
+    }
+
+#[test]
+fn svg_output() {
+    let mut output = Vec::new();
+    write_svg(&parse_sequence("o -o-".to_string()), &DiagramOptions::default(), &mut output).unwrap();
+    let svg = String::from_utf8(output).unwrap();
+    assert!(svg.contains("<svg"));
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(svg.matches("<circle").count(), 2);
+    assert!(svg.contains("stroke-dasharray"));
+    }
@@ -0,0 +1,58 @@
+use arusti::{FigureChange,FigureDiff};
+use arusti::diff::diff_olan;
+
+#[test]
+fn added_removed_and_moved_figures() {
+    assert!(diff_olan("o 2j 24", "o 2j 24").unwrap().is_empty());
+
+    let diff = diff_olan("o 2j h", "2j o").unwrap();
+    assert_eq!(diff.figures, vec![
+        FigureDiff::Moved { from: 0, to: 1 },
+        FigureDiff::Removed { index: 2 },
+        ]);
+
+    let diff = diff_olan("o h", "o 2j h").unwrap();
+    assert_eq!(diff.figures, vec![FigureDiff::Added { index: 1 }]);
+    assert_eq!(diff.to_string(), "Added figure 2");
+    }
+
+#[test]
+fn roll_changes() {
+    let diff = diff_olan("2 d2 2,4", "3 d24 2;4").unwrap();
+    assert_eq!(diff.figures, vec![
+        FigureDiff::Modified { from: 0, to: 0, changes: vec![
+            FigureChange::RollSet { line: 0, before: "2".to_string(), after: "3".to_string() },
+            ] },
+        FigureDiff::Modified { from: 1, to: 1, changes: vec![
+            FigureChange::RollSet { line: 1, before: "2".to_string(), after: "24".to_string() },
+            ] },
+        FigureDiff::Modified { from: 2, to: 2, changes: vec![
+            FigureChange::RollDirection { line: 0, before: "2,4".to_string(), after: "2;4".to_string() },
+            ] },
+        ]);
+    assert_eq!(diff.figures[1].to_string(), "Changed figure 2\n    rolls on line 2 changed from `2` to `24`");
+    }
+
+#[test]
+fn inversion_changes() {
+    let diff = diff_olan("o 2a2 -o-", "o 2a2- -o-").unwrap();
+    let FigureDiff::Modified { changes, .. } = &diff.figures[0] else { panic!("expected a modified figure") };
+    assert_eq!(changes[0], FigureChange::ExitInversion { before: false, after: true });
+    assert_eq!(changes[1], FigureChange::Radii { before: vec![-180.0], after: vec![180.0] });
+
+    let diff = diff_olan("o", "-o").unwrap();
+    let FigureDiff::Modified { changes, .. } = &diff.figures[0] else { panic!("expected a modified figure") };
+    assert_eq!(changes[0], FigureChange::EntryInversion { before: false, after: true });
+    }
+
+#[test]
+fn json_output() {
+    let diff = diff_olan("o [0,2] 2j1", "2j2").unwrap();
+    let json: serde_json::Value = serde_json::from_str(&diff.to_json()).unwrap();
+    assert_eq!(json["figures"][0]["kind"], "removed");
+    assert_eq!(json["figures"][1]["kind"], "modified");
+    assert_eq!(json["figures"][1]["from"], 1);
+    assert_eq!(json["figures"][1]["changes"][0]["change"], "turns");
+    assert_eq!(json["figures"][1]["changes"][1]["change"], "layout");
+    assert_eq!(json["figures"][1]["changes"][1]["before"][0], "[0,2]");
+    }
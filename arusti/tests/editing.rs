@@ -0,0 +1,82 @@
+use arusti::{Element,Figure};
+use arusti::olan::parse_sequence;
+
+fn figure(olan: &str) -> Figure {
+    parse_sequence(olan.to_string()).figures.remove(0)
+    }
+
+#[test]
+fn roll_sets() {
+    let half = Element::roll(180.0, 1);
+    assert_eq!(figure("2a2-").roll_sets(), vec![vec![half], vec![half]]);
+    assert_eq!(figure("d24").roll_sets(), vec![vec![], vec![Element::roll(180.0, 4)], vec![]]);
+
+    // Putting back the same rolls changes nothing when the exit is written the way the figure ends
+    for olan in ["o", "d2", "d24-", "-iv24-", "2j1", "h", "2a2-", "iv24,2f;2if,1-", "id2", "is", "v2", "rc4", "1o1"] {
+        let original = figure(olan);
+        let mut edited = figure(olan);
+        for (line, rolls) in original.roll_sets().iter().enumerate() {
+            edited.set_roll_set(line, rolls).unwrap();
+            }
+        assert_eq!(edited.elements, original.elements, "{}", olan);
+        }
+    }
+
+#[test]
+fn changing_rolls() {
+    let mut sequence = parse_sequence("o o".to_string());
+    let report = sequence.set_roll_set(0, 0, &[Element::roll(180.0, 1)]).unwrap();
+    // The loop is pushed after the half roll and ends inverted, so no longer meets the next figure
+    assert_eq!(sequence.figures[0].elements, figure("2o-").elements);
+    assert_eq!(report.continuity_breaks, vec![1]);
+
+    let report = sequence.set_roll_set(0, 0, &[]).unwrap();
+    assert_eq!(sequence.figures[0].elements, figure("o").elements);
+    assert!(report.continuity_breaks.is_empty());
+
+    let mut d = figure("d");
+    d.set_roll_set(1, &[Element::roll(180.0, 4)]).unwrap();
+    assert_eq!(d.elements, figure("d24-").elements);
+
+    assert!(sequence.set_roll_set(0, 2, &[]).is_err());
+    assert!(sequence.set_roll_set(2, 0, &[]).is_err());
+    assert!(sequence.set_roll_set(0, 0, &[Element::line(0.0)]).is_err());
+    }
+
+#[test]
+fn replacing_shapes() {
+    let mut sequence = parse_sequence("d2 o".to_string());
+    let report = sequence.replace_shape(0, &figure("v")).unwrap();
+    assert_eq!(sequence.figures[0].elements, figure("v2").elements);
+    assert!(report.dropped_rolls.is_empty());
+
+    // A loop has no line in the middle for the rolls
+    let mut sequence = parse_sequence("d2 o".to_string());
+    let report = sequence.replace_shape(0, &figure("o")).unwrap();
+    assert_eq!(sequence.figures[0].elements, figure("o").elements);
+    assert_eq!(report.dropped_rolls, vec![Element::roll(180.0, 1)]);
+
+    // The entry is kept and the rolls on it stay there
+    let mut rolling = figure("-2-");
+    assert!(rolling.replace_shape(&figure("o")).is_empty());
+    assert_eq!(rolling.roll_sets()[0], vec![Element::roll(180.0, 1)]);
+    assert!(rolling.elements[0].inverted());
+    }
+
+#[test]
+fn inserting_and_removing() {
+    let mut sequence = parse_sequence("o o".to_string());
+    let report = sequence.insert_figure(1, figure("h")).unwrap();
+    assert_eq!(report.entry_headings, vec![0.0, 0.0, 180.0]);
+    assert!(report.continuity_breaks.is_empty());
+
+    let report = sequence.insert_figure(3, figure("-2j-")).unwrap();
+    assert_eq!(report.entry_headings, vec![0.0, 0.0, 180.0, 180.0]);
+    assert_eq!(report.continuity_breaks, vec![3]);
+
+    let (removed, report) = sequence.remove_figure(1).unwrap();
+    assert_eq!(removed.elements, figure("h").elements);
+    assert_eq!(report.entry_headings, vec![0.0, 0.0, 0.0]);
+    assert!(sequence.remove_figure(3).is_err());
+    assert!(sequence.insert_figure(4, figure("o")).is_err());
+    }
@@ -0,0 +1,55 @@
+use arusti::{Element,FlatElement,ElementType,RadiusSize,RollDirection,TurnRollDirection};
+use arusti::olan::parse_sequence;
+
+fn rolls(olan: &str) -> Vec<Element> {
+    parse_sequence(olan.to_string()).figures[0].elements.iter()
+        .copied()
+        .filter(|element| element.is_rolling())
+        .collect()
+    }
+
+#[test]
+fn linked_rolls() {
+    // ';' keeps the direction of the previous roll and ',' opposes it
+    let linked = rolls("2;2");
+    assert_eq!(linked.len(), 2);
+    assert!(matches!(linked[0], Element::Roll { linked: false, .. }));
+    assert!(matches!(linked[1], Element::Roll { linked: true, .. }));
+
+    let opposite = rolls("2,2");
+    assert!(matches!(opposite[1], Element::Roll { linked: false, .. }));
+    let (Element::Roll { direction: first, .. }, Element::Roll { direction: second, .. }) = (opposite[0], opposite[1]) else { panic!("{:?}", opposite) };
+    assert_ne!(first, second);
+    }
+
+#[test]
+fn roll_variants() {
+    assert_eq!(rolls("24"), vec![Element::Roll { angle: 180.0, direction: RollDirection::Right, divisions: 4, linked: false }]);
+    assert_eq!(rolls("1if"), vec![Element::Flick { angle: 360.0, direction: RollDirection::Right, negative: true, linked: false }]);
+    assert_eq!(Element::roll(-90.0, 1), Element::Roll { angle: 90.0, direction: RollDirection::Left, divisions: 1, linked: false });
+    }
+
+#[test]
+fn turn_rolls() {
+    let figure = &parse_sequence("2j1".to_string()).figures[0];
+    let Some(Element::Turn { angle, rolls, roll_direction, .. }) = figure.elements.iter().find(|element| element.elem_type() == ElementType::Turn) else {
+        panic!("{:?}", figure.elements)
+        };
+    assert_eq!((*angle, *rolls), (180.0, 1.0));
+    assert_eq!(*roll_direction, TurnRollDirection::Inside);
+
+    let outside = FlatElement { angle: 90.0, argument: -720.0, .. FlatElement::new(ElementType::Turn) };
+    assert_eq!(Element::from_flat(&outside, None), Element::Turn { angle: 90.0, rolls: 2.0, roll_direction: TurnRollDirection::Outside, inverted: false });
+    }
+
+#[test]
+fn flat_round_trip() {
+    let sequence = parse_sequence("/d 2j1 -iv1,1- iv24,2f;2if,1 o 5,2f rc4 2a2 h".to_string());
+    for figure in &sequence.figures {
+        assert_eq!(arusti::Figure::from_flat(&figure.to_flat()).elements, figure.elements);
+        }
+
+    let radius = FlatElement { angle: 90.0, argument: -2.0, .. FlatElement::new(ElementType::Radius) };
+    assert_eq!(Element::from_flat(&radius, None), Element::Radius { angle: 90.0, size: RadiusSize::Fixed(2) });
+    assert_eq!(FlatElement::from(&Element::stall(180.0, -90.0)), FlatElement::stall(180.0, -90.0));
+    }
@@ -0,0 +1,132 @@
+use arusti::{DataPoint,ExportFormat,GeoOrigin,Vector3,UnitQuaternion};
+use arusti::export::{IgcOptions,KmlOptions,GltfOptions,COLUMNS};
+
+fn get_points() -> Vec<DataPoint> {
+    (0..100).map(|i| {
+        let time = i as f32 * 0.1;
+        DataPoint {
+            time,
+            position: Vector3::new(20.0 * time, 0.0, -300.0),
+            attitude: UnitQuaternion::identity(),
+            }
+        }).collect()
+    }
+
+#[test]
+fn format_from_str() {
+    assert_eq!("csv".parse::<ExportFormat>(), Ok(ExportFormat::Csv));
+    assert_eq!("IGC".parse::<ExportFormat>(), Ok(ExportFormat::Igc));
+    assert_eq!("parquet".parse::<ExportFormat>(), Ok(ExportFormat::Parquet));
+    assert_eq!("kml".parse::<ExportFormat>(), Ok(ExportFormat::Kml));
+    assert_eq!("gltf".parse::<ExportFormat>(), Ok(ExportFormat::Gltf));
+    assert!("xls".parse::<ExportFormat>().is_err());
+    }
+
+#[test]
+fn csv_export() {
+    let mut output = Vec::<u8>::new();
+    arusti::export::write_csv(&get_points(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    let mut lines = output.lines();
+    assert_eq!(lines.next().unwrap(), "time,x,y,z,qw,qx,qy,qz,roll,pitch,yaw");
+    let first_row: Vec<f32> = lines.next().unwrap().split(',').map(|v| v.parse().unwrap()).collect();
+    assert_eq!(first_row.len(), COLUMNS.len());
+    assert_eq!(first_row[3], -300.0);
+    assert_eq!(first_row[4], 1.0);
+    assert_eq!(lines.count(), 99);
+    }
+
+#[test]
+fn csv_round_trip() {
+    let mut output = Vec::<u8>::new();
+    arusti::export::write_csv(&get_points(), &mut output).unwrap();
+    let points = arusti::export::read_csv(output.as_slice()).unwrap();
+    assert_eq!(points.len(), 100);
+    assert_eq!(points[50].position, get_points()[50].position);
+
+    assert!(arusti::export::read_csv("time,x,y,z\n0,0,0,0\n".as_bytes()).is_err());
+    }
+
+#[test]
+fn igc_export() {
+    let options = IgcOptions {
+        origin: GeoOrigin::new(52.5, -1.25, 100.0),
+        date: (19, 10, 2026),
+        .. IgcOptions::default()
+        };
+    let mut output = Vec::<u8>::new();
+    arusti::export::write_igc(&get_points(), &options, &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    assert!(output.contains("HFDTEDATE:191026"));
+
+    // One fix per second
+    let fixes: Vec<&str> = output.lines().filter(|line| line.starts_with('B')).collect();
+    assert_eq!(fixes.len(), 10);
+    assert_eq!(fixes[0], "B1200005230000N00115000WA0040000400");
+    assert_eq!(fixes[0].len(), 35);
+    }
+
+#[test]
+fn kml_export() {
+    let mut origin = GeoOrigin::new(52.5, -1.25, 100.0);
+    origin.heading = 90.0;
+    let options = KmlOptions {
+        origin,
+        model: Some("model.dae".to_string()),
+        .. KmlOptions::default()
+        };
+    let mut output = Vec::<u8>::new();
+    arusti::export::write_kml(&get_points(), &options, &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    let document = roxmltree::Document::parse(&output).unwrap();
+    let count = |name: &str| document.descendants().filter(|n| n.tag_name().name() == name).count();
+    assert_eq!(count("when"), 100);
+    assert_eq!(count("coord"), 100);
+    assert_eq!(count("angles"), 100);
+    assert_eq!(count("href"), 1);
+
+    // Box axis rotated to east, so the track heads east from the origin
+    assert!(output.contains("<when>2000-01-01T12:00:00.000Z</when>"));
+    assert!(output.contains("<gx:coord>-1.25000000 52.50000000 400.00</gx:coord>"));
+    assert!(output.contains("<gx:angles>90.00 "));
+    let last_coord = document.descendants().filter(|n| n.tag_name().name() == "coord").last().unwrap();
+    let longitude: f64 = last_coord.text().unwrap().split(' ').next().unwrap().parse().unwrap();
+    assert!(longitude > -1.25);
+    }
+
+#[test]
+fn gltf_export() {
+    let mut output = Vec::<u8>::new();
+    arusti::export::write_gltf(&get_points(), &GltfOptions::default(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    assert!(output.starts_with(r#"{"asset":{"version":"2.0""#));
+    assert!(output.contains(r#""path":"translation""#));
+    assert!(output.contains(r#""path":"rotation""#));
+    // Times run from zero to the end of the flight
+    assert!(output.contains(r#""count":100,"type":"SCALAR","min":[0.0],"max":[9.900001]"#));
+    // Ribbon has a vertex at each wingtip
+    assert!(output.contains(r#""count":200,"type":"VEC3""#));
+
+    let mut output = Vec::<u8>::new();
+    assert!(arusti::export::write_gltf(&get_points()[0..1], &GltfOptions::default(), &mut output).is_err());
+    }
+
+#[cfg(feature = "parquet")]
+#[test]
+fn parquet_export() {
+    let mut output = Vec::<u8>::new();
+    arusti::export::write_points(ExportFormat::Parquet, &get_points(), &mut output).unwrap();
+    assert_eq!(&output[0..4], b"PAR1");
+    assert_eq!(&output[output.len()-4..], b"PAR1");
+    }
+
+#[cfg(not(feature = "parquet"))]
+#[test]
+fn parquet_export_unavailable() {
+    let mut output = Vec::<u8>::new();
+    assert!(arusti::export::write_points(ExportFormat::Parquet, &get_points(), &mut output).is_err());
+    }
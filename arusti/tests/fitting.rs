@@ -0,0 +1,116 @@
+use arusti::{DataPoint,DataPointGenerator,Element,ElementType,PerformanceOptions,SensorNoise,TurnRollDirection,Vector3};
+use arusti::fitting::{fit_line,fit_radius,fit_rotation,fit_segment,ElementFit};
+use arusti::olan::parse_sequence;
+use arusti::segmentation::{segment_elements,SegmentationOptions};
+
+mod common;
+
+/// Fit each element segmented from the flight of a single figure
+fn fit(olan: &str, noise: &mut SensorNoise) -> Vec<ElementFit> {
+    let mut generator = common::generator();
+    let points: Vec<DataPoint> = generator.generate_points(&parse_sequence(olan.to_string())).into_iter()
+        .map(|mut point| { noise.apply(&mut point); point })
+        .collect();
+    let options = SegmentationOptions::default();
+    segment_elements(&points, &options).iter()
+        .map(|segment| fit_segment(&points, segment, &options).unwrap())
+        .collect()
+    }
+
+fn no_noise() -> SensorNoise {
+    SensorNoise::new(0, 0.0, 0.0)
+    }
+
+#[test]
+fn line_fit() {
+    let fits = fit("o", &mut no_noise());
+    let line = &fits[0];
+    assert!(matches!(line.element, Element::Line { pitch, inverted: false } if pitch.abs() < 0.5), "{}", line.element);
+    // Samples start one step into the line
+    assert!((line.size - 17.64).abs() < 0.1, "{}", line.size);
+    assert!(line.rms_residual < 0.01);
+    assert!(line.confidence > 0.99);
+    }
+
+#[test]
+fn radius_fit() {
+    let fits = fit("o", &mut no_noise());
+    let radius = &fits[1];
+    // v / q = 18 m/s / 45 deg/s
+    assert!((radius.size - 22.92).abs() < 0.1, "{}", radius.size);
+    assert!(matches!(radius.element, Element::Radius { angle, .. } if (angle - 360.0).abs() < 1.0), "{}", radius.element);
+    assert!(radius.confidence > 0.99);
+
+    let fits = fit("m", &mut no_noise());
+    assert!(matches!(fits[1].element, Element::Radius { angle, .. } if (angle + 180.0).abs() < 1.0), "{}", fits[1].element);
+    }
+
+#[test]
+fn turn_fit() {
+    let fits = fit("2j", &mut no_noise());
+    let turn = &fits[1];
+    assert!(matches!(turn.element, Element::Turn { angle, rolls, .. } if (angle - 180.0).abs() < 1.0 && rolls < 0.01), "{}", turn.element);
+
+    let fits = fit("2j1", &mut no_noise());
+    let inside = matches!(fits[1].element,
+        Element::Turn { rolls, roll_direction: TurnRollDirection::Inside, .. } if (rolls - 1.0).abs() < 0.01);
+    assert!(inside, "{}", fits[1].element);
+    }
+
+#[test]
+fn roll_fit() {
+    let fits = fit("2-", &mut no_noise());
+    let roll = &fits[1];
+    assert!(matches!(roll.element, Element::Roll { angle, divisions: 1, .. } if (angle - 180.0).abs() < 1.0), "{}", roll.element);
+    assert!(fits[2].element.inverted());
+
+    let fits = fit("24-", &mut no_noise());
+    assert!(matches!(fits[1].element, Element::Roll { angle, divisions: 4, .. } if (angle - 180.0).abs() < 1.0), "{}", fits[1].element);
+    }
+
+#[test]
+fn flick_fit() {
+    let fits = fit("2f-", &mut no_noise());
+    assert_eq!(fits.len(), 3);
+    assert!(matches!(fits[1].element, Element::Flick { angle, negative: false, .. } if (angle - 180.0).abs() < 5.0), "{}", fits[1].element);
+
+    // Whole turns are kept, although the attitude only shows the last part turn
+    let fits = fit("6if", &mut no_noise());
+    assert_eq!(fits.len(), 3);
+    assert!(matches!(fits[1].element, Element::Flick { angle, negative: true, .. } if (angle - 540.0).abs() < 5.0), "{}", fits[1].element);
+    }
+
+#[test]
+fn flick_entry_fit() {
+    // The snap builds up to its rate over the entry, and the entry is part of the flick
+    let performance = PerformanceOptions { snap_entry_time: 0.3, .. common::performance() };
+    let mut generator = DataPointGenerator::new(Vector3::x() * 18.0, Vector3::zeros(), performance);
+    let points = generator.generate_points(&parse_sequence("2f-".to_string()));
+    let options = SegmentationOptions::default();
+    let segments = segment_elements(&points, &options);
+    assert_eq!(segments.len(), 3);
+    let flick = fit_segment(&points, &segments[1], &options).unwrap();
+    assert!(matches!(flick.element, Element::Flick { angle, .. } if (angle - 180.0).abs() < 5.0), "{}", flick.element);
+    }
+
+#[test]
+fn noisy_fit() {
+    let fits = fit("o", &mut SensorNoise::new(5, 0.5, 0.5));
+    assert!((fits[1].size - 22.92).abs() < 1.0, "{}", fits[1].size);
+    assert!(fits[1].confidence > 0.5);
+
+    let fits = fit("24-", &mut SensorNoise::new(5, 0.5, 0.5));
+    assert!(matches!(fits[1].element, Element::Roll { angle, divisions: 4, .. } if (angle - 180.0).abs() < 5.0), "{}", fits[1].element);
+    }
+
+#[test]
+fn short_slices() {
+    let mut generator = common::generator();
+    let points = generator.generate_points(&parse_sequence("o".to_string()));
+    let options = SegmentationOptions::default();
+    assert!(fit_line(&points[..0]).is_none());
+    assert!(fit_line(&points[..2]).is_some());
+    assert!(fit_radius(&points[..2]).is_none());
+    assert!(fit_rotation(&points[..1], ElementType::Roll, &options).is_none());
+    assert!(fit_rotation(&points[..10], ElementType::Line, &options).is_none());
+    }
@@ -1,12 +1,12 @@
 use arusti;
 
-use arusti::{ElementType,Element,Sequence};
+use arusti::{ElementType,FlatElement,Sequence};
 
-fn compare_elements_in_figure(sequence: &Sequence, figure_idx: usize, expectation: &Vec<Element>) {
-    let result = &sequence.figures[figure_idx].elements;
+fn compare_elements_in_figure(sequence: &Sequence, figure_idx: usize, expectation: &Vec<FlatElement>) {
+    let result = &sequence.figures[figure_idx].to_flat();
     assert_eq!(result.len(), expectation.len(), "Figure {} has wrong number of elements", figure_idx);
     for (index,(result,expected)) in result.iter().zip( expectation.iter() ).enumerate() {
-        assert_eq!(result, expected, "Element {} of Figure {} does not match expectation", index, figure_idx);
+        assert_eq!(result, expected, "FlatElement {} of Figure {} does not match expectation", index, figure_idx);
         }
     }
 
@@ -18,139 +18,139 @@ fn full_sequence() {
     compare_elements_in_figure(
         &sequence, 0,
         &vec![
-            Element::line(0.0),
-            Element::radius(45.0),
-            Element::line(45.0),
-            Element::radius(90.0),
-            Element::invline(45.0),
-            Element::radius(90.0),
-            Element::invline(-45.0),
-            Element::radius(90.0),
-            Element::line(-45.0),
-            Element::radius(45.0),
-            Element::line(0.0),
+            FlatElement::line(0.0),
+            FlatElement::radius(45.0),
+            FlatElement::line(45.0),
+            FlatElement::radius(90.0),
+            FlatElement::invline(45.0),
+            FlatElement::radius(90.0),
+            FlatElement::invline(-45.0),
+            FlatElement::radius(90.0),
+            FlatElement::line(-45.0),
+            FlatElement::radius(45.0),
+            FlatElement::line(0.0),
             ]
         );
     
     compare_elements_in_figure(
         &sequence, 1,
         &vec![
-            Element::line(0.0),
-            Element::radius(90.0),
-            Element::line(90.0),
-            Element::radius(-90.0),
-            Element::line(0.0),
+            FlatElement::line(0.0),
+            FlatElement::radius(90.0),
+            FlatElement::line(90.0),
+            FlatElement::radius(-90.0),
+            FlatElement::line(0.0),
             ]
         );
     
     compare_elements_in_figure(
         &sequence, 2,
         &vec![
-            Element::line(0.0),
-            Element::radius(-90.0),
-            Element::line(-90.0),
-            Element { angle: 360.0, argument: 1.0, .. Element::new(ElementType::Spin) },
-            Element::line(-90.0),
-            Element::radius(270.0),
-            Element::invline(0.0),
+            FlatElement::line(0.0),
+            FlatElement::radius(-90.0),
+            FlatElement::line(-90.0),
+            FlatElement { angle: 360.0, argument: 1.0, .. FlatElement::new(ElementType::Spin) },
+            FlatElement::line(-90.0),
+            FlatElement::radius(270.0),
+            FlatElement::invline(0.0),
             ]
         );
     
     compare_elements_in_figure(
         &sequence, 3,
         &vec![
-            Element::invline(0.0),
-            Element { angle: 180.0, inverted: true, .. Element::new(ElementType::Turn) },
-            Element::invline(0.0),
+            FlatElement::invline(0.0),
+            FlatElement { angle: 180.0, inverted: true, .. FlatElement::new(ElementType::Turn) },
+            FlatElement::invline(0.0),
             ]
         );
 
     compare_elements_in_figure(
         &sequence, 4,
         &vec![
-            Element::invline(0.0),
+            FlatElement::invline(0.0),
             // Expect reversed roll due to separator
-            Element { angle: -180.0, argument: 4.0, .. Element::new(ElementType::Roll) },
-            Element::line(0.0),
+            FlatElement { angle: -180.0, argument: 4.0, .. FlatElement::new(ElementType::Roll) },
+            FlatElement::line(0.0),
             ]
         );
 
     compare_elements_in_figure(
         &sequence, 5,
         &vec![
-            Element::line(0.0),
-            Element::radius(225.0),
-            Element::invline(-45.0),
+            FlatElement::line(0.0),
+            FlatElement::radius(225.0),
+            FlatElement::invline(-45.0),
             // Expect reversed roll due to separator
-            Element { angle: -180.0, argument: 4.0, .. Element::new(ElementType::Roll) },
-            Element::line(-45.0),
-            Element::radius(45.0),
-            Element::line(0.0),
+            FlatElement { angle: -180.0, argument: 4.0, .. FlatElement::new(ElementType::Roll) },
+            FlatElement::line(-45.0),
+            FlatElement::radius(45.0),
+            FlatElement::line(0.0),
             ]
         );
     
     compare_elements_in_figure(
         &sequence, 6,
         &vec![
-            Element::line(0.0),
-            Element::radius(90.0),
-            Element::line(90.0),
-            Element::radius(90.0),
-            Element::invline(0.0),
+            FlatElement::line(0.0),
+            FlatElement::radius(90.0),
+            FlatElement::line(90.0),
+            FlatElement::radius(90.0),
+            FlatElement::invline(0.0),
             ]
         );
 
     compare_elements_in_figure(
         &sequence, 7,
         &vec![
-            Element::invline(0.0),
-            Element::radius(45.0),
-            Element::invline(-45.0),
-            Element { angle: 180.0, argument: 1.0, .. Element::new(ElementType::Roll) },
-            Element::line(-45.0),
-            Element::radius(45.0),
-            Element::line(0.0),
+            FlatElement::invline(0.0),
+            FlatElement::radius(45.0),
+            FlatElement::invline(-45.0),
+            FlatElement { angle: 180.0, argument: 1.0, .. FlatElement::new(ElementType::Roll) },
+            FlatElement::line(-45.0),
+            FlatElement::radius(45.0),
+            FlatElement::line(0.0),
             ]
         );
     
     compare_elements_in_figure(
         &sequence, 8,
         &vec![
-            Element::line(0.0),
-            Element { angle: 360.0, argument: 1.0, .. Element::new(ElementType::Roll) },
-            Element::radius(180.0),
-            Element { angle: 180.0, argument: 1.0, .. Element::new(ElementType::Roll) },
-            Element::line(0.0),
+            FlatElement::line(0.0),
+            FlatElement { angle: 360.0, argument: 1.0, .. FlatElement::new(ElementType::Roll) },
+            FlatElement::radius(180.0),
+            FlatElement { angle: 180.0, argument: 1.0, .. FlatElement::new(ElementType::Roll) },
+            FlatElement::line(0.0),
             ]
         );
 
     compare_elements_in_figure(
         &sequence, 9,
         &vec![
-            Element::line(0.0),
-            Element { angle: 180.0, argument: 720.0, .. Element::new(ElementType::Turn) },
-            Element::line(0.0),
+            FlatElement::line(0.0),
+            FlatElement { angle: 180.0, argument: 720.0, .. FlatElement::new(ElementType::Turn) },
+            FlatElement::line(0.0),
             ]
         );
 
     compare_elements_in_figure(
         &sequence, 10,
         &vec![
-            Element::line(0.0),
-            Element { angle: 360.0, argument: 1.0, .. Element::new(ElementType::Flick) },
-            Element { angle: -180.0, argument: 1.0, .. Element::new(ElementType::Roll) },
-            Element::invline(0.0),
+            FlatElement::line(0.0),
+            FlatElement { angle: 360.0, argument: 1.0, .. FlatElement::new(ElementType::Flick) },
+            FlatElement { angle: -180.0, argument: 1.0, .. FlatElement::new(ElementType::Roll) },
+            FlatElement::invline(0.0),
             ]
         );
 
     compare_elements_in_figure(
         &sequence, 11,
         &vec![
-            Element::invline(0.0),
-            Element { angle: 360.0, argument: 2.0, .. Element::new(ElementType::Roll) },
-            Element::radius(180.0),
-            Element { angle: 360.0, argument: 4.0, .. Element::new(ElementType::Roll) },
-            Element::line(0.0),
+            FlatElement::invline(0.0),
+            FlatElement { angle: 360.0, argument: 2.0, .. FlatElement::new(ElementType::Roll) },
+            FlatElement::radius(180.0),
+            FlatElement { angle: 360.0, argument: 4.0, .. FlatElement::new(ElementType::Roll) },
+            FlatElement::line(0.0),
             ]
         );
 
@@ -0,0 +1,69 @@
+use arusti::{Element,LineDirection,SeqFile};
+use arusti::geometry::RollPosition;
+use arusti::olan::parse_sequence;
+
+fn geometry(olan: &str) -> arusti::FigureGeometry {
+    parse_sequence(olan.to_string()).figures[0].geometry()
+    }
+
+#[test]
+fn humpty_bump() {
+    // Line, pull 90, line up, roll, line up, hammerhead, line down, pull 90, line
+    let geometry = geometry("1h");
+    let pitches: Vec<f32> = geometry.states.iter().map(|state| state.pitch).collect();
+    assert_eq!(pitches, vec![0.0, 0.0, 90.0, 90.0, 90.0, 90.0, -90.0, -90.0, 0.0, 0.0]);
+    assert_eq!(geometry.entry(0).heading, 0.0);
+    assert_eq!(geometry.exit(8).heading, 180.0);
+    assert!(!geometry.ends_inverted());
+    assert_eq!(geometry.looping_angle, 180.0);
+    assert_eq!(geometry.rolls, vec![RollPosition { element: 3, direction: LineDirection::VerticalUp }]);
+    let directions: Vec<LineDirection> = geometry.lines.iter().map(|(_, direction)| *direction).collect();
+    assert_eq!(directions, vec![LineDirection::Level, LineDirection::VerticalUp, LineDirection::VerticalUp, LineDirection::VerticalDown, LineDirection::Level]);
+    }
+
+#[test]
+fn inversion() {
+    // A half roll leaves the aircraft inverted whatever the exit line is marked as
+    assert!(geometry("2").ends_inverted());
+    assert!(!geometry("1").ends_inverted());
+    assert!(geometry("-2-").states[0].inverted);
+    assert!(!geometry("-2-").ends_inverted());
+
+    // Half roll to inverted, push over the top of a half loop to upright and half roll to inverted again
+    let geometry = geometry("2a2");
+    assert_eq!(geometry.roll_count(), 2);
+    assert!(geometry.exit(1).inverted);
+    assert!(!geometry.exit(2).inverted);
+    assert_eq!(geometry.exit(2).heading, 180.0);
+    assert!(geometry.ends_inverted());
+    }
+
+#[test]
+fn turns_and_climbs() {
+    let turn = geometry("2j1");
+    assert_eq!(turn.exit(1).heading, 180.0);
+    assert_eq!(turn.roll_count(), 0);
+    assert!(!turn.ends_inverted());
+
+    let climb = geometry("1rc");
+    assert_eq!(climb.rolls[0].direction, LineDirection::Level);
+    assert_eq!(climb.lines[1].1, LineDirection::Climbing);
+    assert_eq!(climb.looping_angle, 270.0);
+
+    let element = Element::radius(-45.0);
+    assert_eq!(element.looping_angle(), 45.0);
+    assert_eq!(element.apply(&arusti::FlightState::entry(false)).line_direction(), LineDirection::Diving);
+    assert!(!element.is_rolling());
+    }
+
+#[test]
+fn lines_match_parsed_angles() {
+    let seq_file = SeqFile::load(std::path::Path::new("tests/resources/Example.seq")).unwrap();
+    for (i, figure) in parse_sequence(seq_file.sequence_text).figures.iter().enumerate() {
+        let geometry = figure.geometry();
+        for (element, _) in &geometry.lines {
+            let Element::Line { pitch, .. } = figure.elements[*element] else { panic!("figure {} element {} is not a line", i + 1, element) };
+            assert_eq!(geometry.entry(*element).pitch, pitch, "figure {} element {}", i + 1, element);
+            }
+        }
+    }
@@ -0,0 +1,283 @@
+use std::path::Path;
+
+use arusti::{DataPoint,GeoOrigin,ImportFormat,ImportOptions,LocalFrame,Vector3,UnitQuaternion};
+use arusti::export::IgcOptions;
+use arusti::import::{ColumnMapping,PositionColumns,AttitudeColumns,Fix,FixPosition};
+
+const ORIGIN: (f64, f64, f64) = (52.5, -1.25, 100.0);
+
+fn origin_options() -> ImportOptions {
+    ImportOptions {
+        origin: Some(GeoOrigin::new(ORIGIN.0, ORIGIN.1, ORIGIN.2)),
+        .. ImportOptions::default()
+        }
+    }
+
+fn read(format: ImportFormat, data: &[u8], options: &ImportOptions) -> Vec<DataPoint> {
+    arusti::import::read_points(format, data, options).unwrap()
+    }
+
+fn assert_close(actual: &Vector3<f32>, expected: &Vector3<f32>, tolerance: f32) {
+    assert!((actual - expected).norm() < tolerance, "{:?} != {:?}", actual, expected);
+    }
+
+fn nmea_sentence(body: &str) -> String {
+    let checksum = body.bytes().fold(0u8, |checksum, byte| checksum ^ byte);
+    format!("${}*{:02X}", body, checksum)
+    }
+
+#[test]
+fn format_from_str() {
+    assert_eq!("DataFlash".parse::<ImportFormat>(), Ok(ImportFormat::DataFlash));
+    assert_eq!("ulog".parse::<ImportFormat>(), Ok(ImportFormat::ULog));
+    assert_eq!("nmea".parse::<ImportFormat>(), Ok(ImportFormat::Nmea));
+    assert!("xls".parse::<ImportFormat>().is_err());
+    assert_eq!(ImportFormat::from_path(Path::new("flight.BIN")), Some(ImportFormat::DataFlash));
+    assert_eq!(ImportFormat::from_path(Path::new("flight.ulg")), Some(ImportFormat::ULog));
+    assert_eq!(ImportFormat::from_path(Path::new("flight.gpx")), Some(ImportFormat::Gpx));
+    assert_eq!(ImportFormat::from_path(Path::new("flight")), None);
+    }
+
+#[test]
+fn geo_round_trip() {
+    let mut origin = GeoOrigin::new(ORIGIN.0, ORIGIN.1, ORIGIN.2);
+    origin.heading = 30.0;
+    let position = Vector3::new(350.0, -120.0, -250.0);
+    let (latitude, longitude, altitude) = origin.to_wgs84(&position);
+    assert_close(&origin.from_wgs84(latitude, longitude, altitude), &position, 0.01);
+
+    // Flying north is flying 30 degrees left of the box axis
+    let attitude = origin.attitude_from_wgs84(&UnitQuaternion::identity());
+    assert!((attitude.euler_angles().2.to_degrees() + 30.0).abs() < 1.0e-3);
+    }
+
+#[test]
+fn local_frames() {
+    assert_eq!("enu".parse::<LocalFrame>(), Ok(LocalFrame::Enu));
+    assert_close(&LocalFrame::Enu.from_ned(&Vector3::new(1.0, 2.0, 3.0)), &Vector3::new(2.0, 1.0, -3.0), 1.0e-6);
+    assert_close(&LocalFrame::Ned.from_ned(&Vector3::new(1.0, 2.0, 3.0)), &Vector3::new(1.0, 2.0, 3.0), 1.0e-6);
+
+    // Level heading north has the nose along the ENU y axis
+    let attitude = LocalFrame::Enu.attitude_from_ned(&UnitQuaternion::identity());
+    assert_close(&(attitude * Vector3::x()), &Vector3::y(), 1.0e-6);
+
+    let fixes = [Fix { time: 0.0, position: FixPosition::Local(Vector3::new(10.0, 0.0, -300.0)), attitude: None }];
+    let options = ImportOptions { frame: LocalFrame::Enu, .. ImportOptions::default() };
+    let points = arusti::import::fixes_to_points(&fixes, &options);
+    assert_close(&points[0].position, &Vector3::new(0.0, 10.0, 300.0), 1.0e-6);
+    }
+
+#[test]
+fn igc_round_trip() {
+    let points: Vec<DataPoint> = (0..100).map(|i| {
+        let time = i as f32 * 0.1;
+        DataPoint {
+            time,
+            position: Vector3::new(0.0, 20.0 * time, -300.0),
+            attitude: UnitQuaternion::identity(),
+            }
+        }).collect();
+    let options = IgcOptions {
+        origin: GeoOrigin::new(ORIGIN.0, ORIGIN.1, ORIGIN.2),
+        start_time: 86395.0,
+        .. IgcOptions::default()
+        };
+    let mut output = Vec::<u8>::new();
+    arusti::export::write_igc(&points, &options, &mut output).unwrap();
+
+    // Times continue through midnight
+    let imported = read(ImportFormat::Igc, &output, &origin_options());
+    assert_eq!(imported.len(), 10);
+    for (i, point) in imported.iter().enumerate() {
+        assert_eq!(point.time, i as f32);
+        assert_close(&point.position, &points[i * 10].position, 2.0);
+        }
+    // Attitude along the track, heading east
+    assert!((imported[5].attitude.euler_angles().2.to_degrees() - 90.0).abs() < 5.0);
+
+    assert!(arusti::import::read_igc("B1200005230000N").is_err());
+    }
+
+#[test]
+fn nmea() {
+    let log = [
+        nmea_sentence("GPGGA,235959.50,5230.000,N,00115.000,W,1,08,0.9,400.0,M,46.9,M,,"),
+        nmea_sentence("GPRMC,235959.50,A,5230.000,N,00115.000,W,0.0,0.0,191026,,"),
+        nmea_sentence("GPGGA,000000.00,5230.000,N,00115.000,W,0,08,0.9,400.0,M,46.9,M,,"),
+        "$GPGGA,000000.50,5230.010,N,00115.000,W,1,08,0.9,410.0,M,46.9,M,,*00".to_string(),
+        nmea_sentence("GNGGA,000000.50,5230.010,N,00115.000,W,1,08,0.9,410.0,M,46.9,M,,"),
+        ].join("\r\n");
+    let points = read(ImportFormat::Nmea, log.as_bytes(), &origin_options());
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[1].time, 1.0);
+    assert_close(&points[0].position, &Vector3::new(0.0, 0.0, -300.0), 0.01);
+    // A hundredth of a minute of latitude is 18.5 m
+    assert_close(&points[1].position, &Vector3::new(18.53, 0.0, -310.0), 0.05);
+    }
+
+#[test]
+fn gpx() {
+    let gpx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <trk><trkseg>
+    <trkpt lat="52.5" lon="-1.25"><ele>400</ele><time>2026-10-19T23:59:59.5Z</time></trkpt>
+    <trkpt lat="52.5" lon="-1.2497"><ele>400</ele><time>2026-10-20T01:00:00.5+01:00</time></trkpt>
+  </trkseg></trk>
+</gpx>"#;
+    let points = read(ImportFormat::Gpx, gpx.as_bytes(), &ImportOptions::default());
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[1].time, 1.0);
+    assert_close(&points[0].position, &Vector3::zeros(), 1.0e-6);
+    assert!((points[1].position[1] - 20.3).abs() < 0.1, "{:?}", points[1].position);
+
+    assert!(arusti::import::read_gpx(r#"<gpx><trk><trkseg><trkpt lat="52.5" lon="-1.25"/></trkseg></trk></gpx>"#).is_err());
+    }
+
+#[test]
+fn csv_mapping() {
+    let mapping: ColumnMapping = "time=t,time_scale=0.001,lat=Lat,lon=Lng,alt=Alt,roll=Roll,pitch=Pitch,yaw=Yaw".parse().unwrap();
+    assert_eq!(mapping.time_scale, 0.001);
+    assert_eq!(mapping.position, PositionColumns::Geodetic(["Lat".to_string(), "Lng".to_string(), "Alt".to_string()]));
+    assert_eq!(mapping.attitude, Some(AttitudeColumns::Euler(["Roll".to_string(), "Pitch".to_string(), "Yaw".to_string()])));
+    assert!("time=t,lat=Lat".parse::<ColumnMapping>().is_err());
+    assert!("speed=v".parse::<ColumnMapping>().is_err());
+
+    let csv = "t,Lat,Lng,Alt,Roll,Pitch,Yaw,Speed\n1000,52.5,-1.25,400,0,0,90,20\n1500,52.5,-1.25,420,90,0,90,20\n";
+    let options = ImportOptions { columns: mapping, .. origin_options() };
+    let points = read(ImportFormat::Csv, csv.as_bytes(), &options);
+    assert_eq!(points[1].time, 0.5);
+    assert_close(&points[1].position, &Vector3::new(0.0, 0.0, -320.0), 0.01);
+    let (roll, _, yaw) = points[1].attitude.euler_angles();
+    assert!((roll.to_degrees() - 90.0).abs() < 1.0e-3);
+    assert!((yaw.to_degrees() - 90.0).abs() < 1.0e-3);
+
+    // The default mapping reads datagen output
+    let mut output = Vec::<u8>::new();
+    arusti::export::write_csv(&[DataPoint { time: 0.0, position: Vector3::new(1.0, 2.0, 3.0), attitude: UnitQuaternion::identity() }], &mut output).unwrap();
+    let points = read(ImportFormat::Csv, &output, &ImportOptions::default());
+    assert_eq!(points[0].position, Vector3::new(1.0, 2.0, 3.0));
+    }
+
+#[test]
+fn dataflash_text() {
+    let log = "\
+FMT, 128, 89, FMT, BBnNZ, Type,Length,Name,Format,Columns
+FMT, 1, 23, ATT, QccC, TimeUS,Roll,Pitch,Yaw
+FMT, 2, 23, POS, QLLf, TimeUS,Lat,Lng,Alt
+ATT, 1000000, 0, 0, 80
+POS, 1500000, 52.5, -1.25, 400
+ATT, 2000000, 0, 0, 100
+POS, 2500000, 52.5001, -1.25, 400
+";
+    let points = read(ImportFormat::DataFlash, log.as_bytes(), &origin_options());
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[1].time, 1.0);
+    assert_close(&points[1].position, &Vector3::new(11.12, 0.0, -300.0), 0.05);
+    // Interpolated between the ATT messages
+    assert!((points[0].attitude.euler_angles().2.to_degrees() - 90.0).abs() < 1.0e-3);
+    assert!((points[1].attitude.euler_angles().2.to_degrees() - 100.0).abs() < 1.0e-3);
+    }
+
+#[test]
+fn dataflash_binary() {
+    fn fmt(msg_type: u8, length: u8, name: &str, format: &str, columns: &str) -> Vec<u8> {
+        let mut message = vec![0xA3, 0x95, 0x80, msg_type, length];
+        for (text, size) in [(name, 4), (format, 16), (columns, 64)].iter() {
+            let mut field = text.as_bytes().to_vec();
+            field.resize(*size, 0);
+            message.extend(field);
+            }
+        message
+        }
+
+    let mut log = fmt(0x80, 89, "FMT", "BBnNZ", "Type,Length,Name,Format,Columns");
+    log.extend(fmt(1, 17, "ATT", "Qcch", "TimeUS,Roll,Pitch,Yaw"));
+    log.extend(fmt(2, 24, "GPS", "QBLLe", "TimeUS,Status,Lat,Lng,Alt"));
+    let attitude = |time: u64, roll: i16| -> Vec<u8> {
+        let mut message = vec![0xA3, 0x95, 1];
+        message.extend(time.to_le_bytes().iter());
+        message.extend(roll.to_le_bytes().iter());
+        message.extend(0i16.to_le_bytes().iter());
+        message.extend(45i16.to_le_bytes().iter());
+        message
+        };
+    let gps = |time: u64, status: u8, latitude: i32| -> Vec<u8> {
+        let mut message = vec![0xA3, 0x95, 2];
+        message.extend(time.to_le_bytes().iter());
+        message.push(status);
+        message.extend(latitude.to_le_bytes().iter());
+        message.extend((-12_500_000i32).to_le_bytes().iter());
+        message.extend(40_000i32.to_le_bytes().iter());
+        message
+        };
+    log.extend(attitude(0, 3000));
+    log.extend(gps(100_000, 1, 0));
+    // Stray bytes are skipped
+    log.extend([0x00, 0xA3, 0x42].iter());
+    log.extend(gps(200_000, 3, 525_000_000));
+    log.extend(gps(700_000, 3, 525_001_000));
+
+    let points = read(ImportFormat::DataFlash, &log, &origin_options());
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[1].time, 0.5);
+    assert_close(&points[0].position, &Vector3::new(0.0, 0.0, -300.0), 0.01);
+    assert_close(&points[1].position, &Vector3::new(11.12, 0.0, -300.0), 0.05);
+    let (roll, pitch, yaw) = points[0].attitude.euler_angles();
+    assert!((roll.to_degrees() - 30.0).abs() < 1.0e-3);
+    assert!(pitch.abs() < 1.0e-6);
+    assert!((yaw.to_degrees() - 45.0).abs() < 1.0e-3);
+    }
+
+#[test]
+fn ulog() {
+    fn message(msg_type: u8, body: &[u8]) -> Vec<u8> {
+        let mut message = (body.len() as u16).to_le_bytes().to_vec();
+        message.push(msg_type);
+        message.extend(body);
+        message
+        }
+    fn subscribe(msg_id: u16, name: &str) -> Vec<u8> {
+        let mut body = vec![0];
+        body.extend(msg_id.to_le_bytes().iter());
+        body.extend(name.as_bytes());
+        message(b'A', &body)
+        }
+    fn data(msg_id: u16, fields: &[&[u8]]) -> Vec<u8> {
+        let mut body = msg_id.to_le_bytes().to_vec();
+        for field in fields {
+            body.extend(field.iter());
+            }
+        message(b'D', &body)
+        }
+
+    let mut log = b"ULog\x01\x12\x35\x01".to_vec();
+    log.extend(0u64.to_le_bytes().iter());
+    log.extend(message(b'F', b"vehicle_attitude:uint64_t timestamp;float[4] q;uint8_t[4] _padding0;"));
+    log.extend(message(b'F', b"vehicle_global_position:uint64_t timestamp;double lat;double lon;float alt;"));
+    log.extend(subscribe(0, "vehicle_attitude"));
+    log.extend(subscribe(1, "vehicle_global_position"));
+
+    // Rolled 90 degrees right
+    let half = std::f32::consts::FRAC_1_SQRT_2;
+    let q: Vec<u8> = [half, half, 0.0, 0.0].iter().flat_map(|part| part.to_le_bytes().to_vec()).collect();
+    log.extend(data(0, &[&1_000_000u64.to_le_bytes(), &q]));
+    log.extend(data(1, &[&1_000_000u64.to_le_bytes(), &52.5f64.to_le_bytes(), &(-1.25f64).to_le_bytes(), &400.0f32.to_le_bytes()]));
+    log.extend(data(1, &[&1_200_000u64.to_le_bytes(), &52.5001f64.to_le_bytes(), &(-1.25f64).to_le_bytes(), &410.0f32.to_le_bytes()]));
+
+    let points = read(ImportFormat::ULog, &log, &origin_options());
+    assert_eq!(points.len(), 2);
+    assert!((points[1].time - 0.2).abs() < 1.0e-6);
+    assert_close(&points[1].position, &Vector3::new(11.12, 0.0, -310.0), 0.05);
+    assert!((points[0].attitude.euler_angles().0.to_degrees() - 90.0).abs() < 1.0e-3);
+
+    assert!(arusti::import::read_ulog(b"not a log").is_err());
+
+    // A format whose field is its own type would never finish decoding
+    let mut log = b"ULog\x01\x12\x35\x01".to_vec();
+    log.extend(0u64.to_le_bytes().iter());
+    log.extend(message(b'F', b"vehicle_attitude:uint64_t timestamp;vehicle_attitude nested;"));
+    log.extend(subscribe(0, "vehicle_attitude"));
+    log.extend(data(0, &[&1_000_000u64.to_le_bytes(), &[0; 16]]));
+    let error = arusti::import::read_ulog(&log).unwrap_err();
+    assert!(error.to_string().contains("contains itself"), "{}", error);
+    }
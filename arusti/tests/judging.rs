@@ -0,0 +1,121 @@
+use arusti::{DataPoint,ErrorModel,PilotError,SensorNoise};
+use arusti::olan::parse_sequence;
+use arusti::judging::{judge_flight,Fault};
+use arusti::segmentation::{kinematics,SegmentationOptions};
+use arusti::{FigureMark,JudgingOptions};
+
+mod common;
+
+const SEQUENCE: &str = "o 2j 24- -o- -2j- 2- o 2j1";
+
+/// Fly the sequence with the given errors, returning the marks and the downgrade earned by each figure's errors
+fn judge(olan: &str, error_model: ErrorModel, noise: &mut SensorNoise) -> (Vec<FigureMark>, Vec<f32>) {
+    let sequence = parse_sequence(olan.to_string());
+    let mut generator = common::generator();
+    generator.set_error_model(error_model);
+    let points: Vec<DataPoint> = generator.generate_points(&sequence).into_iter()
+        .map(|mut point| { noise.apply(&mut point); point })
+        .collect();
+
+    let mut injected = vec![0.0; sequence.figures.len()];
+    for error in generator.take_injected_errors() {
+        injected[error.figure] += error.downgrade;
+        }
+    (judge_flight(&points, &sequence, &JudgingOptions::default()), injected)
+    }
+
+#[test]
+fn perfect_flight() {
+    let (marks, _) = judge(SEQUENCE, ErrorModel::new(), &mut SensorNoise::new(0, 0.0, 0.0));
+    assert_eq!(marks.len(), 8);
+    for mark in &marks {
+        assert_eq!(mark.mark, 10.0, "{:?}", mark.downgrades);
+        assert!(mark.flown.is_some());
+        }
+    }
+
+#[test]
+fn noisy_flight() {
+    let (marks, _) = judge(SEQUENCE, ErrorModel::new(), &mut SensorNoise::new(3, 0.5, 0.5));
+    for mark in &marks {
+        assert!(mark.mark >= 9.0, "{:?}", mark.downgrades);
+        }
+    }
+
+#[test]
+fn injected_errors() {
+    // Flown one at a time, as the generator does not correct errors in later figures
+    let cases = [
+        ("o", 1, PilotError::UnevenRadius(1.2)),
+        ("44", 1, PilotError::HesitationShort(10.0)),
+        ("v", 2, PilotError::OffPitch(5.0)),
+        ("44", 1, PilotError::MissingPoints(1)),
+        ("2-", 1, PilotError::RollRotation(10.0)),
+        ];
+    for (olan, element, error) in cases.iter() {
+        let mut error_model = ErrorModel::new();
+        error_model.push(0, *element, *error);
+        let (marks, injected) = judge(olan, error_model, &mut SensorNoise::new(0, 0.0, 0.0));
+        let downgrade = 10.0 - marks[0].mark;
+        assert!((downgrade - injected[0]).abs() <= 0.5, "{}: {} != {} {:?}", olan, downgrade, injected[0], marks[0].downgrades);
+        }
+    }
+
+#[test]
+fn two_point_roll() {
+    // OLAN gives hesitation points per 360°, so a 2x4 is two quarter rolls with one pause
+    let points = common::generator().generate_points(&parse_sequence("24-".to_string()));
+    let options = SegmentationOptions { rate_window: 0.1, .. SegmentationOptions::default() };
+    let rolling: Vec<bool> = kinematics(&points, &options).iter()
+        .map(|state| state.body_rates[0].abs() > options.roll_threshold)
+        .collect();
+    let rolls = rolling.windows(2).filter(|pair| !pair[0] && pair[1]).count();
+    assert_eq!(rolls, 2);
+
+    let (marks, _) = judge("24-", ErrorModel::new(), &mut SensorNoise::new(0, 0.0, 0.0));
+    assert_eq!(marks[0].mark, 10.0, "{:?}", marks[0].downgrades);
+
+    // Flown as a slow roll
+    let mut error_model = ErrorModel::new();
+    error_model.push(0, 1, PilotError::MissingPoints(1));
+    let (marks, _) = judge("24-", error_model, &mut SensorNoise::new(0, 0.0, 0.0));
+    assert!(marks[0].downgrades.iter().any(|downgrade| downgrade.fault == Fault::MissingPoints(1)), "{:?}", marks[0].downgrades);
+
+    // Held 10° short of the quarter
+    let mut error_model = ErrorModel::new();
+    error_model.push(0, 1, PilotError::HesitationShort(10.0));
+    let (marks, injected) = judge("24-", error_model, &mut SensorNoise::new(0, 0.0, 0.0));
+    assert!((10.0 - marks[0].mark - injected[0]).abs() <= 0.5, "{} {:?}", injected[0], marks[0].downgrades);
+    }
+
+#[test]
+fn wrong_figures() {
+    // Flown as o 2j, filed with a half loop in place of the loop and an extra figure
+    let sequence = parse_sequence("m- 2j o".to_string());
+    let mut generator = common::generator();
+    let points = generator.generate_points(&parse_sequence("o 2j".to_string()));
+    let marks = judge_flight(&points, &sequence, &JudgingOptions::default());
+
+    assert_eq!(marks.len(), 3);
+    assert_eq!(marks[0].mark, 0.0);
+    assert_eq!(marks[1].mark, 10.0);
+    assert_eq!(marks[2].mark, 0.0);
+    assert_eq!(marks[2].downgrades[0].fault, Fault::FigureNotFlown);
+    assert!(marks[2].flown.is_none());
+    }
+
+#[test]
+fn skipped_figure() {
+    // The turn is left out, so the second loop is judged against the last figure, not the turn
+    let sequence = parse_sequence("o 2j o".to_string());
+    let mut generator = common::generator();
+    let points = generator.generate_points(&parse_sequence("o o".to_string()));
+    let marks = judge_flight(&points, &sequence, &JudgingOptions::default());
+
+    assert_eq!(marks.len(), 3);
+    assert_eq!(marks[0].mark, 10.0, "{:?}", marks[0].downgrades);
+    assert_eq!(marks[1].downgrades[0].fault, Fault::FigureNotFlown);
+    assert!(marks[1].downgrades[0].element.is_none());
+    assert!(marks[1].flown.is_none());
+    assert_eq!(marks[2].mark, 10.0, "{:?}", marks[2].downgrades);
+    }
@@ -1,8 +1,8 @@
 use arusti;
 
-use arusti::{ElementType,Element};
+use arusti::{ElementType,FlatElement};
 
-fn compare_elements(result: &Vec<Element>, expectation: &Vec<Element>) {
+fn compare_elements(result: &Vec<FlatElement>, expectation: &Vec<FlatElement>) {
     assert_eq!(result.len(), expectation.len(), "Figure has wrong number of elements");
     for (index,(result,expected)) in result.iter().zip( expectation.iter() ).enumerate() {
         assert_eq!(result, expected, "Element {} does not match expectation", index);
@@ -15,13 +15,13 @@ fn loop_plain() {
     let sequence = arusti::olan::parse_sequence(sequence_str);
 
     let expected_elements = vec![
-        Element::line(0.0),
-        Element::radius(180.0),
-        Element::radius(180.0),
-        Element::line(0.0),
+        FlatElement::line(0.0),
+        FlatElement::radius(180.0),
+        FlatElement::radius(180.0),
+        FlatElement::line(0.0),
         ];
     
-    compare_elements(&sequence.figures[0].elements, &expected_elements);
+    compare_elements(&sequence.figures[0].to_flat(), &expected_elements);
     }
 
 #[test]
@@ -30,14 +30,14 @@ fn loop_with_leading_roll() {
     let sequence = arusti::olan::parse_sequence(sequence_str);
 
     let expected_elements = vec![
-        Element::line(0.0),
-        Element { angle: 360.0, argument: 1.0, .. Element::new(ElementType::Roll) },
-        Element::radius(180.0),
-        Element::radius(180.0),
-        Element::line(0.0),
+        FlatElement::line(0.0),
+        FlatElement { angle: 360.0, argument: 1.0, .. FlatElement::new(ElementType::Roll) },
+        FlatElement::radius(180.0),
+        FlatElement::radius(180.0),
+        FlatElement::line(0.0),
         ];
     
-    compare_elements(&sequence.figures[0].elements, &expected_elements);
+    compare_elements(&sequence.figures[0].to_flat(), &expected_elements);
     }
 
 #[test]
@@ -46,14 +46,14 @@ fn loop_with_combining_roll() {
     let sequence = arusti::olan::parse_sequence(sequence_str);
 
     let expected_elements = vec![
-        Element::line(0.0),
-        Element::radius(180.0),
-        Element { angle: 360.0, argument: 1.0, .. Element::new(ElementType::Roll) },
-        Element::radius(180.0),
-        Element::line(0.0),
+        FlatElement::line(0.0),
+        FlatElement::radius(180.0),
+        FlatElement { angle: 360.0, argument: 1.0, .. FlatElement::new(ElementType::Roll) },
+        FlatElement::radius(180.0),
+        FlatElement::line(0.0),
         ];
     
-    compare_elements(&sequence.figures[0].elements, &expected_elements);
+    compare_elements(&sequence.figures[0].to_flat(), &expected_elements);
     }
 
 #[test]
@@ -62,15 +62,15 @@ fn loop_with_all_rolls() {
     let sequence = arusti::olan::parse_sequence(sequence_str);
 
     let expected_elements = vec![
-        Element::line(0.0),
-        Element { angle: 360.0, argument: 1.0, .. Element::new(ElementType::Roll) },
-        Element::radius(180.0),
-        Element { angle: 360.0, argument: 1.0, .. Element::new(ElementType::Roll) },
-        Element::radius(180.0),
-        Element::line(0.0),
+        FlatElement::line(0.0),
+        FlatElement { angle: 360.0, argument: 1.0, .. FlatElement::new(ElementType::Roll) },
+        FlatElement::radius(180.0),
+        FlatElement { angle: 360.0, argument: 1.0, .. FlatElement::new(ElementType::Roll) },
+        FlatElement::radius(180.0),
+        FlatElement::line(0.0),
         ];
     
-    compare_elements(&sequence.figures[0].elements, &expected_elements);
+    compare_elements(&sequence.figures[0].to_flat(), &expected_elements);
     }
 
 #[test]
@@ -79,13 +79,13 @@ fn loop_with_inverted_flight() {
     let sequence = arusti::olan::parse_sequence(sequence_str);
 
     let expected_elements = vec![
-        Element::invline(0.0),
-        Element::radius(-180.0),
-        Element::radius(-180.0),
-        Element::invline(0.0),
+        FlatElement::invline(0.0),
+        FlatElement::radius(-180.0),
+        FlatElement::radius(-180.0),
+        FlatElement::invline(0.0),
         ];
     
-    compare_elements(&sequence.figures[0].elements, &expected_elements);
+    compare_elements(&sequence.figures[0].to_flat(), &expected_elements);
     }
 
 #[test]
@@ -94,14 +94,14 @@ fn loop_with_inverted_entry_and_inverting_leading_roll() {
     let sequence = arusti::olan::parse_sequence(sequence_str);
 
     let expected_elements = vec![
-        Element::invline(0.0),
-        Element { angle: 180.0, argument: 1.0, .. Element::new(ElementType::Roll) },
-        Element::radius(180.0),
-        Element::radius(180.0),
-        Element::line(0.0),
+        FlatElement::invline(0.0),
+        FlatElement { angle: 180.0, argument: 1.0, .. FlatElement::new(ElementType::Roll) },
+        FlatElement::radius(180.0),
+        FlatElement::radius(180.0),
+        FlatElement::line(0.0),
         ];
     
-    compare_elements(&sequence.figures[0].elements, &expected_elements);
+    compare_elements(&sequence.figures[0].to_flat(), &expected_elements);
     }
 
 #[test]
@@ -110,14 +110,14 @@ fn loop_with_inverted_entry_and_inverting_combining_roll() {
     let sequence = arusti::olan::parse_sequence(sequence_str);
 
     let expected_elements = vec![
-        Element::invline(0.0),
-        Element::radius(-180.0),
-        Element { angle: 180.0, argument: 1.0, .. Element::new(ElementType::Roll) },
-        Element::radius(180.0),
-        Element::line(0.0),
+        FlatElement::invline(0.0),
+        FlatElement::radius(-180.0),
+        FlatElement { angle: 180.0, argument: 1.0, .. FlatElement::new(ElementType::Roll) },
+        FlatElement::radius(180.0),
+        FlatElement::line(0.0),
         ];
     
-    compare_elements(&sequence.figures[0].elements, &expected_elements);
+    compare_elements(&sequence.figures[0].to_flat(), &expected_elements);
     }
 
 #[test]
@@ -126,14 +126,93 @@ fn hammerhead_turn() {
     let sequence = arusti::olan::parse_sequence(sequence_str);
 
     let expected_elements = vec![
-        Element::line(0.0),    
-        Element::radius(90.0),
-        Element::line(90.0),
-        Element { angle: 180.0, argument: 0.0, .. Element::new(ElementType::Stall) },
-        Element::line(-90.0),
-        Element::radius(90.0),
-        Element::line(0.0),
+        FlatElement::line(0.0),    
+        FlatElement::radius(90.0),
+        FlatElement::line(90.0),
+        FlatElement { angle: 180.0, argument: 0.0, .. FlatElement::new(ElementType::Stall) },
+        FlatElement::line(-90.0),
+        FlatElement::radius(90.0),
+        FlatElement::line(0.0),
         ];
     
-    compare_elements(&sequence.figures[0].elements, &expected_elements);
+    compare_elements(&sequence.figures[0].to_flat(), &expected_elements);
+    }
+
+#[test]
+fn invalid_sequence_is_error() {
+    assert!(arusti::olan::try_parse_sequence("o".to_string()).is_ok());
+    assert!(arusti::olan::try_parse_sequence("#o".to_string()).is_err());
+    }
+
+#[test]
+fn trailing_input_is_error() {
+    // The grammar stops at xyz, which must not be dropped silently
+    let error = arusti::olan::try_parse_sequence("o xyz 1".to_string()).unwrap_err();
+    assert!(error.contains("position 2"), "Unexpected error {}", error);
+    assert!(arusti::olan::try_parse_sequence("o 1 ".to_string()).is_ok());
+    }
+
+#[test]
+fn inverted_flight_continuity() {
+    let sequence = arusti::olan::parse_sequence("o 2- -o- -2o".to_string());
+    assert!(sequence.continuity_breaks().is_empty());
+
+    let sequence = arusti::olan::parse_sequence("o -o- o".to_string());
+    assert_eq!(sequence.continuity_breaks(), vec![1, 2]);
+    }
+
+#[test]
+fn mismatched_exit_without_radius() {
+    // No radius to invert, so the exit is left as written
+    let sequence = arusti::olan::parse_sequence("2".to_string());
+    let expected_elements = vec![
+        FlatElement::line(0.0),
+        FlatElement { angle: 180.0, argument: 1.0, .. FlatElement::new(ElementType::Roll) },
+        FlatElement::line(0.0),
+        ];
+    compare_elements(&sequence.figures[0].to_flat(), &expected_elements);
+    }
+
+#[test]
+fn layout_commands() {
+    use arusti::LayoutCommand;
+
+    let sequence = arusti::olan::parse_sequence("eja o 2> [0,20] 1 (3,-4) //d /".to_string());
+    assert_eq!(sequence.figures.len(), 3);
+    assert_eq!(sequence.figures[0].layout, vec![LayoutCommand::EntryAwayFromJudges]);
+    assert_eq!(sequence.figures[1].layout, vec![LayoutCommand::MoveForward(2), LayoutCommand::MoveBy(0, 20)]);
+    assert_eq!(sequence.figures[2].layout, vec![LayoutCommand::CurveTo(3, -4), LayoutCommand::SwitchXAxis]);
+
+    let sequence = arusti::olan::parse_sequence("> ej o".to_string());
+    assert_eq!(sequence.figures[0].layout, vec![LayoutCommand::MoveForward(1), LayoutCommand::EntryTowardsJudges]);
+    assert!(arusti::olan::try_parse_sequence("99999999999> o".to_string()).is_err());
+    }
+
+#[test]
+fn eighth_rolls_on_45_lines() {
+    // An eighth roll stays nearer upright, three eighths leaves the line inverted
+    for (olan, inverted) in [("d18", false), ("d38", true)] {
+        let sequence = arusti::olan::parse_sequence(olan.to_string());
+        let angle = if inverted { 135.0 } else { 45.0 };
+        let expected_elements = vec![
+            FlatElement::line(0.0),
+            FlatElement::radius(45.0),
+            FlatElement::line(45.0),
+            FlatElement { angle, argument: 8.0, .. FlatElement::new(ElementType::Roll) },
+            FlatElement { inverted, .. FlatElement::line(45.0) },
+            FlatElement::radius(-45.0),
+            FlatElement::line(0.0),
+            ];
+        compare_elements(&sequence.figures[0].to_flat(), &expected_elements);
+        }
+    }
+
+#[test]
+fn hesitation_roll_angles() {
+    let roll_angles = |olan: &str| arusti::olan::parse_sequence(olan.to_string()).figures[0].to_flat().iter()
+        .filter(|element| element.elem_type == ElementType::Roll)
+        .map(|element| (element.angle, element.argument))
+        .collect::<Vec<(f32, f32)>>();
+    assert_eq!(roll_angles("4;2;3;9"), vec![(90.0, 1.0), (180.0, 1.0), (270.0, 1.0), (720.0, 1.0)]);
+    assert_eq!(roll_angles("12;32;44;58"), vec![(180.0, 2.0), (540.0, 2.0), (360.0, 4.0), (225.0, 8.0)]);
     }
@@ -0,0 +1,81 @@
+use arusti::{PilotError,ErrorModel};
+
+mod common;
+
+#[test]
+fn downgrades() {
+    assert_eq!(PilotError::RollRotation(2.0).downgrade(), 0.0);
+    assert_eq!(PilotError::RollRotation(-10.0).downgrade(), 2.0);
+    assert_eq!(PilotError::OffHeading(7.5).downgrade(), 1.5);
+    assert_eq!(PilotError::OffPitch(90.0).downgrade(), 10.0);
+    assert_eq!(PilotError::UnevenRadius(1.2).downgrade(), 2.0);
+    assert_eq!(PilotError::MissingPoints(1).downgrade(), 10.0);
+    }
+
+#[test]
+fn over_rotated_roll() {
+    let sequence = arusti::olan::parse_sequence("2-".to_string());
+
+    let mut generator = common::generator();
+    let perfect_points = generator.generate_points(&sequence);
+
+    let mut error_model = ErrorModel::new();
+    error_model.push(0, 1, PilotError::RollRotation(10.0));
+
+    let mut generator = common::generator();
+    generator.set_error_model(error_model);
+    let points = generator.generate_points(&sequence);
+    let injected = generator.take_injected_errors();
+
+    assert_eq!(injected.len(), 1);
+    assert_eq!(injected[0].figure, 0);
+    assert_eq!(injected[0].element, 1);
+    assert_eq!(injected[0].downgrade, 2.0);
+    assert!(injected[0].start_index < injected[0].end_index);
+    assert!(injected[0].end_index <= points.len());
+
+    // Exit attitude should be banked by the over-rotation
+    let exit_attitude = points.last().unwrap().attitude;
+    let perfect_exit_attitude = perfect_points.last().unwrap().attitude;
+    let bank = exit_attitude.angle_to(&perfect_exit_attitude).to_degrees();
+    assert!((bank - 10.0).abs() < 0.1, "Exit bank {} does not match error", bank);
+    }
+
+#[test]
+fn missing_hesitation_point() {
+    let sequence = arusti::olan::parse_sequence("44".to_string());
+
+    let mut generator = common::generator();
+    let perfect_len = generator.generate_points(&sequence).len();
+    assert!(generator.take_injected_errors().is_empty());
+
+    let mut error_model = ErrorModel::new();
+    error_model.push(0, 1, PilotError::MissingPoints(1));
+    generator.set_error_model(error_model);
+    let points = generator.generate_points(&sequence);
+    let injected = generator.take_injected_errors();
+
+    assert_eq!(injected.len(), 1);
+    assert_eq!(injected[0].downgrade, 10.0);
+    // One fewer hesitation flown
+    assert!(points.len() < perfect_len);
+    }
+
+#[test]
+fn errors_recorded_where_flown() {
+    let sequence = arusti::olan::parse_sequence("o 1".to_string());
+    let mut error_model = ErrorModel::new();
+    // A line error on a radius and missing points of a continuous roll are not flown
+    error_model.push(0, 1, PilotError::OffHeading(10.0));
+    error_model.push(1, 1, PilotError::MissingPoints(1));
+    error_model.push(0, 1, PilotError::UnevenRadius(1.2));
+    error_model.push(1, 0, PilotError::OffPitch(5.0));
+
+    let mut generator = common::generator();
+    generator.set_error_model(error_model);
+    generator.generate_points(&sequence);
+    let injected = generator.take_injected_errors();
+
+    let errors: Vec<PilotError> = injected.iter().map(|injected| injected.error).collect();
+    assert_eq!(errors, vec![PilotError::UnevenRadius(1.2), PilotError::OffPitch(5.0)]);
+    }
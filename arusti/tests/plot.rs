@@ -0,0 +1,98 @@
+use std::path::Path;
+
+use arusti::{BoxFrame,Category,DataPoint,DataPointGenerator,FigureSegment,PlotFormat,PlotOptions,Projection,SegmentationOptions,Vector3};
+use arusti::box_frame::BoxDirection;
+use arusti::olan::parse_sequence;
+use arusti::plot::{element_colour,plot,write_svg,Mark};
+use arusti::segmentation::segment_figures;
+use arusti::ElementType;
+
+mod common;
+
+fn generate(olan: &str, box_frame: &BoxFrame) -> (Vec<DataPoint>, Vec<FigureSegment>) {
+    let entry = Vector3::new(-200.0, 0.0, 600.0);
+    let mut generator = DataPointGenerator::builder(common::performance())
+        .velocity(Vector3::x() * 18.0)
+        .box_entry(box_frame, entry, BoxDirection::TowardsB)
+        .build();
+    let points = generator.generate_points(&parse_sequence(olan.to_string()));
+    let figures = segment_figures(&points, &SegmentationOptions::default());
+    (points, figures)
+    }
+
+fn texts(marks: &[Mark]) -> Vec<&str> {
+    marks.iter()
+        .filter_map(|mark| match mark { Mark::Text { text, .. } => Some(text.as_str()), _ => None })
+        .collect()
+    }
+
+#[test]
+fn plot_contents() {
+    let box_frame = BoxFrame::new(Category::Intermediate);
+    let (points, figures) = generate("o 2j", &box_frame);
+    assert_eq!(figures.len(), 2);
+    let options = PlotOptions { box_frame, .. PlotOptions::default() };
+    let marks = plot(&points, &figures, &options);
+
+    let labels = texts(&marks);
+    for title in ["Side", "Top", "Judge"] {
+        assert!(labels.contains(&title), "{:?}", labels);
+        }
+    assert_eq!(labels.iter().filter(|text| **text == "2").count(), 3);
+    let dots = marks.iter().filter(|mark| matches!(mark, Mark::Dot { .. })).count();
+    assert_eq!(dots, 2 * figures.len() * 3);
+
+    // The loop and turn are coloured by element and everything is drawn inside the image
+    let colours: Vec<[u8; 3]> = marks.iter().filter_map(|mark| match mark { Mark::Line { colour, .. } => Some(*colour), _ => None }).collect();
+    assert!(colours.contains(&element_colour(ElementType::Radius)));
+    assert!(colours.contains(&element_colour(ElementType::Turn)));
+    let (width, height) = options.image_size();
+    assert_eq!((width, height), (800, 1200));
+    for mark in &marks {
+        if let Mark::Line { points, .. } = mark {
+            assert!(points.iter().all(|point| point[0] >= 0.0 && point[0] <= width as f32 && point[1] >= 0.0 && point[1] <= height as f32), "{:?}", mark);
+            }
+        }
+
+    // Twelve box edges in each view, and none without the box
+    let lines = |options: &PlotOptions| plot(&points, &figures, options).iter().filter(|mark| matches!(mark, Mark::Line { .. })).count();
+    let without_box = PlotOptions { show_box: false, .. options.clone() };
+    assert_eq!(lines(&options) - lines(&without_box), 12 * 3);
+    let single_view = PlotOptions { projections: vec![Projection::Top], wingtip_interval: 0.0, .. options.clone() };
+    assert_eq!(texts(&plot(&points, &figures, &single_view)).iter().filter(|text| **text == "Side").count(), 0);
+    }
+
+#[test]
+fn plot_formats() {
+    assert_eq!(PlotFormat::from_path(Path::new("flight.SVG")), Some(PlotFormat::Svg));
+    assert_eq!(PlotFormat::from_path(Path::new("flight.png")), Some(PlotFormat::Png));
+    assert_eq!(PlotFormat::from_path(Path::new("flight.csv")), None);
+    assert_eq!("judge".parse::<Projection>(), Ok(Projection::Judge));
+    assert!("front".parse::<Projection>().is_err());
+    }
+
+#[test]
+fn svg_output() {
+    let box_frame = BoxFrame::new(Category::Intermediate);
+    let (points, figures) = generate("o", &box_frame);
+    let mut output = Vec::new();
+    write_svg(&points, &figures, &PlotOptions { box_frame, .. PlotOptions::default() }, &mut output).unwrap();
+    let svg = String::from_utf8(output).unwrap();
+    assert!(svg.contains(r#"width="800" height="1200""#));
+    assert!(svg.contains("<polyline"));
+    assert_eq!(svg.matches("<circle").count(), 2 * figures.len() * 3);
+    assert!(svg.ends_with("</svg>\n"));
+    }
+
+#[cfg(feature = "png")]
+#[test]
+fn png_output() {
+    let box_frame = BoxFrame::new(Category::Intermediate);
+    let (points, figures) = generate("o", &box_frame);
+    let options = PlotOptions { box_frame, projections: vec![Projection::Side], .. PlotOptions::default() };
+    let mut output = Vec::new();
+    arusti::plot::write_png(&points, &figures, &options, &mut output).unwrap();
+    assert_eq!(&output[..8], b"\x89PNG\r\n\x1a\n");
+    // Width and height from the IHDR chunk
+    assert_eq!(&output[16..24], &[0, 0, 3, 32, 0, 0, 1, 144]);
+    }
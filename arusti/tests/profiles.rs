@@ -0,0 +1,81 @@
+use arusti::{AircraftProfile,DataPoint,Vector3};
+use arusti::olan::parse_sequence;
+use arusti::profiles::AircraftCategory;
+
+#[test]
+fn builtin_profiles() {
+    let names = AircraftProfile::builtin_names();
+    assert!(names.contains(&"extra330"));
+    for name in names {
+        assert!(AircraftProfile::builtin(name).is_some(), "{} failed to load", name);
+        }
+    assert!(AircraftProfile::builtin("concorde").is_none());
+
+    let glider = AircraftProfile::builtin("swift_s1").unwrap();
+    assert_eq!(glider.category, AircraftCategory::Glider);
+    }
+
+#[test]
+fn pitch_rate_limited_by_g() {
+    let mut profile = AircraftProfile::builtin("extra330").unwrap();
+    // 9g above 1g at 72 m/s
+    assert!((profile.pitch_rate() - 70.2).abs() < 0.1);
+
+    // 4g below -1g at 72 m/s
+    profile.g_limits.negative = -5.0;
+    assert!((profile.pitch_rate() - 31.2).abs() < 0.1);
+
+    profile.rates.pitch = 30.0;
+    assert_eq!(profile.pitch_rate(), 30.0);
+    assert_eq!(profile.performance_options().pitch_rate, 30.0);
+    profile.rates.pitch = 50.0;
+    profile.g_limits.negative = -10.0;
+    assert_eq!(profile.pitch_rate(), 50.0);
+    assert_eq!(profile.performance_options().pitch_rate, 50.0);
+    }
+
+#[test]
+fn json_matches_toml() {
+    let profile = AircraftProfile::builtin("pitts_s2b").unwrap();
+    let json = serde_json::to_string(&profile).unwrap();
+    assert_eq!(AircraftProfile::from_json(&json).unwrap(), profile);
+    }
+
+#[test]
+fn invalid_profiles() {
+    let source = arusti::profiles::BUILTIN_PROFILES[0].1;
+    assert!(AircraftProfile::from_toml(&source.replace("roll = 400.0", "roll = -400.0")).is_err());
+    assert!(AircraftProfile::from_toml(&source.replace("entry = 72.0", "entry = 200.0")).is_err());
+    assert!(AircraftProfile::from_toml(&source.replace("[snap]", "[snaps]")).is_err());
+    }
+
+#[test]
+fn speed_range() {
+    let profile = AircraftProfile::builtin("extra330").unwrap();
+    assert!(profile.check_speed(72.0).is_ok());
+    assert!(profile.check_speed(20.0).is_err());
+    assert!(profile.check_speed(150.0).is_err());
+    }
+
+#[test]
+fn snap_entry() {
+    let sequence = parse_sequence("2f".to_string());
+    let flight = |profile: &AircraftProfile| profile.generator_builder().build().generate_points(&sequence);
+    let duration = |points: &[DataPoint]| points[points.len() - 1].time - points[0].time;
+    // Furthest the nose gets from the horizon (degrees)
+    let nose_pitch = |points: &[DataPoint]| points.iter()
+        .map(|point| (-(point.attitude * Vector3::x())[2]).asin().to_degrees().abs())
+        .fold(0.0, f32::max);
+    let profile = AircraftProfile::builtin("extra330").unwrap();
+    let points = flight(&profile);
+
+    // Building up to the snap rate over longer takes longer
+    let mut slow_entry = profile.clone();
+    slow_entry.snap.entry_time = 0.5;
+    assert!(duration(&flight(&slow_entry)) > duration(&points) + 0.1);
+
+    // A deeper break pitches the nose further from the flight path
+    let mut deep_break = profile.clone();
+    deep_break.snap.entry_pitch = 30.0;
+    assert!(nose_pitch(&flight(&deep_break)) > nose_pitch(&points) + 10.0, "{} {}", nose_pitch(&flight(&deep_break)), nose_pitch(&points));
+    }
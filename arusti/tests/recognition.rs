@@ -0,0 +1,55 @@
+use arusti::{DataPoint,Figure,Sequence,SensorNoise};
+use arusti::olan::parse_sequence;
+use arusti::recognition::{best_guess,recognise_flight};
+use arusti::{Candidate,SegmentationOptions};
+
+mod common;
+
+const SEQUENCE: &str = "o 2j 24- -o- -2j- 2- o 2j1";
+// The half roll is entered inverted from the inverted turn
+const RECOGNISED: &str = "o 2j 24- -o- -2j- -2 o 2j1";
+
+fn generate(olan: &str, noise: &mut SensorNoise) -> Vec<DataPoint> {
+    let sequence = parse_sequence(olan.to_string());
+    let mut generator = common::generator();
+    let mut points = Vec::new();
+    for figure in &sequence.figures {
+        let single = Sequence { figures: vec![Figure { elements: figure.elements.clone(), layout: Vec::new() }] };
+        points.extend(generator.generate_points(&single).into_iter().map(|mut point| { noise.apply(&mut point); point }));
+        }
+    points
+    }
+
+fn recognise(olan: &str, noise: &mut SensorNoise) -> Vec<Vec<Candidate>> {
+    recognise_flight(&generate(olan, noise), &SegmentationOptions::default())
+    }
+
+#[test]
+fn single_figures() {
+    let figures = [
+        "o", "m-", "v", "iv", "d", "z", "p", "q", "y", "b", "k", "t",
+        "2b", "v2", "4b4", "2j", "2j1", "24-", "2-", "22", "1",
+        ];
+    for olan in figures.iter() {
+        let candidates = recognise(olan, &mut SensorNoise::new(0, 0.0, 0.0));
+        assert_eq!(candidates.len(), 1);
+        let best = &candidates[0][0];
+        assert_eq!(&best.olan, olan, "{:?}", &candidates[0][..3]);
+        assert!(best.likelihood > 0.5, "{:?}", best);
+
+        let total: f32 = candidates[0].iter().map(|candidate| candidate.likelihood).sum();
+        assert!((total - 1.0).abs() < 1e-3);
+        }
+    }
+
+#[test]
+fn generated_sequence() {
+    let figures = recognise(SEQUENCE, &mut SensorNoise::new(0, 0.0, 0.0));
+    assert_eq!(best_guess(&figures), RECOGNISED);
+    }
+
+#[test]
+fn noisy_sequence() {
+    let figures = recognise(SEQUENCE, &mut SensorNoise::new(3, 0.5, 0.5));
+    assert_eq!(best_guess(&figures), RECOGNISED);
+    }
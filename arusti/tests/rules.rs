@@ -0,0 +1,111 @@
+use arusti::{RuleSet,SeqFile,Sequence};
+use arusti::olan::parse_sequence;
+use arusti::rules::{RollType,ViolationKind};
+use arusti::seq_file::{CatalogueElement,CatalogueFigure};
+
+fn example() -> (SeqFile, Sequence) {
+    let seq_file = SeqFile::load(std::path::Path::new("tests/resources/Example.seq")).unwrap();
+    let sequence = parse_sequence(seq_file.sequence_text.clone());
+    (seq_file, sequence)
+    }
+
+fn figure(aresti: &[&str], k: f32) -> CatalogueFigure {
+    CatalogueFigure {
+        elements: aresti.iter().map(|aresti| CatalogueElement { aresti: aresti.to_string(), k: 0.0 }).collect(),
+        k,
+        }
+    }
+
+#[test]
+fn builtin_rule_sets() {
+    for name in RuleSet::builtin_names() {
+        let rules = RuleSet::builtin(name).unwrap();
+        assert!(!rules.programs.is_empty(), "{}", name);
+        }
+    let baea = RuleSet::builtin("BAeA").unwrap();
+    assert_eq!(baea.name, "BAeA");
+    assert!(baea.program("intermediate", "free known").is_some());
+    assert!(baea.program("Intermediate", "Free Unknown").is_none());
+    assert!(RuleSet::builtin("nonexistent").is_none());
+    }
+
+#[test]
+fn baea_example_is_legal() {
+    let (seq_file, sequence) = example();
+    let rules = RuleSet::builtin(seq_file.rules.as_deref().unwrap()).unwrap();
+    assert_eq!(rules.check_seq_file(&seq_file, &sequence).unwrap(), vec![]);
+    }
+
+#[test]
+fn baea_violations() {
+    let (seq_file, sequence) = example();
+    let baea = RuleSet::builtin("baea").unwrap();
+    let rules = baea.program("Intermediate", "Free Known").unwrap();
+
+    let mut catalogue = seq_file.figures.clone();
+    // Repeat the first figure as the second, fly a negative flick in the third and raise the K of the last
+    catalogue[1] = catalogue[0].clone();
+    catalogue[2].elements[1].aresti = "9.10.1.4".to_string();
+    catalogue[11].k = 40.0;
+
+    let violations = rules.check(&sequence, &catalogue);
+    let kinds: Vec<(Option<usize>, &ViolationKind)> = violations.iter().map(|violation| (violation.figure, &violation.kind)).collect();
+    assert_eq!(kinds, vec![
+        (None, &ViolationKind::TotalKTooHigh { k: 192.0, max: 190.0 }),
+        (Some(1), &ViolationKind::Repeated { aresti: "7.4.5.1".to_string(), max: 1 }),
+        (Some(2), &ViolationKind::FamilyNotAllowed("9.10.1.4".to_string())),
+        ]);
+    assert_eq!(violations[2].to_string(), "Figure 3: 9.10.1.4 is not allowed");
+
+    // Family 9.1 does not include 9.10 or 9.11
+    let sports = baea.program("Sports", "Free Known").unwrap();
+    let violations = sports.check(&parse_sequence("o".to_string()), &[figure(&["7.4.1.1", "9.10.1.4"], 10.0)]);
+    assert!(violations.iter().any(|violation| violation.kind == ViolationKind::FamilyNotAllowed("9.10.1.4".to_string())));
+    }
+
+#[test]
+fn civa_violations() {
+    let civa = RuleSet::builtin("CIVA").unwrap();
+
+    let (seq_file, sequence) = example();
+    let rules = civa.program("Advanced", "Free Known").unwrap();
+    let violations = rules.check(&sequence, &seq_file.figures);
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].kind, ViolationKind::TooManyFigures { count: 12, max: 10 });
+
+    // No hesitation or flick, four rolls in the first figure and the second spin of the sequence
+    let sequence = parse_sequence("1o1,1,1 2j 1o1 1iv".to_string());
+    let catalogue = vec![
+        figure(&["7.4.1.1", "9.1.3.4", "9.1.1.4", "9.1.1.4", "9.1.3.4"], 40.0),
+        figure(&["2.2.1.1"], 8.0),
+        figure(&["9.11.1.4", "7.4.2.1", "9.1.3.4"], 30.0),
+        figure(&["9.11.1.4", "1.1.1.1"], 60.0),
+        ];
+    let rules = civa.program("Advanced", "Free Unknown").unwrap();
+    let violations = rules.check(&sequence, &catalogue);
+    let kinds: Vec<(Option<usize>, &ViolationKind)> = violations.iter().map(|violation| (violation.figure, &violation.kind)).collect();
+    assert_eq!(kinds, vec![
+        (None, &ViolationKind::TooFewFigures { count: 4, min: 9 }),
+        (Some(0), &ViolationKind::TooManyRolls { count: 4, max: 3 }),
+        (None, &ViolationKind::MissingRollType(RollType::Hesitation)),
+        (None, &ViolationKind::MissingRollType(RollType::Flick)),
+        (Some(3), &ViolationKind::FigureKTooHigh { k: 60.0, max: 50.0 }),
+        (Some(3), &ViolationKind::FamilyLimit { family: "9.11".to_string(), max: 1 }),
+        ]);
+    }
+
+#[test]
+fn rule_set_from_toml() {
+    let rules = RuleSet::from_toml(r#"
+        name = "Club"
+
+        [[programs]]
+        category = "Primary"
+        program = "Known"
+        max_figures = 2
+        "#).unwrap();
+    let program = rules.program("Primary", "Known").unwrap();
+    assert_eq!(program.required_rolls, vec![]);
+    let violations = program.check(&parse_sequence("o 2j 1".to_string()), &[]);
+    assert_eq!(violations[0].to_string(), "3 figures, at most 2 allowed");
+    }
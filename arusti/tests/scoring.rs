@@ -0,0 +1,97 @@
+use arusti::{DataPoint,SeqFile,Vector3};
+use arusti::olan::parse_sequence;
+use arusti::scoring::{score_sequence,PresentationFault};
+use arusti::box_frame::BoxEventKind;
+use arusti::{BoxFrame,Coefficients,ScoringOptions};
+
+mod common;
+
+fn generate(olan: &str) -> Vec<DataPoint> {
+    let mut generator = common::generator();
+    generator.generate_points(&parse_sequence(olan.to_string()))
+    }
+
+/// Box centred on the flight, with the flight at the given height
+fn centred_options(points: &[DataPoint], height: f32) -> ScoringOptions {
+    let (min, max) = points.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), point| (min.min(point.position[0]), max.max(point.position[0])));
+    let across = points.iter().map(|point| point.position[1]).sum::<f32>() / points.len() as f32;
+    ScoringOptions {
+        box_frame: BoxFrame {
+            centre: Vector3::new((min + max) / 2.0, across, height),
+            .. BoxFrame::default()
+            },
+        .. ScoringOptions::default()
+        }
+    }
+
+#[test]
+fn seq_file() {
+    let contents = std::fs::read_to_string("tests/resources/Example.seq").unwrap();
+    let seq_file = SeqFile::parse(&contents).unwrap();
+    assert_eq!(seq_file.category.as_deref(), Some("Intermediate"));
+    assert_eq!(seq_file.figure_k(), vec![15.0, 10.0, 18.0, 5.0, 7.0, 15.0, 9.0, 11.0, 18.0, 22.0, 17.0, 26.0]);
+    assert_eq!(seq_file.positioning, 10.0);
+    assert_eq!(seq_file.harmony, 0.0);
+    assert_eq!(seq_file.figures[2].elements.len(), 2);
+    assert_eq!(seq_file.figures[2].elements[1].aresti, "9.11.1.4");
+    assert_eq!(seq_file.figures[2].elements[1].k, 5.0);
+
+    let coefficients = Coefficients::from(&seq_file);
+    assert_eq!(coefficients.figure_k(2), 18.0);
+    assert_eq!(coefficients.figure_k(12), 1.0);
+    assert!(SeqFile::parse("<sequence></sequence>").is_err());
+    }
+
+#[test]
+fn centred_flight() {
+    let olan = "o";
+    let points = generate(olan);
+    let options = centred_options(&points, 300.0);
+    let coefficients = Coefficients { figure_k: vec![10.0], positioning: 5.0, harmony: 1.0 };
+    let score = score_sequence(&points, &parse_sequence(olan.to_string()), &coefficients, &options);
+    assert!(score.events.is_empty(), "{:?}", score.events);
+    assert_eq!(score.positioning.mark, 10.0, "{:?}", score.positioning.deductions);
+    assert_eq!(score.harmony.mark, 10.0, "{:?}", score.harmony.deductions);
+    assert_eq!(score.penalties, 0.0);
+    assert_eq!(score.total, 160.0);
+    assert_eq!(score.maximum, 160.0);
+    }
+
+#[test]
+fn off_centre_flight() {
+    let olan = "o";
+    let points = generate(olan);
+    let mut options = centred_options(&points, 300.0);
+    options.box_frame.centre += Vector3::new(110.0, 0.0, 0.0);
+    let score = score_sequence(&points, &parse_sequence(olan.to_string()), &Coefficients::default(), &options);
+    assert_eq!(score.positioning.deductions.len(), 2, "{:?}", score.positioning.deductions);
+    for (fault, deduction) in &score.positioning.deductions {
+        match fault {
+            PresentationFault::OffCentre(distance) | PresentationFault::Unbalanced(distance) => assert!((distance - 110.0).abs() < 1.0, "{}", fault),
+            _ => panic!("unexpected {}", fault),
+            }
+        assert_eq!(*deduction, 2.0);
+        }
+    assert_eq!(score.positioning.mark, 6.0);
+    }
+
+#[test]
+fn box_penalties() {
+    let olan = "o 2j o";
+    let points = generate(olan);
+
+    let low = centred_options(&points, 50.0);
+    let events = low.box_frame.box_events(&points);
+    assert_eq!(events.len(), 1, "{:?}", events);
+    assert_eq!(events[0].kind(), BoxEventKind::LowAltitude);
+    let score = score_sequence(&points, &parse_sequence(olan.to_string()), &Coefficients::default(), &low);
+    assert_eq!(score.penalties, low.low_altitude_penalty);
+    assert_eq!(score.total, score.figures.iter().map(|mark| mark.mark).sum::<f32>() - score.penalties);
+
+    let mut out = centred_options(&points, 300.0);
+    out.box_frame.centre += Vector3::new(0.0, 600.0, 0.0);
+    let score = score_sequence(&points, &parse_sequence(olan.to_string()), &Coefficients::default(), &out);
+    assert!(!score.events.is_empty());
+    assert!(score.events.iter().all(|event| event.kind() == BoxEventKind::BoxOut), "{:?}", score.events);
+    assert_eq!(score.penalties, out.box_out_penalty * score.events.len() as f32);
+    }
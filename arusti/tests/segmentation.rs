@@ -0,0 +1,95 @@
+use arusti::{DataPoint,ElementType,Figure,Sequence,SensorNoise};
+use arusti::olan::parse_sequence;
+use arusti::segmentation::{segment_elements,segment_figures,SegmentationOptions};
+
+mod common;
+
+const SEQUENCE: &str = "o 2j 24- -o- -2j- 2- o 2j1";
+
+/// Generate a flight one figure at a time, returning the points and the index at which each figure ends
+fn generate(olan: &str, noise: &mut SensorNoise) -> (Vec<DataPoint>, Vec<usize>) {
+    let sequence = parse_sequence(olan.to_string());
+    let mut generator = common::generator();
+    let mut points = Vec::new();
+    let mut figure_ends = Vec::new();
+    for figure in &sequence.figures {
+        let single = Sequence { figures: vec![Figure { elements: figure.elements.clone(), layout: Vec::new() }] };
+        points.extend(generator.generate_points(&single).into_iter().map(|mut point| { noise.apply(&mut point); point }));
+        figure_ends.push(points.len());
+        }
+    (points, figure_ends)
+    }
+
+fn check_segmentation(noise: &mut SensorNoise) {
+    let (points, figure_ends) = generate(SEQUENCE, noise);
+    let figures = segment_figures(&points, &SegmentationOptions::default());
+    assert_eq!(figures.len(), figure_ends.len());
+
+    // Figures end in the middle of the lines between them, within 0.1 s
+    for (figure, figure_end) in figures.iter().zip(&figure_ends) {
+        assert!((figure.end as i64 - *figure_end as i64).abs() <= 5, "{} != {} {:?}", figure.end, figure_end, figures);
+        }
+
+    use ElementType::*;
+    let expected = [
+        vec![Line, Radius, Line],
+        vec![Line, Turn, Line],
+        vec![Line, Roll, Line],
+        vec![Line, Radius, Line],
+        vec![Line, Turn, Line],
+        vec![Line, Roll, Line],
+        vec![Line, Radius, Line],
+        vec![Line, Turn, Line],
+        ];
+    for (figure, expected_types) in figures.iter().zip(&expected) {
+        let types: Vec<ElementType> = figure.elements.iter().map(|element| element.elem_type).collect();
+        assert_eq!(&types, expected_types, "{:?}", figure);
+        assert_eq!(figure.elements.first().unwrap().start, figure.start);
+        assert_eq!(figure.elements.last().unwrap().end, figure.end);
+        }
+
+    // Inverted between the half rolls
+    let inverted: Vec<bool> = figures.iter().map(|figure| figure.elements[0].inverted).collect();
+    assert_eq!(inverted, vec![false, false, false, true, true, true, false, false]);
+    }
+
+#[test]
+fn generated_flight() {
+    check_segmentation(&mut SensorNoise::new(0, 0.0, 0.0));
+    }
+
+#[test]
+fn noisy_flight() {
+    check_segmentation(&mut SensorNoise::new(3, 0.5, 0.5));
+    }
+
+#[test]
+fn loop_duration() {
+    let (points, _) = generate("o", &mut SensorNoise::new(0, 0.0, 0.0));
+    let elements = segment_elements(&points, &SegmentationOptions::default());
+    assert_eq!(elements.len(), 3);
+
+    // 360° at 45 deg/s
+    let radius_time = points[elements[1].end].time - points[elements[1].start].time;
+    assert!((radius_time - 8.0).abs() < 0.05, "{}", radius_time);
+    }
+
+#[test]
+fn duplicate_timestamps() {
+    // A logger that stops its clock gives samples with no time between them to take rates over
+    let (mut points, _) = generate("o", &mut SensorNoise::new(0, 0.0, 0.0));
+    let boundary = segment_elements(&points, &SegmentationOptions::default())[1].start;
+    let stopped = points[boundary - 20].time;
+    for point in &mut points[boundary - 20..boundary + 20] {
+        point.time = stopped;
+        }
+    let elements = segment_elements(&points, &SegmentationOptions::default());
+    assert!(!elements.is_empty());
+
+    // With every sample at the same time there is nothing to segment
+    for point in &mut points {
+        point.time = stopped;
+        }
+    assert!(segment_elements(&points, &SegmentationOptions::default()).is_empty());
+    assert!(segment_figures(&points, &SegmentationOptions::default()).is_empty());
+    }
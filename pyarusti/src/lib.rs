@@ -5,7 +5,7 @@ use pyo3::types::{PyDict,PyList};
 
 use arusti;
 
-struct ElementWrapper(arusti::Element);
+struct ElementWrapper(arusti::FlatElement);
 
 impl IntoPy<PyObject> for ElementWrapper {
     fn into_py(self, py: Python) -> PyObject {
@@ -36,7 +36,7 @@ impl IntoPy<PyObject> for FigureWrapper {
     fn into_py(self, py: Python) -> PyObject {
         let pyelements = PyList::empty(py);
 
-        for element in self.0.elements {
+        for element in self.0.to_flat() {
             pyelements.append(ElementWrapper(element).into_py(py));
             }
         